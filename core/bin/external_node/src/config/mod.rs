@@ -3,7 +3,7 @@ use std::{env, time::Duration};
 use anyhow::Context;
 use serde::Deserialize;
 use url::Url;
-use zksync_basic_types::{Address, L1ChainId, L2ChainId};
+use zksync_basic_types::{Address, L1ChainId, L2ChainId, H256};
 use zksync_config::ObjectStoreConfig;
 use zksync_consensus_roles::node;
 use zksync_core::{
@@ -165,6 +165,9 @@ pub struct OptionalENConfig {
     /// different node.
     #[serde(default)]
     pub filters_disabled: bool,
+    /// Identifier of the chain fork served by the node, returned by `zks_getForkId`. If not set,
+    /// the method returns `null`.
+    pub fork_id: Option<H256>,
 
     // Health checks
     /// Time limit in milliseconds to mark a health check as slow and log the corresponding warning.
@@ -610,6 +613,7 @@ impl From<ExternalNodeConfig> for InternalApiConfig {
             req_entities_limit: config.optional.req_entities_limit,
             fee_history_limit: config.optional.fee_history_limit,
             filters_disabled: config.optional.filters_disabled,
+            fork_id: config.optional.fork_id,
         }
     }
 }
@@ -569,7 +569,7 @@ async fn main() -> anyhow::Result<()> {
     .context("init_tasks")?;
 
     let reorg_detector = ReorgDetector::new(main_node_client, connection_pool.clone());
-    app_health.insert_component(reorg_detector.health_check().clone());
+    app_health.insert_component(reorg_detector.health_check());
     let mut reorg_detector_handle = tokio::spawn(reorg_detector.run(stop_receiver)).fuse();
     let mut reorg_detector_result = None;
 
@@ -96,6 +96,9 @@ pub struct Web3JsonRpcConfig {
     pub websocket_requests_per_minute_limit: Option<NonZeroU32>,
     /// Tree API url, currently used to proxy `getProof` calls to the tree
     pub tree_api_url: Option<String>,
+    /// Identifier of the chain fork served by the node, returned by `zks_getForkId`. If not set,
+    /// the method returns `null`.
+    pub fork_id: Option<H256>,
 }
 
 impl Web3JsonRpcConfig {
@@ -131,6 +134,7 @@ impl Web3JsonRpcConfig {
             max_response_body_size_mb: Default::default(),
             websocket_requests_per_minute_limit: Default::default(),
             tree_api_url: None,
+            fork_id: None,
         }
     }
 
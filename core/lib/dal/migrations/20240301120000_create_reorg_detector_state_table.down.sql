@@ -0,0 +1 @@
+DROP TABLE IF EXISTS reorg_detector_state;
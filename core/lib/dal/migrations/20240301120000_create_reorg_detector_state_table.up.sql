@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS reorg_detector_state (
+    -- artificial primary key ensuring that the table contains at most 1 row.
+    fake_key BOOLEAN PRIMARY KEY,
+    -- first L1 batch that has diverged from the main node according to the last detected reorg;
+    -- reset once the reorg is rolled back.
+    diverged_l1_batch_number BIGINT,
+    updated_at TIMESTAMP NOT NULL,
+    CHECK (fake_key)
+);
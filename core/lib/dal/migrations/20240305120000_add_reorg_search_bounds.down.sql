@@ -0,0 +1,3 @@
+ALTER TABLE reorg_detector_state
+    DROP COLUMN IF EXISTS search_known_valid_l1_batch_number,
+    DROP COLUMN IF EXISTS search_known_diverged_l1_batch_number;
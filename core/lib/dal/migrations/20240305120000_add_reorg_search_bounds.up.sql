@@ -0,0 +1,4 @@
+-- bounds of the last interrupted reorg localization; reset once the localization completes.
+ALTER TABLE reorg_detector_state
+    ADD COLUMN IF NOT EXISTS search_known_valid_l1_batch_number BIGINT,
+    ADD COLUMN IF NOT EXISTS search_known_diverged_l1_batch_number BIGINT;
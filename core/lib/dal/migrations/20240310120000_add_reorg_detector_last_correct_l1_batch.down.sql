@@ -0,0 +1,2 @@
+ALTER TABLE reorg_detector_state
+    DROP COLUMN IF EXISTS last_correct_l1_batch_number;
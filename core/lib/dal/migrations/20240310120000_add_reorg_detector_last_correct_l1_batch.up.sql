@@ -0,0 +1,3 @@
+-- last L1 batch confirmed to match the main node; used to narrow down reorg localization after restarts.
+ALTER TABLE reorg_detector_state
+    ADD COLUMN IF NOT EXISTS last_correct_l1_batch_number BIGINT;
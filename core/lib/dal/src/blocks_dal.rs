@@ -164,6 +164,33 @@ impl BlocksDal<'_, '_> {
         Ok(row.number.map(|num| L1BatchNumber(num as u32)))
     }
 
+    /// Returns the number of L1 batches with metadata in the specified range.
+    pub async fn count_l1_batches_with_metadata(
+        &mut self,
+        number_range: ops::RangeInclusive<L1BatchNumber>,
+    ) -> sqlx::Result<u64> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) AS "count!"
+            FROM
+                l1_batches
+            WHERE
+                number BETWEEN $1 AND $2
+                AND hash IS NOT NULL
+            "#,
+            i64::from(number_range.start().0),
+            i64::from(number_range.end().0)
+        )
+        .instrument("count_l1_batches_with_metadata")
+        .with_arg("number_range", &number_range)
+        .report_latency()
+        .fetch_one(self.storage)
+        .await?;
+
+        Ok(row.count as u64)
+    }
+
     pub async fn get_l1_batches_for_eth_tx_id(
         &mut self,
         eth_tx_id: u32,
@@ -1652,6 +1679,69 @@ impl BlocksDal<'_, '_> {
         .map(|hash| H256::from_slice(&hash)))
     }
 
+    pub async fn get_l1_batch_l2_l1_merkle_root(
+        &mut self,
+        number: L1BatchNumber,
+    ) -> sqlx::Result<Option<H256>> {
+        Ok(sqlx::query!(
+            r#"
+            SELECT
+                l2_l1_merkle_root
+            FROM
+                l1_batches
+            WHERE
+                number = $1
+            "#,
+            number.0 as i64
+        )
+        .fetch_optional(self.storage.conn())
+        .await?
+        .and_then(|row| row.l2_l1_merkle_root)
+        .map(|hash| H256::from_slice(&hash)))
+    }
+
+    pub async fn get_l1_batch_commitment(
+        &mut self,
+        number: L1BatchNumber,
+    ) -> sqlx::Result<Option<H256>> {
+        Ok(sqlx::query!(
+            r#"
+            SELECT
+                commitment
+            FROM
+                l1_batches
+            WHERE
+                number = $1
+            "#,
+            number.0 as i64
+        )
+        .fetch_optional(self.storage.conn())
+        .await?
+        .and_then(|row| row.commitment)
+        .map(|hash| H256::from_slice(&hash)))
+    }
+
+    pub async fn get_l1_batch_pass_through_data_hash(
+        &mut self,
+        number: L1BatchNumber,
+    ) -> sqlx::Result<Option<H256>> {
+        Ok(sqlx::query!(
+            r#"
+            SELECT
+                pass_through_data_hash
+            FROM
+                l1_batches
+            WHERE
+                number = $1
+            "#,
+            number.0 as i64
+        )
+        .fetch_optional(self.storage.conn())
+        .await?
+        .and_then(|row| row.pass_through_data_hash)
+        .map(|hash| H256::from_slice(&hash)))
+    }
+
     pub async fn get_l1_batch_state_root_and_timestamp(
         &mut self,
         number: L1BatchNumber,
@@ -1909,6 +1999,32 @@ impl BlocksDal<'_, '_> {
         )))
     }
 
+    /// Returns hashes of transactions in the specified miniblock, ordered by their position in the miniblock.
+    pub async fn get_miniblock_transaction_hashes(
+        &mut self,
+        miniblock_number: MiniblockNumber,
+    ) -> sqlx::Result<Vec<H256>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                hash
+            FROM
+                transactions
+            WHERE
+                miniblock_number = $1
+            ORDER BY
+                index_in_block
+            "#,
+            miniblock_number.0 as i64
+        )
+        .fetch_all(self.storage.conn())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| H256::from_slice(&row.hash))
+            .collect())
+    }
+
     /// Returns `true` if there exists a non-sealed batch (i.e. there is one+ stored miniblock that isn't assigned
     /// to any batch yet).
     pub async fn pending_batch_exists(&mut self) -> sqlx::Result<bool> {
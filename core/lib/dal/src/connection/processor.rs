@@ -155,6 +155,18 @@ impl<'a> StorageProcessor<'a> {
         Ok(StorageProcessor { inner })
     }
 
+    /// Starts a read-only transaction with the repeatable read isolation level. All reads in such a transaction
+    /// observe the same DB snapshot, taken at its first query.
+    pub async fn start_repeatable_read_transaction(
+        &mut self,
+    ) -> sqlx::Result<StorageProcessor<'_>> {
+        let mut transaction = self.start_transaction().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(transaction.conn())
+            .await?;
+        Ok(transaction)
+    }
+
     /// Checks if the `StorageProcessor` is currently within database transaction.
     pub fn in_transaction(&self) -> bool {
         matches!(self.inner, StorageProcessorInner::Transaction { .. })
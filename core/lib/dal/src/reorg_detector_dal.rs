@@ -0,0 +1,159 @@
+use zksync_types::L1BatchNumber;
+
+use crate::StorageProcessor;
+
+/// Storage access methods for `zksync_core::reorg_detector` module.
+#[derive(Debug)]
+pub struct ReorgDetectorDal<'a, 'c> {
+    pub(crate) storage: &'a mut StorageProcessor<'c>,
+}
+
+impl ReorgDetectorDal<'_, '_> {
+    /// Returns the first diverged L1 batch according to the last detected reorg, if it wasn't reset since then.
+    pub async fn get_diverged_l1_batch(&mut self) -> sqlx::Result<Option<L1BatchNumber>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                diverged_l1_batch_number
+            FROM
+                reorg_detector_state
+            WHERE
+                fake_key
+            "#
+        )
+        .fetch_optional(self.storage.conn())
+        .await?;
+
+        Ok(row
+            .and_then(|row| row.diverged_l1_batch_number)
+            .map(|number| L1BatchNumber(number as u32)))
+    }
+
+    /// Sets or resets the first diverged L1 batch according to the last detected reorg.
+    pub async fn set_diverged_l1_batch(
+        &mut self,
+        number: Option<L1BatchNumber>,
+    ) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO
+                reorg_detector_state (fake_key, diverged_l1_batch_number, updated_at)
+            VALUES
+                (TRUE, $1, NOW())
+            ON CONFLICT (fake_key) DO
+            UPDATE
+            SET
+                diverged_l1_batch_number = excluded.diverged_l1_batch_number,
+                updated_at = excluded.updated_at
+            "#,
+            number.map(|number| i64::from(number.0))
+        )
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the last L1 batch confirmed to match the main node, if it was persisted.
+    pub async fn get_last_correct_l1_batch(&mut self) -> sqlx::Result<Option<L1BatchNumber>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                last_correct_l1_batch_number
+            FROM
+                reorg_detector_state
+            WHERE
+                fake_key
+            "#
+        )
+        .fetch_optional(self.storage.conn())
+        .await?;
+
+        Ok(row
+            .and_then(|row| row.last_correct_l1_batch_number)
+            .map(|number| L1BatchNumber(number as u32)))
+    }
+
+    /// Sets or resets the last L1 batch confirmed to match the main node.
+    pub async fn set_last_correct_l1_batch(
+        &mut self,
+        number: Option<L1BatchNumber>,
+    ) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO
+                reorg_detector_state (fake_key, last_correct_l1_batch_number, updated_at)
+            VALUES
+                (TRUE, $1, NOW())
+            ON CONFLICT (fake_key) DO
+            UPDATE
+            SET
+                last_correct_l1_batch_number = excluded.last_correct_l1_batch_number,
+                updated_at = excluded.updated_at
+            "#,
+            number.map(|number| i64::from(number.0))
+        )
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Returns bounds of the last interrupted reorg localization (the last known valid L1 batch and the first
+    /// known diverged one), if they weren't reset since then.
+    pub async fn get_reorg_search_bounds(
+        &mut self,
+    ) -> sqlx::Result<Option<(L1BatchNumber, L1BatchNumber)>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                search_known_valid_l1_batch_number,
+                search_known_diverged_l1_batch_number
+            FROM
+                reorg_detector_state
+            WHERE
+                fake_key
+            "#
+        )
+        .fetch_optional(self.storage.conn())
+        .await?;
+
+        Ok(row.and_then(|row| {
+            let known_valid = row.search_known_valid_l1_batch_number?;
+            let known_diverged = row.search_known_diverged_l1_batch_number?;
+            Some((
+                L1BatchNumber(known_valid as u32),
+                L1BatchNumber(known_diverged as u32),
+            ))
+        }))
+    }
+
+    /// Sets or resets bounds of an interrupted reorg localization.
+    pub async fn set_reorg_search_bounds(
+        &mut self,
+        bounds: Option<(L1BatchNumber, L1BatchNumber)>,
+    ) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO
+                reorg_detector_state (
+                    fake_key,
+                    search_known_valid_l1_batch_number,
+                    search_known_diverged_l1_batch_number,
+                    updated_at
+                )
+            VALUES
+                (TRUE, $1, $2, NOW())
+            ON CONFLICT (fake_key) DO
+            UPDATE
+            SET
+                search_known_valid_l1_batch_number = excluded.search_known_valid_l1_batch_number,
+                search_known_diverged_l1_batch_number = excluded.search_known_diverged_l1_batch_number,
+                updated_at = excluded.updated_at
+            "#,
+            bounds.map(|(known_valid, _)| i64::from(known_valid.0)),
+            bounds.map(|(_, known_diverged)| i64::from(known_diverged.0))
+        )
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use sqlx::types::chrono::NaiveDateTime;
 use zksync_types::{
-    api, api::TransactionReceipt, Address, L2ChainId, MiniblockNumber, Transaction,
+    api, api::TransactionReceipt, Address, L1BatchNumber, L2ChainId, MiniblockNumber, Transaction,
     ACCOUNT_CODE_STORAGE_ADDRESS, FAILED_CONTRACT_DEPLOYMENT_BYTECODE_HASH, H256, U256,
 };
 
@@ -221,6 +221,33 @@ impl TransactionsWeb3Dal<'_, '_> {
             .next())
     }
 
+    /// Returns the hash of the transaction with the specified index in an L1 batch.
+    pub async fn get_transaction_hash_by_l1_batch_position(
+        &mut self,
+        l1_batch_number: L1BatchNumber,
+        index_in_l1_batch: u32,
+    ) -> sqlx::Result<Option<H256>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                hash
+            FROM
+                transactions
+            WHERE
+                l1_batch_number = $1
+                AND l1_batch_tx_index = $2
+            "#,
+            i64::from(l1_batch_number.0),
+            index_in_l1_batch as i32
+        )
+        .instrument("get_transaction_hash_by_l1_batch_position")
+        .with_arg("l1_batch_number", &l1_batch_number)
+        .with_arg("index_in_l1_batch", &index_in_l1_batch)
+        .fetch_optional(self.storage)
+        .await?;
+        Ok(row.map(|row| H256::from_slice(&row.hash)))
+    }
+
     pub async fn get_transaction_details(
         &mut self,
         hash: H256,
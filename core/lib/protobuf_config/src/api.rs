@@ -120,6 +120,12 @@ impl ProtoRepr for proto::Web3JsonRpc {
                 .transpose()
                 .context("websocket_requests_per_minute_limit")?,
             tree_api_url: self.tree_api_url.clone(),
+            fork_id: self
+                .fork_id
+                .as_ref()
+                .map(|x| parse_h256(x))
+                .transpose()
+                .context("fork_id")?,
         })
     }
     fn build(this: &Self::Type) -> Self {
@@ -169,6 +175,7 @@ impl ProtoRepr for proto::Web3JsonRpc {
                 .websocket_requests_per_minute_limit
                 .map(|x| x.into()),
             tree_api_url: this.tree_api_url.clone(),
+            fork_id: this.fork_id.as_ref().map(|x| x.as_bytes().into()),
         }
     }
 }
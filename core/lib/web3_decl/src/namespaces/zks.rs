@@ -96,6 +96,15 @@ pub trait ZksNamespace {
     async fn get_l1_batch_details(&self, batch: L1BatchNumber)
         -> RpcResult<Option<L1BatchDetails>>;
 
+    #[method(name = "getL1BatchCommitment")]
+    async fn get_l1_batch_commitment(&self, batch: L1BatchNumber) -> RpcResult<Option<H256>>;
+
+    #[method(name = "getL1BatchPassThroughDataHash")]
+    async fn get_l1_batch_pass_through_data_hash(
+        &self,
+        batch: L1BatchNumber,
+    ) -> RpcResult<Option<H256>>;
+
     #[method(name = "getBytecodeByHash")]
     async fn get_bytecode_by_hash(&self, hash: H256) -> RpcResult<Option<Vec<u8>>>;
 
@@ -111,6 +120,9 @@ pub trait ZksNamespace {
         version_id: Option<u16>,
     ) -> RpcResult<Option<ProtocolVersion>>;
 
+    #[method(name = "getForkId")]
+    async fn get_fork_id(&self) -> RpcResult<Option<H256>>;
+
     #[method(name = "getProof")]
     async fn get_proof(
         &self,
@@ -134,6 +134,24 @@ impl ZksNamespaceServer for ZksNamespace {
             .map_err(|err| self.current_method().map_err(err))
     }
 
+    async fn get_l1_batch_commitment(
+        &self,
+        batch_number: L1BatchNumber,
+    ) -> RpcResult<Option<H256>> {
+        self.get_l1_batch_commitment_impl(batch_number)
+            .await
+            .map_err(|err| self.current_method().map_err(err))
+    }
+
+    async fn get_l1_batch_pass_through_data_hash(
+        &self,
+        batch_number: L1BatchNumber,
+    ) -> RpcResult<Option<H256>> {
+        self.get_l1_batch_pass_through_data_hash_impl(batch_number)
+            .await
+            .map_err(|err| self.current_method().map_err(err))
+    }
+
     async fn get_bytecode_by_hash(&self, hash: H256) -> RpcResult<Option<Vec<u8>>> {
         self.get_bytecode_by_hash_impl(hash)
             .await
@@ -157,6 +175,10 @@ impl ZksNamespaceServer for ZksNamespace {
             .map_err(|err| self.current_method().map_err(err))
     }
 
+    async fn get_fork_id(&self) -> RpcResult<Option<H256>> {
+        Ok(self.get_fork_id_impl())
+    }
+
     async fn get_proof(
         &self,
         address: Address,
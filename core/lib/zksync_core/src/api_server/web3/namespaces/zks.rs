@@ -135,6 +135,11 @@ impl ZksNamespace {
         U64::from(*self.state.api_config.l1_chain_id)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn get_fork_id_impl(&self) -> Option<H256> {
+        self.state.api_config.fork_id
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn get_confirmed_tokens_impl(
         &self,
@@ -435,6 +440,34 @@ impl ZksNamespace {
             .context("get_l1_batch_details")?)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub async fn get_l1_batch_commitment_impl(
+        &self,
+        batch_number: L1BatchNumber,
+    ) -> Result<Option<H256>, Web3Error> {
+        self.state.start_info.ensure_not_pruned(batch_number)?;
+        let mut storage = self.access_storage().await?;
+        Ok(storage
+            .blocks_dal()
+            .get_l1_batch_commitment(batch_number)
+            .await
+            .context("get_l1_batch_commitment")?)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_l1_batch_pass_through_data_hash_impl(
+        &self,
+        batch_number: L1BatchNumber,
+    ) -> Result<Option<H256>, Web3Error> {
+        self.state.start_info.ensure_not_pruned(batch_number)?;
+        let mut storage = self.access_storage().await?;
+        Ok(storage
+            .blocks_dal()
+            .get_l1_batch_pass_through_data_hash(batch_number)
+            .await
+            .context("get_l1_batch_pass_through_data_hash")?)
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn get_bytecode_by_hash_impl(
         &self,
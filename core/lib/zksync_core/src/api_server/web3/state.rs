@@ -91,6 +91,8 @@ pub struct InternalApiConfig {
     pub req_entities_limit: usize,
     pub fee_history_limit: u64,
     pub filters_disabled: bool,
+    /// Identifier of the chain fork served by the node, if configured.
+    pub fork_id: Option<H256>,
 }
 
 impl InternalApiConfig {
@@ -118,6 +120,7 @@ impl InternalApiConfig {
             req_entities_limit: web3_config.req_entities_limit(),
             fee_history_limit: web3_config.fee_history_limit(),
             filters_disabled: web3_config.filters_disabled,
+            fork_id: web3_config.fork_id,
         }
     }
 }
@@ -207,6 +207,11 @@ trait HttpTest: Send + Sync {
     fn filters_disabled(&self) -> bool {
         false
     }
+
+    /// Overrides the `fork_id` configuration parameter for HTTP server startup
+    fn fork_id(&self) -> Option<H256> {
+        None
+    }
 }
 
 /// Storage initialization strategy.
@@ -280,7 +285,8 @@ async fn test_http_server(test: impl HttpTest) {
 
     let (stop_sender, stop_receiver) = watch::channel(false);
     let contracts_config = ContractsConfig::for_tests();
-    let web3_config = Web3JsonRpcConfig::for_tests();
+    let mut web3_config = Web3JsonRpcConfig::for_tests();
+    web3_config.fork_id = test.fork_id();
     let mut api_config = InternalApiConfig::new(&network_config, &web3_config, &contracts_config);
     api_config.filters_disabled = test.filters_disabled();
     let mut server_handles = spawn_http_server(
@@ -472,6 +478,27 @@ async fn http_server_basics() {
     test_http_server(HttpServerBasicsTest).await;
 }
 
+#[derive(Debug)]
+struct ForkIdTest;
+
+#[async_trait]
+impl HttpTest for ForkIdTest {
+    fn fork_id(&self) -> Option<H256> {
+        Some(H256::repeat_byte(0xfe))
+    }
+
+    async fn test(&self, client: &HttpClient, _pool: &ConnectionPool) -> anyhow::Result<()> {
+        let fork_id = client.get_fork_id().await?;
+        assert_eq!(fork_id, Some(H256::repeat_byte(0xfe)));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn configured_fork_id_is_returned() {
+    test_http_server(ForkIdTest).await;
+}
+
 #[derive(Debug)]
 struct BlockMethodsWithSnapshotRecovery;
 
@@ -2,7 +2,10 @@
 
 use std::{fmt, time::Duration};
 
-use vise::{Buckets, Counter, EncodeLabelSet, EncodeLabelValue, Family, Gauge, Histogram, Metrics};
+use vise::{
+    Buckets, Counter, EncodeLabelSet, EncodeLabelValue, Family, Gauge, Histogram, LabeledFamily,
+    Metrics,
+};
 use zksync_dal::transactions_dal::L2TxSubmissionResult;
 use zksync_types::aggregated_operations::AggregatedActionType;
 
@@ -179,6 +182,32 @@ pub(crate) struct ExternalNodeMetrics {
     pub last_correct_batch: Family<CheckerComponent, Gauge<u64>>,
     /// Number of the last miniblock checked by the re-org detector or consistency checker.
     pub last_correct_miniblock: Family<CheckerComponent, Gauge<u64>>,
+    /// Depth of the last detected re-org, i.e. the number of L1 batches between the last correct L1 batch
+    /// and the diverged L1 batch that triggered re-org detection.
+    pub reorg_depth: Family<CheckerComponent, Gauge<u64>>,
+    /// Number of the highest L1 batch with the root hash verified by the re-org detector.
+    pub reorg_detector_max_verified_batch: Gauge<u64>,
+    /// Number of seconds elapsed since the last successful consistency check performed by the re-org detector
+    /// (or since the detector start if no check has succeeded yet).
+    pub reorg_detector_seconds_since_last_successful_check: Gauge<u64>,
+    /// Latency of a single consistency check performed by the re-org detector, including local DB reads
+    /// and comparisons with the main node (and re-org localization, if a re-org is detected).
+    #[metrics(buckets = Buckets::LATENCIES)]
+    pub reorg_detector_check_latency: Histogram<Duration>,
+    /// Latency of main node RPC call attempts made by the re-org detector, labeled by the main node client method.
+    #[metrics(buckets = Buckets::LATENCIES, labels = ["method"])]
+    pub reorg_detector_rpc_latency: LabeledFamily<&'static str, Histogram<Duration>>,
+    /// Number of L1 batch root hash comparisons with the main node made during a single re-org localization.
+    #[metrics(buckets = Buckets::exponential(1.0..=1_024.0, 2.0))]
+    pub reorg_detector_localization_probes: Histogram<usize>,
+    /// Number of re-orgs detected and localized by the re-org detector.
+    pub reorg_detector_detected_reorgs: Counter,
+    /// Number of consistency checks aborted by the re-org detector watchdog.
+    pub reorg_detector_watchdog_timeouts: Counter,
+    /// Number of failures reacquiring a DB connection during re-org localization.
+    pub reorg_detector_storage_reacquisition_failures: Counter,
+    /// Number of root hash checks for L1 batches missing on the main node, i.e. when the node is ahead of the main node.
+    pub missing_remote_l1_batches: Family<CheckerComponent, Counter>,
 }
 
 #[vise::register]
@@ -1,27 +1,65 @@
-use std::{fmt, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    num::{NonZeroU32, NonZeroUsize},
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
 use anyhow::Context as _;
 use async_trait::async_trait;
-use tokio::sync::watch;
-use zksync_dal::ConnectionPool;
+use bitflags::bitflags;
+use futures::{future, stream, StreamExt as _, TryStreamExt as _};
+use governor::{
+    clock::DefaultClock,
+    middleware::NoOpMiddleware,
+    state::{direct::InsufficientCapacity, InMemoryState, NotKeyed},
+    Quota, RateLimiter,
+};
+use lru::LruCache;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, watch};
+use tracing::Instrument as _;
+use zksync_dal::{ConnectionPool, StorageProcessor};
 use zksync_health_check::{Health, HealthStatus, HealthUpdater, ReactiveHealthCheck};
-use zksync_types::{L1BatchNumber, MiniblockNumber, H256};
+use zksync_types::{
+    api::{BlockDetailsBase, BlockNumber, L1BatchDetails, TransactionVariant},
+    L1BatchNumber, MiniblockNumber, H256, U64,
+};
 use zksync_web3_decl::{
     error::{ClientRpcContext, EnrichedClientError, EnrichedClientResult},
-    jsonrpsee::{core::ClientError as RpcError, http_client::HttpClient},
-    namespaces::{EthNamespaceClient, ZksNamespaceClient},
+    jsonrpsee::{
+        core::{client::ClientT, params::BatchRequestBuilder, ClientError as RpcError},
+        http_client::{HttpClient, HttpClientBuilder},
+        rpc_params,
+        ws_client::WsClient,
+    },
+    namespaces::{EnNamespaceClient, EthNamespaceClient, ZksNamespaceClient},
 };
 
 use crate::{
+    block_reverter::{BlockReverter, BlockReverterFlags},
     metrics::{CheckerComponent, EN_METRICS},
-    utils::{binary_search_with, wait_for_l1_batch_with_metadata},
+    utils::{
+        binary_search_with, search_with_cancellation, wait_for_l1_batch_with_metadata,
+        SearchOutcome,
+    },
 };
 
+#[cfg(any(test, feature = "test-util"))]
+pub mod testonly;
 #[cfg(test)]
 mod tests;
 
 #[derive(Debug, thiserror::Error)]
-enum HashMatchError {
+pub enum HashMatchError {
     #[error("RPC error calling main node")]
     Rpc(#[from] EnrichedClientError),
     #[error(
@@ -38,16 +76,137 @@ enum HashMatchError {
         Using an earlier snapshot could help."
     )]
     EarliestL1BatchTruncated(L1BatchNumber),
+    #[error(
+        "Unrecoverable error: L1 batches with metadata in the local DB have a gap in range #{0}..=#{1}. \
+        This is a local DB inconsistency rather than a reorg; hash comparisons cannot be anchored reliably."
+    )]
+    LocalBatchGap(L1BatchNumber, L1BatchNumber),
+    #[error(
+        "Unrecoverable error: a reorg starting from L1 batch #{0} was detected previously, but the local DB \
+        was not rolled back. Roll back the local DB to the last correct L1 batch (e.g., using the block reverter) \
+        before restarting the node"
+    )]
+    UnresolvedPriorReorg(L1BatchNumber),
+    #[error(
+        "Main node returned mutually inconsistent data for miniblock #{0}: its hash doesn't match \
+        the parent hash of the following miniblock. This is likely caused by responses being cached \
+        (e.g., by a CDN) and is not treated as a reorg"
+    )]
+    RemoteCachingArtifact(MiniblockNumber),
+    #[error(
+        "Main node returned incoherent chain state: its `zks` namespace reports L1 batch #{l1_batch} as sealed, \
+        but its `eth` namespace reports miniblock #{miniblock} as sealed, which doesn't cover this L1 batch. \
+        This is likely caused by the namespaces being served from diverging replicas and is not treated as a reorg"
+    )]
+    IncoherentNamespaces {
+        l1_batch: L1BatchNumber,
+        miniblock: MiniblockNumber,
+    },
+    #[error(
+        "Unrecoverable error: the local node and the main node agree on the root hash of L1 batch #{0}, \
+        but it disagrees with the independent anchor. Both nodes are likely following a bad branch; \
+        rolling back to the last L1 batch correct per the main node won't help"
+    )]
+    ConsensusWithAnchorMismatch(L1BatchNumber),
+    #[error(
+        "Unrecoverable error: the trusted L1 batch floor #{0} has mismatched root hash with the main node. \
+        L1 batches below the floor were assumed to be immutable; re-check the configured floor"
+    )]
+    TrustedFloorMismatch(L1BatchNumber),
+    #[error(
+        "Unrecoverable error: L1 batch #{0} executed on L1 according to the main node has mismatched root hash \
+        with the main node. Executed L1 batches are immutable, so the local DB has diverged from L1"
+    )]
+    ExecutedL1BatchMismatch(L1BatchNumber),
+    #[error(
+        "Unrecoverable error: L1 batch #{0} in the local DB has mismatched root hash with the main node, \
+        as detected during startup verification of recent L1 batches"
+    )]
+    StartupDivergence(L1BatchNumber),
+    #[error(
+        "Unrecoverable error: the main node serves chain fork {actual:?}, while fork {expected:?} is expected. \
+        Hash comparisons across forks are meaningless; make sure you're connected to the right main node"
+    )]
+    ForkMismatch {
+        expected: H256,
+        actual: Option<H256>,
+    },
     #[error("Internal error")]
     Internal(#[from] anyhow::Error),
 }
 
+impl HashMatchError {
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Rpc(err) => is_transient_err(err),
+            Self::RemoteCachingArtifact(_) | Self::IncoherentNamespaces { .. } => true,
+            _ => false,
+        }
+    }
+}
+
 impl From<zksync_dal::SqlxError> for HashMatchError {
     fn from(err: zksync_dal::SqlxError) -> Self {
         Self::Internal(err.into())
     }
 }
 
+/// Errors returned by public [`ReorgDetector`] methods.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    HashMatch(#[from] HashMatchError),
+}
+
+impl Error {
+    /// Checks whether the error is transient, i.e., the failed operation can be retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::HashMatch(err) => err.is_transient(),
+        }
+    }
+}
+
+/// Outcome of a single consistency check performed by [`ReorgDetector`]. Can be serialized, e.g. to log
+/// or export it from monitoring wrappers on each check.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum CheckOutcome {
+    /// The checked L1 batch and miniblock match the main node.
+    Consistent {
+        l1_batch: L1BatchNumber,
+        miniblock: MiniblockNumber,
+    },
+    /// There was nothing to check, e.g. no synced L1 batches in the catch-up-only mode, or the detected divergence
+    /// was [tolerated as tip churn](ReorgDetector::with_tip_churn_tolerance()).
+    Skipped,
+    /// A reorg was detected and localized.
+    ReorgDetected {
+        last_correct_l1_batch: L1BatchNumber,
+        /// Hashes that have diverged during the check, or `None` if they are unknown (e.g., if the divergence
+        /// was found by an optional comparison, or when resuming interrupted localization).
+        divergence: Option<DivergenceKind>,
+    },
+    /// A reorg was detected, but its localization was interrupted by a stop signal.
+    LocalizationInterrupted {
+        known_valid: L1BatchNumber,
+        known_diverged: L1BatchNumber,
+    },
+}
+
+/// Result of reorg localization performed by [`ReorgDetector`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchResult {
+    /// Localization has completed; contains the last correct L1 batch.
+    Complete(L1BatchNumber),
+    /// Localization was interrupted by a stop signal; contains the best known bounds, from which the search
+    /// can be resumed.
+    Partial {
+        known_valid: L1BatchNumber,
+        known_diverged: L1BatchNumber,
+    },
+}
+
 fn is_transient_err(err: &EnrichedClientError) -> bool {
     matches!(
         err.as_ref(),
@@ -55,357 +214,5496 @@ fn is_transient_err(err: &EnrichedClientError) -> bool {
     )
 }
 
+/// Main node client used by [`ReorgDetector`]. Implemented for [`HttpClient`] and [`WsClient`]; can be implemented
+/// by wrappers adding caching, rate limiting, failover etc. and provided to the detector
+/// via [`ReorgDetector::from_parts()`].
 #[async_trait]
-trait MainNodeClient: fmt::Debug + Send + Sync {
+pub trait MainNodeClient: fmt::Debug + Send + Sync {
+    /// Returns the number of the latest sealed miniblock on the main node.
     async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber>;
 
+    /// Returns the number of the latest sealed L1 batch on the main node.
     async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber>;
 
+    /// Returns the hash of the specified miniblock, or `None` if the miniblock is missing on the main node
+    /// or its hash is not available yet.
     async fn miniblock_hash(&self, number: MiniblockNumber) -> EnrichedClientResult<Option<H256>>;
 
-    async fn l1_batch_root_hash(&self, number: L1BatchNumber)
-        -> EnrichedClientResult<Option<H256>>;
-}
-
-#[async_trait]
-impl MainNodeClient for HttpClient {
-    async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber> {
-        let number = self
-            .get_block_number()
-            .rpc_context("sealed_miniblock_number")
-            .await?;
-        let number = u32::try_from(number).map_err(|err| {
-            EnrichedClientError::custom(err, "u32::try_from").with_arg("number", &number)
-        })?;
-        Ok(MiniblockNumber(number))
+    /// Returns hashes for the specified range of miniblocks, in the same format as [`Self::miniblock_hash()`].
+    /// The default implementation requests hashes one by one.
+    async fn miniblock_hashes(
+        &self,
+        range: RangeInclusive<MiniblockNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        let mut hashes =
+            Vec::with_capacity(range.end().0.saturating_sub(range.start().0) as usize + 1);
+        for number in range.start().0..=range.end().0 {
+            hashes.push(self.miniblock_hash(MiniblockNumber(number)).await?);
+        }
+        Ok(hashes)
     }
 
-    async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber> {
-        let number = self
-            .get_l1_batch_number()
-            .rpc_context("sealed_l1_batch_number")
-            .await?;
-        let number = u32::try_from(number).map_err(|err| {
-            EnrichedClientError::custom(err, "u32::try_from").with_arg("number", &number)
-        })?;
-        Ok(L1BatchNumber(number))
-    }
+    /// Returns the parent hash of the specified miniblock, or `None` if the miniblock is missing on the main node.
+    async fn miniblock_parent_hash(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<H256>>;
 
-    async fn miniblock_hash(&self, number: MiniblockNumber) -> EnrichedClientResult<Option<H256>> {
-        Ok(self
-            .get_block_by_number(number.0.into(), false)
-            .rpc_context("miniblock_hash")
-            .with_arg("number", &number)
-            .await?
-            .map(|block| block.hash))
+    /// Returns the state root hash of the specified L1 batch, or `None` if the batch is missing on the main node
+    /// or its root hash is not computed yet.
+    async fn l1_batch_root_hash(&self, number: L1BatchNumber)
+        -> EnrichedClientResult<Option<H256>>;
+
+    /// Returns state root hashes for the specified range of L1 batches, in the same format as
+    /// [`Self::l1_batch_root_hash()`]. The default implementation requests root hashes one by one.
+    async fn l1_batch_root_hashes(
+        &self,
+        range: RangeInclusive<L1BatchNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        let mut hashes =
+            Vec::with_capacity(range.end().0.saturating_sub(range.start().0) as usize + 1);
+        for number in range.start().0..=range.end().0 {
+            hashes.push(self.l1_batch_root_hash(L1BatchNumber(number)).await?);
+        }
+        Ok(hashes)
     }
 
-    async fn l1_batch_root_hash(
+    /// Returns the range of miniblocks in the specified L1 batch, or `None` if the batch is missing on the main node.
+    async fn l1_batch_miniblock_range(
         &self,
         number: L1BatchNumber,
-    ) -> EnrichedClientResult<Option<H256>> {
-        Ok(self
-            .get_l1_batch_details(number)
-            .rpc_context("l1_batch_root_hash")
-            .with_arg("number", &number)
-            .await?
-            .and_then(|batch| batch.base.root_hash))
-    }
-}
+    ) -> EnrichedClientResult<Option<(MiniblockNumber, MiniblockNumber)>>;
 
-trait HandleReorgDetectorEvent: fmt::Debug + Send + Sync {
-    fn initialize(&mut self);
+    /// Returns the latest protocol version on the main node.
+    async fn protocol_version(&self) -> EnrichedClientResult<u16>;
 
-    fn update_correct_block(
-        &mut self,
-        last_correct_miniblock: MiniblockNumber,
-        last_correct_l1_batch: L1BatchNumber,
-    );
+    /// Returns the identifier of the chain fork served by the main node, or `None` if it is not configured.
+    async fn fork_id(&self) -> EnrichedClientResult<Option<H256>>;
 
-    fn report_divergence(&mut self, diverged_l1_batch: L1BatchNumber);
+    /// Returns the number of virtual blocks associated with the specified miniblock, or `None` if the miniblock
+    /// is missing or the main node doesn't expose virtual block info for it.
+    async fn miniblock_virtual_blocks(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<u32>>;
 
-    fn start_shutting_down(&mut self);
-}
+    /// Returns details for the specified L1 batch, or `None` if the batch is missing on the main node.
+    async fn l1_batch_details(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<L1BatchDetails>>;
 
-/// Default implementation of [`HandleReorgDetectorEvent`] that reports values as metrics.
-impl HandleReorgDetectorEvent for HealthUpdater {
-    fn initialize(&mut self) {
-        self.update(Health::from(HealthStatus::Ready));
-    }
+    /// Returns whether the specified L1 batch is proven on L1 according to the main node, or `None`
+    /// if the batch is missing on the main node.
+    async fn l1_batch_proven(&self, number: L1BatchNumber) -> EnrichedClientResult<Option<bool>>;
 
-    fn update_correct_block(
-        &mut self,
-        last_correct_miniblock: MiniblockNumber,
-        last_correct_l1_batch: L1BatchNumber,
-    ) {
-        let last_correct_miniblock = last_correct_miniblock.0.into();
-        let prev_checked_miniblock = EN_METRICS.last_correct_miniblock
-            [&CheckerComponent::ReorgDetector]
-            .set(last_correct_miniblock);
-        if prev_checked_miniblock != last_correct_miniblock {
-            tracing::debug!("No reorg at miniblock #{last_correct_miniblock}");
-        }
+    /// Returns the root of the L2-to-L1 logs Merkle tree for the L1 batch containing the specified transaction,
+    /// or `None` if the transaction is unknown to the main node or has emitted no L2-to-L1 logs.
+    async fn l2_to_l1_logs_root_hash(&self, tx_hash: H256) -> EnrichedClientResult<Option<H256>>;
 
-        let last_correct_l1_batch = last_correct_l1_batch.0.into();
-        let prev_checked_l1_batch = EN_METRICS.last_correct_batch[&CheckerComponent::ReorgDetector]
-            .set(last_correct_l1_batch);
-        if prev_checked_l1_batch != last_correct_l1_batch {
-            tracing::debug!("No reorg at L1 batch #{last_correct_l1_batch}");
-        }
+    /// Returns the commitment hash of the specified L1 batch, or `None` if the batch is missing on the main node
+    /// or its commitment isn't computed yet.
+    async fn l1_batch_commitment(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>>;
 
-        let health_details = serde_json::json!({
-            "last_correct_miniblock": last_correct_miniblock,
-            "last_correct_l1_batch": last_correct_l1_batch,
-        });
-        self.update(Health::from(HealthStatus::Ready).with_details(health_details));
-    }
+    /// Returns hashes of transactions in the specified miniblock in the order of their execution, or `None`
+    /// if the miniblock is missing on the main node.
+    async fn miniblock_transaction_hashes(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<Vec<H256>>>;
 
-    fn report_divergence(&mut self, diverged_l1_batch: L1BatchNumber) {
-        let health_details = serde_json::json!({
-            "diverged_l1_batch": diverged_l1_batch,
-        });
-        self.update(Health::from(HealthStatus::Affected).with_details(health_details));
-    }
+    /// Returns the number and hash of the pending (not yet sealed) miniblock on the main node, or `None`
+    /// if the main node doesn't have a pending miniblock.
+    async fn pending_miniblock(&self) -> EnrichedClientResult<Option<(MiniblockNumber, H256)>>;
 
-    fn start_shutting_down(&mut self) {
-        self.update(HealthStatus::ShuttingDown.into());
-    }
+    /// Returns the last L1 batch executed on L1 according to the main node, or `None` if no L1 batches
+    /// are executed yet.
+    async fn last_executed_l1_batch(&self) -> EnrichedClientResult<Option<L1BatchNumber>>;
+
+    /// Returns the pass-through data hash of the specified L1 batch, or `None` if the batch is missing
+    /// on the main node or its commitment isn't computed yet.
+    async fn l1_batch_pass_through_data_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>>;
 }
 
-/// Output of hash match methods in [`ReorgDetector`].
-#[derive(Debug)]
-enum MatchOutput {
-    Match,
-    Mismatch,
-    NoRemoteReference,
+/// Max number of concurrent requests made by [`MainNodeClient::miniblock_hashes()`] implementations
+/// for `jsonrpsee` clients.
+const MAX_CONCURRENT_MINIBLOCK_HASH_REQUESTS: usize = 10;
+
+/// Part of the `eth_getBlockByNumber` response used by [`MainNodeClient::miniblock_hash()`] implementations
+/// for `jsonrpsee` clients. Unlike the full block, tolerates a null hash, which the main node may briefly return
+/// for a miniblock being sealed.
+#[derive(Debug, Deserialize)]
+struct RemoteBlockHash {
+    hash: Option<H256>,
 }
 
-impl MatchOutput {
-    fn new(is_match: bool) -> Self {
-        if is_match {
-            Self::Match
-        } else {
-            Self::Mismatch
+impl RemoteBlockHash {
+    /// Converts the response to the miniblock hash. A present miniblock with a null hash is treated
+    /// the same as a missing one, i.e., as not yet available on the main node.
+    fn into_hash(response: Option<Self>, number: MiniblockNumber) -> Option<H256> {
+        let hash = response?.hash;
+        if hash.is_none() {
+            tracing::debug!(
+                miniblock = number.0,
+                "Main node returned miniblock with null hash; treating it as not yet available"
+            );
         }
+        hash
     }
 }
 
-/// This is a component that is responsible for detecting the batch re-orgs.
-/// Batch re-org is a rare event of manual intervention, when the node operator
-/// decides to revert some of the not yet finalized batches for some reason
-/// (e.g. inability to generate a proof), and then potentially
-/// re-organize transactions in them to fix the problem.
-///
-/// To detect them, we constantly check the latest sealed batch root hash,
-/// and in the event of mismatch, we know that there has been a re-org.
-/// We then perform a binary search to find the latest correct block
-/// and revert all batches after it, to keep being consistent with the main node.
-///
-/// This is the only component that is expected to finish its execution
-/// in the even of re-org, since we have to restart the node after a rollback is performed,
-/// and is special-cased in the `zksync_external_node` crate.
-#[derive(Debug)]
-pub struct ReorgDetector {
-    client: Box<dyn MainNodeClient>,
-    event_handler: Box<dyn HandleReorgDetectorEvent>,
-    pool: ConnectionPool,
-    sleep_interval: Duration,
-    health_check: ReactiveHealthCheck,
+/// Part of the `eth_getBlockByNumber` response used by [`MainNodeClient::miniblock_parent_hash()`]
+/// and [`MainNodeClient::pending_miniblock()`] implementations for `jsonrpsee` clients. Like [`RemoteBlockHash`],
+/// tolerates a null block hash.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteBlockHeader {
+    number: U64,
+    hash: Option<H256>,
+    parent_hash: H256,
 }
 
-impl ReorgDetector {
-    const DEFAULT_SLEEP_INTERVAL: Duration = Duration::from_secs(5);
-
-    pub fn new(client: HttpClient, pool: ConnectionPool) -> Self {
-        let (health_check, health_updater) = ReactiveHealthCheck::new("reorg_detector");
-        Self {
-            client: Box::new(client),
-            event_handler: Box::new(health_updater),
-            pool,
-            sleep_interval: Self::DEFAULT_SLEEP_INTERVAL,
-            health_check,
-        }
+impl RemoteBlockHeader {
+    /// Converts the response to the number and hash of the pending miniblock. A pending miniblock with a null hash
+    /// is treated the same as a missing one, since there's nothing to compare.
+    fn into_pending_miniblock(
+        response: Option<Self>,
+    ) -> EnrichedClientResult<Option<(MiniblockNumber, H256)>> {
+        let Some(header) = response else {
+            return Ok(None);
+        };
+        let number = u32::try_from(header.number).map_err(|err| {
+            EnrichedClientError::custom(err, "u32::try_from").with_arg("number", &header.number)
+        })?;
+        let Some(hash) = header.hash else {
+            tracing::debug!(
+                miniblock = number,
+                "Main node returned pending miniblock with null hash; skipping comparison"
+            );
+            return Ok(None);
+        };
+        Ok(Some((MiniblockNumber(number), hash)))
     }
+}
 
-    pub fn health_check(&self) -> &ReactiveHealthCheck {
-        &self.health_check
-    }
+/// Implements [`MainNodeClient`] for a `jsonrpsee` client type. Error enrichment is shared, so that
+/// [`HashMatchError::is_transient()`] works identically for all client types.
+macro_rules! impl_main_node_client {
+    ($client:ty) => {
+        #[async_trait]
+        impl MainNodeClient for $client {
+            async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber> {
+                const METHOD: &str = "sealed_miniblock_number";
 
-    /// Compares hashes of the given local miniblock and the same miniblock from main node.
-    async fn miniblock_hashes_match(
-        &self,
-        miniblock_number: MiniblockNumber,
-    ) -> Result<MatchOutput, HashMatchError> {
-        let mut storage = self.pool.access_storage().await?;
-        let local_hash = storage
-            .blocks_dal()
-            .get_miniblock_header(miniblock_number)
-            .await?
-            .with_context(|| {
-                format!("Header does not exist for local miniblock #{miniblock_number}")
-            })?
-            .hash;
-        drop(storage);
+                let number = self.get_block_number().rpc_context(METHOD).await?;
+                let number = u32::try_from(number).map_err(|err| {
+                    EnrichedClientError::custom(err, "u32::try_from").with_arg("number", &number)
+                })?;
+                Ok(MiniblockNumber(number))
+            }
 
-        let Some(remote_hash) = self.client.miniblock_hash(miniblock_number).await? else {
-            // Due to reorg, locally we may be ahead of the main node.
-            // Lack of the hash on the main node is treated as a hash match,
-            // We need to wait for our knowledge of main node to catch up.
-            return Ok(MatchOutput::NoRemoteReference);
-        };
+            async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber> {
+                const METHOD: &str = "sealed_l1_batch_number";
 
-        if remote_hash != local_hash {
-            tracing::warn!(
-                "Reorg detected: local hash {local_hash:?} doesn't match the hash from \
-                main node {remote_hash:?} (miniblock #{miniblock_number})"
-            );
-        }
-        Ok(MatchOutput::new(remote_hash == local_hash))
-    }
+                let number = self.get_l1_batch_number().rpc_context(METHOD).await?;
+                let number = u32::try_from(number).map_err(|err| {
+                    EnrichedClientError::custom(err, "u32::try_from").with_arg("number", &number)
+                })?;
+                Ok(L1BatchNumber(number))
+            }
 
-    /// Checks hash correspondence for the latest miniblock sealed both locally and on the main node.
-    async fn check_sealed_miniblock_hash(
-        &self,
-        sealed_miniblock_number: MiniblockNumber,
-    ) -> Result<(MiniblockNumber, bool), HashMatchError> {
-        let mut main_node_sealed_miniblock_number = sealed_miniblock_number;
-        loop {
-            let checked_number = sealed_miniblock_number.min(main_node_sealed_miniblock_number);
-            match self.miniblock_hashes_match(checked_number).await? {
-                MatchOutput::Match => break Ok((checked_number, true)),
-                MatchOutput::Mismatch => break Ok((checked_number, false)),
-                MatchOutput::NoRemoteReference => {
-                    tracing::info!(
-                        "Main node has no miniblock #{checked_number}; will check last miniblock on the main node"
-                    );
-                    main_node_sealed_miniblock_number =
-                        self.client.sealed_miniblock_number().await?;
-                    tracing::debug!(
-                        "Fetched last miniblock on the main node: #{main_node_sealed_miniblock_number}"
-                    );
-                }
+            async fn miniblock_hash(
+                &self,
+                number: MiniblockNumber,
+            ) -> EnrichedClientResult<Option<H256>> {
+                const METHOD: &str = "miniblock_hash";
+
+                let response = self
+                    .request::<Option<RemoteBlockHash>, _>(
+                        "eth_getBlockByNumber",
+                        rpc_params![BlockNumber::Number(number.0.into()), false],
+                    )
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await?;
+                Ok(RemoteBlockHash::into_hash(response, number))
             }
-        }
-    }
 
-    /// Compares root hashes of the latest local batch and of the same batch from the main node.
-    async fn root_hashes_match(
-        &self,
-        l1_batch_number: L1BatchNumber,
-    ) -> Result<MatchOutput, HashMatchError> {
-        let mut storage = self.pool.access_storage().await?;
-        let local_hash = storage
-            .blocks_dal()
-            .get_l1_batch_state_root(l1_batch_number)
-            .await?
-            .with_context(|| {
-                format!("Root hash does not exist for local batch #{l1_batch_number}")
-            })?;
-        drop(storage);
+            /// Requests miniblock hashes concurrently, with a bounded number of in-flight requests.
+            async fn miniblock_hashes(
+                &self,
+                range: RangeInclusive<MiniblockNumber>,
+            ) -> EnrichedClientResult<Vec<Option<H256>>> {
+                let hashes = (range.start().0..=range.end().0)
+                    .map(|number| self.miniblock_hash(MiniblockNumber(number)));
+                stream::iter(hashes)
+                    .buffered(MAX_CONCURRENT_MINIBLOCK_HASH_REQUESTS)
+                    .try_collect()
+                    .await
+            }
 
-        let Some(remote_hash) = self.client.l1_batch_root_hash(l1_batch_number).await? else {
-            // Due to reorg, locally we may be ahead of the main node.
-            // Lack of the root hash on the main node is treated as a hash match,
-            // We need to wait for our knowledge of main node to catch up.
-            return Ok(MatchOutput::NoRemoteReference);
-        };
+            async fn miniblock_parent_hash(
+                &self,
+                number: MiniblockNumber,
+            ) -> EnrichedClientResult<Option<H256>> {
+                const METHOD: &str = "miniblock_parent_hash";
 
-        if remote_hash != local_hash {
-            tracing::warn!(
-                "Reorg detected: local root hash {local_hash:?} doesn't match the state hash from \
-                main node {remote_hash:?} (L1 batch #{l1_batch_number})"
-            );
-        }
-        Ok(MatchOutput::new(remote_hash == local_hash))
-    }
+                let response = self
+                    .request::<Option<RemoteBlockHeader>, _>(
+                        "eth_getBlockByNumber",
+                        rpc_params![BlockNumber::Number(number.0.into()), false],
+                    )
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await?;
+                Ok(response.map(|header| header.parent_hash))
+            }
 
-    /// Checks hash correspondence for the latest L1 batch sealed and having metadata both locally and on the main node.
-    async fn check_sealed_l1_batch_root_hash(
-        &self,
-        sealed_l1_batch_number: L1BatchNumber,
-    ) -> Result<(L1BatchNumber, bool), HashMatchError> {
-        let mut main_node_sealed_l1_batch_number = sealed_l1_batch_number;
-        loop {
-            let checked_number = sealed_l1_batch_number.min(main_node_sealed_l1_batch_number);
-            match self.root_hashes_match(checked_number).await? {
-                MatchOutput::Match => break Ok((checked_number, true)),
-                MatchOutput::Mismatch => break Ok((checked_number, false)),
-                MatchOutput::NoRemoteReference => {
-                    tracing::info!(
-                        "Main node has no L1 batch #{checked_number}; will check last L1 batch on the main node"
-                    );
-                    let fetched_number = self.client.sealed_l1_batch_number().await?;
-                    tracing::debug!("Fetched last L1 batch on the main node: #{fetched_number}");
-                    let number_changed = fetched_number != main_node_sealed_l1_batch_number;
-                    main_node_sealed_l1_batch_number = fetched_number;
+            async fn l1_batch_root_hash(
+                &self,
+                number: L1BatchNumber,
+            ) -> EnrichedClientResult<Option<H256>> {
+                const METHOD: &str = "l1_batch_root_hash";
 
-                    if !number_changed {
-                        // May happen if the main node has an L1 batch, but its state root hash is not computed yet.
-                        tracing::debug!("Last L1 batch number on the main node has not changed; waiting until its state hash is computed");
-                        tokio::time::sleep(self.sleep_interval / 10).await;
-                    }
-                }
+                Ok(self
+                    .get_l1_batch_details(number)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await?
+                    .and_then(|batch| batch.base.root_hash))
             }
-        }
-    }
 
-    /// Localizes a re-org: performs binary search to determine the last non-diverged block.
-    async fn detect_reorg(
-        &self,
-        known_valid_l1_batch: L1BatchNumber,
-        diverged_l1_batch: L1BatchNumber,
-    ) -> Result<L1BatchNumber, HashMatchError> {
-        // TODO (BFT-176, BFT-181): We have to look through the whole history, since batch status updater may mark
-        //   a block as executed even if the state diverges for it.
-        binary_search_with(
-            known_valid_l1_batch.0,
-            diverged_l1_batch.0,
-            |number| async move {
-                Ok(match self.root_hashes_match(L1BatchNumber(number)).await? {
-                    MatchOutput::Match | MatchOutput::NoRemoteReference => true,
-                    MatchOutput::Mismatch => false,
-                })
-            },
-        )
-        .await
-        .map(L1BatchNumber)
-    }
+            /// Requests root hashes using a single batch request.
+            async fn l1_batch_root_hashes(
+                &self,
+                range: RangeInclusive<L1BatchNumber>,
+            ) -> EnrichedClientResult<Vec<Option<H256>>> {
+                const METHOD: &str = "l1_batch_root_hashes";
 
-    pub async fn run(
-        mut self,
-        mut stop_receiver: watch::Receiver<bool>,
-    ) -> anyhow::Result<Option<L1BatchNumber>> {
-        self.event_handler.initialize();
-        loop {
-            match self.run_inner(&mut stop_receiver).await {
-                Ok(l1_batch_number) => return Ok(l1_batch_number),
-                Err(HashMatchError::Rpc(err)) if is_transient_err(&err) => {
-                    tracing::warn!("Following transport error occurred: {err}");
-                    tracing::info!("Trying again after a delay");
-                    tokio::time::sleep(self.sleep_interval).await;
+                if range.is_empty() {
+                    return Ok(vec![]);
                 }
-                Err(HashMatchError::Internal(err)) => return Err(err),
-                Err(err) => return Err(err.into()),
+                let mut batch = BatchRequestBuilder::new();
+                for number in range.start().0..=range.end().0 {
+                    batch
+                        .insert("zks_getL1BatchDetails", rpc_params![L1BatchNumber(number)])
+                        .map_err(|err| {
+                            EnrichedClientError::custom(err.to_string(), METHOD)
+                                .with_arg("range", &range)
+                        })?;
+                }
+                let response = self
+                    .batch_request::<Option<L1BatchDetails>>(batch)
+                    .await
+                    .map_err(|err| {
+                        EnrichedClientError::new(err, METHOD).with_arg("range", &range)
+                    })?;
+                response
+                    .into_iter()
+                    .map(|details| match details {
+                        Ok(details) => Ok(details.and_then(|batch| batch.base.root_hash)),
+                        Err(err) => Err(EnrichedClientError::new(
+                            RpcError::Call(err.into_owned()),
+                            METHOD,
+                        )
+                        .with_arg("range", &range)),
+                    })
+                    .collect()
             }
-        }
+
+            async fn l1_batch_miniblock_range(
+                &self,
+                number: L1BatchNumber,
+            ) -> EnrichedClientResult<Option<(MiniblockNumber, MiniblockNumber)>> {
+                const METHOD: &str = "l1_batch_miniblock_range";
+
+                let Some((first, last)) = self
+                    .get_miniblock_range(number)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                let to_miniblock_number = |number: U64| {
+                    u32::try_from(number).map(MiniblockNumber).map_err(|err| {
+                        EnrichedClientError::custom(err, "u32::try_from")
+                            .with_arg("number", &number)
+                    })
+                };
+                Ok(Some((
+                    to_miniblock_number(first)?,
+                    to_miniblock_number(last)?,
+                )))
+            }
+
+            async fn protocol_version(&self) -> EnrichedClientResult<u16> {
+                const METHOD: &str = "protocol_version";
+
+                let version = self
+                    .get_protocol_version(None)
+                    .rpc_context(METHOD)
+                    .await?
+                    .ok_or_else(|| {
+                        EnrichedClientError::custom("no protocol version on main node", METHOD)
+                    })?;
+                Ok(version.version_id)
+            }
+
+            async fn fork_id(&self) -> EnrichedClientResult<Option<H256>> {
+                const METHOD: &str = "fork_id";
+
+                self.get_fork_id().rpc_context(METHOD).await
+            }
+
+            async fn miniblock_virtual_blocks(
+                &self,
+                number: MiniblockNumber,
+            ) -> EnrichedClientResult<Option<u32>> {
+                const METHOD: &str = "miniblock_virtual_blocks";
+
+                Ok(self
+                    .sync_l2_block(number, false)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await?
+                    .and_then(|block| block.virtual_blocks))
+            }
+
+            async fn l1_batch_details(
+                &self,
+                number: L1BatchNumber,
+            ) -> EnrichedClientResult<Option<L1BatchDetails>> {
+                const METHOD: &str = "l1_batch_details";
+
+                self.get_l1_batch_details(number)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await
+            }
+
+            async fn l1_batch_proven(
+                &self,
+                number: L1BatchNumber,
+            ) -> EnrichedClientResult<Option<bool>> {
+                const METHOD: &str = "l1_batch_proven";
+
+                Ok(self
+                    .get_l1_batch_details(number)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await?
+                    .map(|batch| batch.base.prove_tx_hash.is_some()))
+            }
+
+            async fn l2_to_l1_logs_root_hash(
+                &self,
+                tx_hash: H256,
+            ) -> EnrichedClientResult<Option<H256>> {
+                const METHOD: &str = "l2_to_l1_logs_root_hash";
+
+                Ok(self
+                    .get_l2_to_l1_log_proof(tx_hash, None)
+                    .rpc_context(METHOD)
+                    .with_arg("tx_hash", &tx_hash)
+                    .await?
+                    .map(|proof| proof.root))
+            }
+
+            async fn l1_batch_commitment(
+                &self,
+                number: L1BatchNumber,
+            ) -> EnrichedClientResult<Option<H256>> {
+                const METHOD: &str = "l1_batch_commitment";
+
+                self.get_l1_batch_commitment(number)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await
+            }
+
+            async fn miniblock_transaction_hashes(
+                &self,
+                number: MiniblockNumber,
+            ) -> EnrichedClientResult<Option<Vec<H256>>> {
+                const METHOD: &str = "miniblock_transaction_hashes";
+
+                let Some(block) = self
+                    .get_block_by_number(number.0.into(), false)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                let hashes = block.transactions.into_iter().map(|tx| match tx {
+                    TransactionVariant::Full(tx) => tx.hash,
+                    TransactionVariant::Hash(hash) => hash,
+                });
+                Ok(Some(hashes.collect()))
+            }
+
+            async fn pending_miniblock(
+                &self,
+            ) -> EnrichedClientResult<Option<(MiniblockNumber, H256)>> {
+                const METHOD: &str = "pending_miniblock";
+
+                let response = self
+                    .request::<Option<RemoteBlockHeader>, _>(
+                        "eth_getBlockByNumber",
+                        rpc_params![BlockNumber::Pending, false],
+                    )
+                    .rpc_context(METHOD)
+                    .await?;
+                RemoteBlockHeader::into_pending_miniblock(response)
+            }
+
+            async fn last_executed_l1_batch(&self) -> EnrichedClientResult<Option<L1BatchNumber>> {
+                const METHOD: &str = "last_executed_l1_batch";
+
+                // The finalized miniblock is the last miniblock in the last executed L1 batch.
+                let number = self
+                    .get_block_by_number(BlockNumber::Finalized, false)
+                    .rpc_context(METHOD)
+                    .await?
+                    .and_then(|block| block.l1_batch_number);
+                let Some(number) = number else {
+                    return Ok(None);
+                };
+                let number = u32::try_from(number).map_err(|err| {
+                    EnrichedClientError::custom(err, "u32::try_from").with_arg("number", &number)
+                })?;
+                Ok(Some(L1BatchNumber(number)))
+            }
+
+            async fn l1_batch_pass_through_data_hash(
+                &self,
+                number: L1BatchNumber,
+            ) -> EnrichedClientResult<Option<H256>> {
+                const METHOD: &str = "l1_batch_pass_through_data_hash";
+
+                self.get_l1_batch_pass_through_data_hash(number)
+                    .rpc_context(METHOD)
+                    .with_arg("number", &number)
+                    .await
+            }
+        }
+    };
+}
+
+impl_main_node_client!(HttpClient);
+impl_main_node_client!(WsClient);
+
+type RpcRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>;
+
+/// Wrapper around a [`MainNodeClient`] limiting the duration of each call. Elapsed calls are reported
+/// as transient [`RpcError::RequestTimeout`] errors, so that they are retried by [`ReorgDetector`].
+/// If a rate limiter is set, calls wait until they fit into the rate limit before being sent.
+/// Calls failing with a transient error are retried up to the configured number of times
+/// before the error is returned; retries are aborted once a stop signal is received.
+#[derive(Debug)]
+struct TimeoutClient<'a> {
+    inner: &'a dyn MainNodeClient,
+    timeout: Duration,
+    rate_limiter: Option<&'a RpcRateLimiter>,
+    retries: RpcRetries,
+    /// Upper bound for the backoff between retries.
+    max_backoff: Duration,
+    clock: &'a dyn Clock,
+    metrics: &'a dyn MetricsSink,
+    stop_receiver: Option<&'a watch::Receiver<bool>>,
+}
+
+impl TimeoutClient<'_> {
+    async fn call<T, F>(
+        &self,
+        method: &'static str,
+        call: impl Fn() -> F,
+    ) -> EnrichedClientResult<T>
+    where
+        F: Future<Output = EnrichedClientResult<T>>,
+    {
+        self.call_with_cost(method, 1, call).await
+    }
+
+    /// Same as [`Self::call()`], but consumes the specified number of requests from the rate limit.
+    /// Used for calls sending multiple requests to the main node.
+    ///
+    /// Each call (including waiting for the rate limit) is wrapped in a span, so that the RPC latency breakdown
+    /// of a consistency check can be observed, e.g. by exporting spans to OpenTelemetry.
+    async fn call_with_cost<T, F>(
+        &self,
+        method: &'static str,
+        cost: usize,
+        call: impl Fn() -> F,
+    ) -> EnrichedClientResult<T>
+    where
+        F: Future<Output = EnrichedClientResult<T>>,
+    {
+        let span = tracing::debug_span!(
+            "main_node_rpc",
+            method,
+            cost,
+            attempts = tracing::field::Empty,
+            is_ok = tracing::field::Empty
+        );
+        async {
+            let mut attempt = 0;
+            let result = loop {
+                if let Some(rate_limiter) = self.rate_limiter {
+                    Self::wait_for_rate_limit(rate_limiter, cost).await;
+                }
+                let started_at = Instant::now();
+                let result = match tokio::time::timeout(self.timeout, call()).await {
+                    Ok(result) => {
+                        self.metrics.record_histogram(
+                            DetectorHistogram::RpcLatency { method },
+                            started_at.elapsed(),
+                        );
+                        result
+                    }
+                    Err(_) => Err(EnrichedClientError::new(RpcError::RequestTimeout, method)),
+                };
+                match result {
+                    Err(err) if is_transient_err(&err) && attempt < self.retries.max_retries => {
+                        let backoff = self
+                            .retries
+                            .backoff
+                            .saturating_mul(2_u32.saturating_pow(attempt as u32))
+                            .min(self.max_backoff);
+                        tracing::debug!(
+                            method,
+                            attempt,
+                            ?backoff,
+                            %err,
+                            "Transient error calling main node; retrying"
+                        );
+                        if !self.sleep_before_retry(backoff).await {
+                            tracing::debug!(method, "Stop signal received; aborting retries");
+                            break Err(err);
+                        }
+                        attempt += 1;
+                    }
+                    result => break result,
+                }
+            };
+            let span = tracing::Span::current();
+            span.record("attempts", attempt + 1);
+            span.record("is_ok", result.is_ok());
+            result
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Waits until `cost` requests fit into the rate limit. If `cost` exceeds the burst size of the limiter,
+    /// requests are consumed in burst-sized chunks.
+    async fn wait_for_rate_limit(rate_limiter: &RpcRateLimiter, cost: usize) {
+        let mut remaining = u32::try_from(cost).unwrap_or(u32::MAX);
+        while let Some(n) = NonZeroU32::new(remaining) {
+            match rate_limiter.until_n_ready(n).await {
+                Ok(()) => break,
+                Err(InsufficientCapacity(burst_size)) => {
+                    let burst = NonZeroU32::new(burst_size).expect("burst size is positive");
+                    rate_limiter
+                        .until_n_ready(burst)
+                        .await
+                        .expect("burst size fits into the rate limiter");
+                    remaining -= burst_size;
+                }
+            }
+        }
+    }
+
+    /// Sleeps for the specified duration. Returns `false` if a stop signal was received during the sleep.
+    async fn sleep_before_retry(&self, duration: Duration) -> bool {
+        let Some(stop_receiver) = self.stop_receiver else {
+            self.clock.sleep(duration).await;
+            return true;
+        };
+        let mut stop_receiver = stop_receiver.clone();
+        let stop_signal = async move {
+            if stop_receiver.wait_for(|&stop| stop).await.is_err() {
+                // The stop sender is dropped, so the sleep can no longer be interrupted.
+                future::pending::<()>().await;
+            }
+        };
+        tokio::select! {
+            () = self.clock.sleep(duration) => true,
+            () = stop_signal => false,
+        }
+    }
+}
+
+/// Retries of transient errors for each call to the main node performed by [`ReorgDetector`]. This is separate
+/// from (and is performed before) retrying the entire consistency check.
+#[derive(Debug, Clone, Copy, Default)]
+struct RpcRetries {
+    max_retries: usize,
+    /// Backoff before the first retry; doubled for each following retry.
+    backoff: Duration,
+}
+
+#[async_trait]
+impl MainNodeClient for TimeoutClient<'_> {
+    async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber> {
+        self.call("sealed_miniblock_number", || {
+            self.inner.sealed_miniblock_number()
+        })
+        .await
+    }
+
+    async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber> {
+        self.call("sealed_l1_batch_number", || {
+            self.inner.sealed_l1_batch_number()
+        })
+        .await
+    }
+
+    async fn miniblock_hash(&self, number: MiniblockNumber) -> EnrichedClientResult<Option<H256>> {
+        self.call("miniblock_hash", || self.inner.miniblock_hash(number))
+            .await
+    }
+
+    async fn miniblock_hashes(
+        &self,
+        range: RangeInclusive<MiniblockNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        let cost = range.end().0.saturating_sub(range.start().0) as usize + 1;
+        self.call_with_cost("miniblock_hashes", cost, || {
+            self.inner.miniblock_hashes(range.clone())
+        })
+        .await
+    }
+
+    async fn miniblock_parent_hash(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call("miniblock_parent_hash", || {
+            self.inner.miniblock_parent_hash(number)
+        })
+        .await
+    }
+
+    async fn l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call("l1_batch_root_hash", || {
+            self.inner.l1_batch_root_hash(number)
+        })
+        .await
+    }
+
+    async fn l1_batch_root_hashes(
+        &self,
+        range: RangeInclusive<L1BatchNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        let cost = range.end().0.saturating_sub(range.start().0) as usize + 1;
+        self.call_with_cost("l1_batch_root_hashes", cost, || {
+            self.inner.l1_batch_root_hashes(range.clone())
+        })
+        .await
+    }
+
+    async fn l1_batch_miniblock_range(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<(MiniblockNumber, MiniblockNumber)>> {
+        self.call("l1_batch_miniblock_range", || {
+            self.inner.l1_batch_miniblock_range(number)
+        })
+        .await
+    }
+
+    async fn protocol_version(&self) -> EnrichedClientResult<u16> {
+        self.call("protocol_version", || self.inner.protocol_version())
+            .await
+    }
+
+    async fn fork_id(&self) -> EnrichedClientResult<Option<H256>> {
+        self.call("fork_id", || self.inner.fork_id()).await
+    }
+
+    async fn miniblock_virtual_blocks(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<u32>> {
+        self.call("miniblock_virtual_blocks", || {
+            self.inner.miniblock_virtual_blocks(number)
+        })
+        .await
+    }
+
+    async fn l1_batch_details(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<L1BatchDetails>> {
+        self.call("l1_batch_details", || self.inner.l1_batch_details(number))
+            .await
+    }
+
+    async fn l1_batch_proven(&self, number: L1BatchNumber) -> EnrichedClientResult<Option<bool>> {
+        self.call("l1_batch_proven", || self.inner.l1_batch_proven(number))
+            .await
+    }
+
+    async fn l2_to_l1_logs_root_hash(&self, tx_hash: H256) -> EnrichedClientResult<Option<H256>> {
+        self.call("l2_to_l1_logs_root_hash", || {
+            self.inner.l2_to_l1_logs_root_hash(tx_hash)
+        })
+        .await
+    }
+
+    async fn l1_batch_commitment(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call("l1_batch_commitment", || {
+            self.inner.l1_batch_commitment(number)
+        })
+        .await
+    }
+
+    async fn miniblock_transaction_hashes(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<Vec<H256>>> {
+        self.call("miniblock_transaction_hashes", || {
+            self.inner.miniblock_transaction_hashes(number)
+        })
+        .await
+    }
+
+    async fn pending_miniblock(&self) -> EnrichedClientResult<Option<(MiniblockNumber, H256)>> {
+        self.call("pending_miniblock", || self.inner.pending_miniblock())
+            .await
+    }
+
+    async fn last_executed_l1_batch(&self) -> EnrichedClientResult<Option<L1BatchNumber>> {
+        self.call("last_executed_l1_batch", || {
+            self.inner.last_executed_l1_batch()
+        })
+        .await
+    }
+
+    async fn l1_batch_pass_through_data_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call("l1_batch_pass_through_data_hash", || {
+            self.inner.l1_batch_pass_through_data_hash(number)
+        })
+        .await
+    }
+}
+
+/// [`MainNodeClient`] failing over across multiple main node endpoints. Calls are sent to the current endpoint
+/// until it returns a transient error (e.g., a transport error), in which case the client rotates to the next
+/// endpoint in a round-robin fashion and retries the call. The call fails only if all endpoints fail.
+#[derive(Debug)]
+pub struct FailoverClient<C = HttpClient> {
+    clients: Vec<C>,
+    current: AtomicUsize,
+}
+
+impl<C: MainNodeClient> FailoverClient<C> {
+    /// Creates a client with the specified endpoints, starting from the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `clients` is empty.
+    pub fn new(clients: Vec<C>) -> Self {
+        assert!(
+            !clients.is_empty(),
+            "Failover client requires at least one endpoint"
+        );
+        Self {
+            clients,
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    async fn call<'a, T, F>(&'a self, method: impl Fn(&'a C) -> F) -> EnrichedClientResult<T>
+    where
+        F: Future<Output = EnrichedClientResult<T>> + 'a,
+    {
+        let len = self.clients.len();
+        let start = self.current.load(Ordering::Relaxed);
+        let mut last_err = None;
+        for offset in 0..len {
+            let index = (start + offset) % len;
+            match method(&self.clients[index]).await {
+                Err(err) if is_transient_err(&err) => {
+                    let next_index = (index + 1) % len;
+                    tracing::warn!(
+                        "Transient error calling main node endpoint #{index}, failing over to endpoint \
+                         #{next_index}: {err}"
+                    );
+                    self.current.store(next_index, Ordering::Relaxed);
+                    last_err = Some(err);
+                }
+                output => return output,
+            }
+        }
+        Err(last_err.expect("no endpoints were called"))
+    }
+}
+
+#[async_trait]
+impl<C: MainNodeClient> MainNodeClient for FailoverClient<C> {
+    async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber> {
+        self.call(|client| client.sealed_miniblock_number()).await
+    }
+
+    async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber> {
+        self.call(|client| client.sealed_l1_batch_number()).await
+    }
+
+    async fn miniblock_hash(&self, number: MiniblockNumber) -> EnrichedClientResult<Option<H256>> {
+        self.call(|client| client.miniblock_hash(number)).await
+    }
+
+    async fn miniblock_hashes(
+        &self,
+        range: RangeInclusive<MiniblockNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        self.call(|client| client.miniblock_hashes(range.clone()))
+            .await
+    }
+
+    async fn miniblock_parent_hash(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call(|client| client.miniblock_parent_hash(number))
+            .await
+    }
+
+    async fn l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call(|client| client.l1_batch_root_hash(number)).await
+    }
+
+    async fn l1_batch_root_hashes(
+        &self,
+        range: RangeInclusive<L1BatchNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        self.call(|client| client.l1_batch_root_hashes(range.clone()))
+            .await
+    }
+
+    async fn l1_batch_miniblock_range(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<(MiniblockNumber, MiniblockNumber)>> {
+        self.call(|client| client.l1_batch_miniblock_range(number))
+            .await
+    }
+
+    async fn protocol_version(&self) -> EnrichedClientResult<u16> {
+        self.call(|client| client.protocol_version()).await
+    }
+
+    async fn fork_id(&self) -> EnrichedClientResult<Option<H256>> {
+        self.call(|client| client.fork_id()).await
+    }
+
+    async fn miniblock_virtual_blocks(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<u32>> {
+        self.call(|client| client.miniblock_virtual_blocks(number))
+            .await
+    }
+
+    async fn l1_batch_details(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<L1BatchDetails>> {
+        self.call(|client| client.l1_batch_details(number)).await
+    }
+
+    async fn l1_batch_proven(&self, number: L1BatchNumber) -> EnrichedClientResult<Option<bool>> {
+        self.call(|client| client.l1_batch_proven(number)).await
+    }
+
+    async fn l2_to_l1_logs_root_hash(&self, tx_hash: H256) -> EnrichedClientResult<Option<H256>> {
+        self.call(|client| client.l2_to_l1_logs_root_hash(tx_hash))
+            .await
+    }
+
+    async fn l1_batch_commitment(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call(|client| client.l1_batch_commitment(number)).await
+    }
+
+    async fn miniblock_transaction_hashes(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<Vec<H256>>> {
+        self.call(|client| client.miniblock_transaction_hashes(number))
+            .await
+    }
+
+    async fn pending_miniblock(&self) -> EnrichedClientResult<Option<(MiniblockNumber, H256)>> {
+        self.call(|client| client.pending_miniblock()).await
+    }
+
+    async fn last_executed_l1_batch(&self) -> EnrichedClientResult<Option<L1BatchNumber>> {
+        self.call(|client| client.last_executed_l1_batch()).await
+    }
+
+    async fn l1_batch_pass_through_data_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.call(|client| client.l1_batch_pass_through_data_hash(number))
+            .await
+    }
+}
+
+/// Factory (re)creating main node clients for [`ReorgDetector`]. Used to recover from half-broken connections
+/// or to fail over to another main node URL.
+pub trait ClientFactory: fmt::Debug + Send + Sync {
+    /// Creates a new client.
+    fn create_client(&self) -> anyhow::Result<HttpClient>;
+}
+
+/// [`ClientFactory`] creating HTTP clients for a main node URL, with custom headers sent on each request.
+/// Can be used to run the detector against main nodes behind an authentication proxy or managed RPC providers.
+#[derive(Debug, Clone)]
+pub struct HttpClientFactory {
+    url: String,
+    headers: HeaderMap,
+}
+
+impl HttpClientFactory {
+    /// Creates a factory for the specified main node URL without custom headers.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Adds a header sent on each request to the main node. If a header with the same name was added before,
+    /// it is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header name or value is invalid.
+    pub fn with_header(mut self, name: &str, value: &str) -> anyhow::Result<Self> {
+        let name: HeaderName = name
+            .parse()
+            .with_context(|| format!("invalid header name `{name}`"))?;
+        let value = HeaderValue::from_str(value)
+            .with_context(|| format!("invalid value for header `{name}`"))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Adds an `Authorization` header with the specified bearer token. The token is marked as sensitive,
+    /// so it isn't output in debug representations of the factory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token contains characters not allowed in headers.
+    pub fn with_bearer_token(mut self, token: &str) -> anyhow::Result<Self> {
+        let mut value =
+            HeaderValue::from_str(&format!("Bearer {token}")).context("invalid bearer token")?;
+        value.set_sensitive(true);
+        self.headers.insert(AUTHORIZATION, value);
+        Ok(self)
+    }
+}
+
+impl ClientFactory for HttpClientFactory {
+    fn create_client(&self) -> anyhow::Result<HttpClient> {
+        HttpClientBuilder::default()
+            .set_headers(self.headers.clone())
+            .build(&self.url)
+            .with_context(|| format!("failed creating HTTP client for `{}`", self.url))
+    }
+}
+
+/// Object-safe version of [`ClientFactory`] creating boxed clients; necessary to mock clients in tests.
+trait CreateMainNodeClient: fmt::Debug + Send + Sync {
+    fn create(&self) -> anyhow::Result<Box<dyn MainNodeClient>>;
+}
+
+impl<T: ClientFactory> CreateMainNodeClient for T {
+    fn create(&self) -> anyhow::Result<Box<dyn MainNodeClient>> {
+        Ok(Box::new(self.create_client()?))
+    }
+}
+
+/// Divergence between local and main node data that doesn't necessarily mean a reorg, but is worth reporting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReorgSuspicion {
+    /// Number of virtual blocks associated with a miniblock differs from that on the main node.
+    VirtualBlocksMismatch {
+        miniblock: MiniblockNumber,
+        local: u32,
+        remote: u32,
+    },
+    /// Latest protocol version known locally differs from that on the main node. This may explain hash divergences.
+    ProtocolVersionMismatch { local: u16, remote: u16 },
+    /// Proof status of an L1 batch differs from that on the main node. This is not a reorg per se, but may indicate
+    /// that the main node has proven or reverted a batch unbeknownst to the local node.
+    DivergentProofStatus {
+        l1_batch: L1BatchNumber,
+        local_proven: bool,
+        remote_proven: bool,
+    },
+    /// Some of the compared fields in L1 batch details differ from those on the main node.
+    FieldDivergence(FieldDivergence),
+    /// Divergence was localized to miniblocks after the last sealed local L1 batch, so there is nothing to roll back.
+    TipChurn { l1_batch: L1BatchNumber },
+    /// Reorg was localized, but has not been observed on the required number of consecutive checks yet;
+    /// see [`ReorgDetector::with_confirmation_checks()`].
+    UnconfirmedReorg {
+        diverged_l1_batch: L1BatchNumber,
+        observations: u32,
+        required_observations: u32,
+    },
+    /// Hash of the pending miniblock on the main node differs from the hash of the same miniblock in the local DB.
+    /// Since pending state is volatile, this doesn't trigger a rollback.
+    PendingMiniblockMismatch {
+        miniblock: MiniblockNumber,
+        local_hash: H256,
+        remote_hash: H256,
+    },
+}
+
+/// Handler of [`ReorgDetector`] events. By default, events are reported via the detector health check;
+/// custom handlers can be added using [`ReorgDetectorBuilder::event_handler()`].
+///
+/// Handlers are async, so they can perform I/O (e.g., persist events to Postgres or call a webhook).
+/// Handler methods are awaited inline by the detector, so slow handlers delay consistency checks.
+#[async_trait]
+pub trait HandleReorgDetectorEvent: fmt::Debug + Send + Sync {
+    /// Reports that the detector has started.
+    async fn initialize(&mut self);
+
+    /// Reports that the last checked miniblock and L1 batch match the main node.
+    async fn update_correct_block(
+        &mut self,
+        last_correct_miniblock: MiniblockNumber,
+        last_correct_l1_batch: L1BatchNumber,
+    );
+
+    /// Reports that a divergence from the main node was detected; the reorg is not localized yet.
+    async fn report_divergence(&mut self, diverged_l1_batch: L1BatchNumber);
+
+    /// Reports that a reorg was localized. `last_correct_miniblock` is set if miniblock hashes have diverged.
+    async fn report_localized_reorg(
+        &mut self,
+        last_correct_l1_batch: L1BatchNumber,
+        last_correct_miniblock: Option<MiniblockNumber>,
+    );
+
+    /// Reports a divergence that doesn't necessarily mean a reorg.
+    async fn report_suspicion(&mut self, suspicion: ReorgSuspicion);
+
+    /// Reports that a check has failed with a transient error and will be retried. `consecutive_failures`
+    /// is the number of transient failures since the last successful check.
+    async fn report_transient_failure(
+        &mut self,
+        consecutive_failures: usize,
+        last_correct_block: Option<(MiniblockNumber, L1BatchNumber)>,
+    );
+
+    /// Reports that the detector has failed with the specified error and is about to exit.
+    async fn report_failure(&mut self, error: &str);
+
+    /// Reports that the detector has received a stop signal.
+    async fn start_shutting_down(&mut self);
+}
+
+/// Stable value of the `reason` field in health details set while a reorg is detected.
+const REORG_HEALTH_REASON: &str = "reorg_detected";
+
+/// Default implementation of [`HandleReorgDetectorEvent`] that updates the detector health check. Metrics are
+/// reported separately via the detector [`MetricsSink`].
+///
+/// While a reorg is detected, the health status is [`HealthStatus::Affected`], and health details contain
+/// the `"reason": "reorg_detected"` field distinguishing it from other degraded states. The health is reset
+/// to [`HealthStatus::Ready`] once a subsequent check passes (e.g., in the [dry-run mode](ReorgDetector::with_dry_run())).
+#[async_trait]
+impl HandleReorgDetectorEvent for HealthUpdater {
+    async fn initialize(&mut self) {
+        self.update(Health::from(HealthStatus::Ready));
+    }
+
+    async fn update_correct_block(
+        &mut self,
+        last_correct_miniblock: MiniblockNumber,
+        last_correct_l1_batch: L1BatchNumber,
+    ) {
+        tracing::trace!(
+            "No reorg at miniblock #{last_correct_miniblock}, L1 batch #{last_correct_l1_batch}"
+        );
+        let health_details = serde_json::json!({
+            "last_correct_miniblock": last_correct_miniblock,
+            "last_correct_l1_batch": last_correct_l1_batch,
+            "checked_at": chrono::Utc::now(),
+        });
+        self.update(Health::from(HealthStatus::Ready).with_details(health_details));
+    }
+
+    async fn report_divergence(&mut self, diverged_l1_batch: L1BatchNumber) {
+        let health_details = serde_json::json!({
+            "reason": REORG_HEALTH_REASON,
+            "diverged_l1_batch": diverged_l1_batch,
+        });
+        self.update(Health::from(HealthStatus::Affected).with_details(health_details));
+    }
+
+    async fn report_localized_reorg(
+        &mut self,
+        last_correct_l1_batch: L1BatchNumber,
+        last_correct_miniblock: Option<MiniblockNumber>,
+    ) {
+        let mut health_details = serde_json::json!({
+            "reason": REORG_HEALTH_REASON,
+            "diverged_l1_batch": last_correct_l1_batch + 1,
+            "last_correct_l1_batch": last_correct_l1_batch,
+        });
+        if let Some(last_correct_miniblock) = last_correct_miniblock {
+            health_details["last_correct_miniblock"] = last_correct_miniblock.0.into();
+        }
+        self.update(Health::from(HealthStatus::Affected).with_details(health_details));
+    }
+
+    async fn report_suspicion(&mut self, suspicion: ReorgSuspicion) {
+        match suspicion {
+            ReorgSuspicion::VirtualBlocksMismatch {
+                miniblock,
+                local,
+                remote,
+            } => {
+                tracing::warn!(
+                    miniblock = miniblock.0,
+                    local_virtual_blocks = local,
+                    remote_virtual_blocks = remote,
+                    "Possible reorg: local number of virtual blocks doesn't match the number from main node"
+                );
+            }
+            ReorgSuspicion::ProtocolVersionMismatch { local, remote } => {
+                tracing::warn!(
+                    "Latest local protocol version {local} doesn't match the version on main node {remote}; \
+                     this may cause hash divergences not related to reorgs"
+                );
+            }
+            ReorgSuspicion::DivergentProofStatus {
+                l1_batch,
+                local_proven,
+                remote_proven,
+            } => {
+                tracing::info!(
+                    l1_batch = l1_batch.0,
+                    local_proven,
+                    remote_proven,
+                    "Proof status of L1 batch differs from main node"
+                );
+            }
+            ReorgSuspicion::FieldDivergence(divergence) => {
+                tracing::warn!(
+                    l1_batch = divergence.l1_batch.0,
+                    fields = ?divergence.fields,
+                    "Details of L1 batch differ from main node"
+                );
+            }
+            ReorgSuspicion::UnconfirmedReorg {
+                diverged_l1_batch,
+                observations,
+                required_observations,
+            } => {
+                tracing::warn!(
+                    diverged_l1_batch = diverged_l1_batch.0,
+                    observations,
+                    required_observations,
+                    "Reorg is not confirmed by enough consecutive checks yet; it may be caused by a race \
+                     with the main node sealing blocks"
+                );
+            }
+            ReorgSuspicion::TipChurn { l1_batch } => {
+                tracing::warn!(
+                    l1_batch = l1_batch.0,
+                    "Divergence is limited to miniblocks after the last sealed L1 batch, which matches \
+                     the main node; treating it as tip churn rather than a reorg"
+                );
+            }
+            ReorgSuspicion::PendingMiniblockMismatch {
+                miniblock,
+                local_hash,
+                remote_hash,
+            } => {
+                tracing::warn!(
+                    miniblock = miniblock.0,
+                    ?local_hash,
+                    ?remote_hash,
+                    "Possible reorg: local miniblock hash doesn't match the hash of the pending miniblock on main node"
+                );
+            }
+        }
+    }
+
+    async fn report_transient_failure(
+        &mut self,
+        consecutive_failures: usize,
+        last_correct_block: Option<(MiniblockNumber, L1BatchNumber)>,
+    ) {
+        let mut health_details = serde_json::json!({
+            "consecutive_transient_failures": consecutive_failures,
+        });
+        if let Some((last_correct_miniblock, last_correct_l1_batch)) = last_correct_block {
+            health_details["last_correct_miniblock"] = last_correct_miniblock.0.into();
+            health_details["last_correct_l1_batch"] = last_correct_l1_batch.0.into();
+        }
+        self.update(Health::from(HealthStatus::Affected).with_details(health_details));
+    }
+
+    async fn report_failure(&mut self, error: &str) {
+        let health_details = serde_json::json!({
+            "error": error,
+        });
+        self.update(Health::from(HealthStatus::NotReady).with_details(health_details));
+    }
+
+    async fn start_shutting_down(&mut self) {
+        self.update(HealthStatus::ShuttingDown.into());
+    }
+}
+
+/// Lifecycle event of [`ReorgDetector`] published via a [broadcast channel](ReorgDetector::with_event_broadcast()).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReorgEvent {
+    /// The detector has started.
+    Initialized,
+    /// The last checked miniblock and L1 batch match the main node.
+    CorrectBlockUpdated {
+        last_correct_miniblock: MiniblockNumber,
+        last_correct_l1_batch: L1BatchNumber,
+    },
+    /// A divergence from the main node was detected; the reorg is not localized yet.
+    DivergenceReported { diverged_l1_batch: L1BatchNumber },
+    /// A reorg was localized. `last_correct_miniblock` is set if miniblock hashes have diverged,
+    /// in which case the first diverged miniblock is localized as well.
+    ReorgLocalized {
+        last_correct_l1_batch: L1BatchNumber,
+        last_correct_miniblock: Option<MiniblockNumber>,
+    },
+    /// A check has failed with a transient error and will be retried.
+    TransientFailure { consecutive_failures: usize },
+    /// The detector has failed and is about to exit.
+    Failed { error: String },
+    /// The detector has received a stop signal.
+    ShuttingDown,
+}
+
+/// Event handler publishing events to a broadcast channel in addition to the wrapped handler.
+#[derive(Debug)]
+struct BroadcastingEventHandler {
+    inner: Box<dyn HandleReorgDetectorEvent>,
+    sender: broadcast::Sender<ReorgEvent>,
+}
+
+impl BroadcastingEventHandler {
+    fn publish(&self, event: ReorgEvent) {
+        // An error means that there are no subscribers at the moment, which is fine.
+        self.sender.send(event).ok();
+    }
+}
+
+#[async_trait]
+impl HandleReorgDetectorEvent for BroadcastingEventHandler {
+    async fn initialize(&mut self) {
+        self.inner.initialize().await;
+        self.publish(ReorgEvent::Initialized);
+    }
+
+    async fn update_correct_block(
+        &mut self,
+        last_correct_miniblock: MiniblockNumber,
+        last_correct_l1_batch: L1BatchNumber,
+    ) {
+        self.inner
+            .update_correct_block(last_correct_miniblock, last_correct_l1_batch)
+            .await;
+        self.publish(ReorgEvent::CorrectBlockUpdated {
+            last_correct_miniblock,
+            last_correct_l1_batch,
+        });
+    }
+
+    async fn report_divergence(&mut self, diverged_l1_batch: L1BatchNumber) {
+        self.inner.report_divergence(diverged_l1_batch).await;
+        self.publish(ReorgEvent::DivergenceReported { diverged_l1_batch });
+    }
+
+    async fn report_localized_reorg(
+        &mut self,
+        last_correct_l1_batch: L1BatchNumber,
+        last_correct_miniblock: Option<MiniblockNumber>,
+    ) {
+        self.inner
+            .report_localized_reorg(last_correct_l1_batch, last_correct_miniblock)
+            .await;
+        self.publish(ReorgEvent::ReorgLocalized {
+            last_correct_l1_batch,
+            last_correct_miniblock,
+        });
+    }
+
+    async fn report_suspicion(&mut self, suspicion: ReorgSuspicion) {
+        // Suspicions are internal diagnostics and are not published.
+        self.inner.report_suspicion(suspicion).await;
+    }
+
+    async fn report_transient_failure(
+        &mut self,
+        consecutive_failures: usize,
+        last_correct_block: Option<(MiniblockNumber, L1BatchNumber)>,
+    ) {
+        self.inner
+            .report_transient_failure(consecutive_failures, last_correct_block)
+            .await;
+        self.publish(ReorgEvent::TransientFailure {
+            consecutive_failures,
+        });
+    }
+
+    async fn report_failure(&mut self, error: &str) {
+        self.inner.report_failure(error).await;
+        self.publish(ReorgEvent::Failed {
+            error: error.to_owned(),
+        });
+    }
+
+    async fn start_shutting_down(&mut self) {
+        self.inner.start_shutting_down().await;
+        self.publish(ReorgEvent::ShuttingDown);
+    }
+}
+
+/// Event handler forwarding events to multiple handlers in the order they were provided.
+#[derive(Debug)]
+pub struct CompositeEventHandler {
+    handlers: Vec<Box<dyn HandleReorgDetectorEvent>>,
+}
+
+impl CompositeEventHandler {
+    pub fn new(handlers: Vec<Box<dyn HandleReorgDetectorEvent>>) -> Self {
+        Self { handlers }
+    }
+}
+
+#[async_trait]
+impl HandleReorgDetectorEvent for CompositeEventHandler {
+    async fn initialize(&mut self) {
+        for handler in &mut self.handlers {
+            handler.initialize().await;
+        }
+    }
+
+    async fn update_correct_block(
+        &mut self,
+        last_correct_miniblock: MiniblockNumber,
+        last_correct_l1_batch: L1BatchNumber,
+    ) {
+        for handler in &mut self.handlers {
+            handler
+                .update_correct_block(last_correct_miniblock, last_correct_l1_batch)
+                .await;
+        }
+    }
+
+    async fn report_divergence(&mut self, diverged_l1_batch: L1BatchNumber) {
+        for handler in &mut self.handlers {
+            handler.report_divergence(diverged_l1_batch).await;
+        }
+    }
+
+    async fn report_localized_reorg(
+        &mut self,
+        last_correct_l1_batch: L1BatchNumber,
+        last_correct_miniblock: Option<MiniblockNumber>,
+    ) {
+        for handler in &mut self.handlers {
+            handler
+                .report_localized_reorg(last_correct_l1_batch, last_correct_miniblock)
+                .await;
+        }
+    }
+
+    async fn report_suspicion(&mut self, suspicion: ReorgSuspicion) {
+        for handler in &mut self.handlers {
+            handler.report_suspicion(suspicion.clone()).await;
+        }
+    }
+
+    async fn report_transient_failure(
+        &mut self,
+        consecutive_failures: usize,
+        last_correct_block: Option<(MiniblockNumber, L1BatchNumber)>,
+    ) {
+        for handler in &mut self.handlers {
+            handler
+                .report_transient_failure(consecutive_failures, last_correct_block)
+                .await;
+        }
+    }
+
+    async fn report_failure(&mut self, error: &str) {
+        for handler in &mut self.handlers {
+            handler.report_failure(error).await;
+        }
+    }
+
+    async fn start_shutting_down(&mut self) {
+        for handler in &mut self.handlers {
+            handler.start_shutting_down().await;
+        }
+    }
+}
+
+bitflags! {
+    /// Set of fields in L1 batch details compared by [`ReorgDetector`] with the main node.
+    pub struct FieldSet: u32 {
+        const ROOT_HASH = 0b_0000_0001;
+        const TIMESTAMP = 0b_0000_0010;
+        const TX_COUNTS = 0b_0000_0100;
+        const GAS_PRICES = 0b_0000_1000;
+        const COMMIT_TX_HASH = 0b_0001_0000;
+        const PROVE_TX_HASH = 0b_0010_0000;
+        const EXECUTE_TX_HASH = 0b_0100_0000;
+    }
+}
+
+impl FieldSet {
+    /// Returns fields from this set that differ between the provided details.
+    fn diverged_fields(self, local: &BlockDetailsBase, remote: &BlockDetailsBase) -> Self {
+        let mut diverged = Self::empty();
+        diverged.set(Self::ROOT_HASH, local.root_hash != remote.root_hash);
+        diverged.set(Self::TIMESTAMP, local.timestamp != remote.timestamp);
+        diverged.set(
+            Self::TX_COUNTS,
+            local.l1_tx_count != remote.l1_tx_count || local.l2_tx_count != remote.l2_tx_count,
+        );
+        diverged.set(
+            Self::GAS_PRICES,
+            local.l1_gas_price != remote.l1_gas_price
+                || local.l2_fair_gas_price != remote.l2_fair_gas_price,
+        );
+        diverged.set(
+            Self::COMMIT_TX_HASH,
+            local.commit_tx_hash != remote.commit_tx_hash,
+        );
+        diverged.set(
+            Self::PROVE_TX_HASH,
+            local.prove_tx_hash != remote.prove_tx_hash,
+        );
+        diverged.set(
+            Self::EXECUTE_TX_HASH,
+            local.execute_tx_hash != remote.execute_tx_hash,
+        );
+        diverged & self
+    }
+}
+
+/// Divergence of L1 batch details from the main node found by [`ReorgDetector`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldDivergence {
+    pub l1_batch: L1BatchNumber,
+    /// All compared fields that differ from the main node.
+    pub fields: FieldSet,
+}
+
+/// Cadence of comparisons performed by [`ReorgDetector`], expressed as the number of iterations between
+/// consecutive comparisons of each type. E.g., the cadence of 3 means that the comparison is performed
+/// on iterations 0, 3, 6, etc. Zero values are treated as 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComparisonCadence {
+    /// Comparison of the latest L1 batch root hash (including the anchor and logs tree root checks).
+    pub l1_batch_root_hash: u32,
+    /// Comparison of the latest miniblock hash and of miniblocks sampled from the checked L1 batch.
+    pub miniblock_hash: u32,
+    /// Comparison of [L1 batch detail fields](ReorgDetector::with_field_comparison()).
+    pub extended_fields: u32,
+}
+
+impl Default for ComparisonCadence {
+    fn default() -> Self {
+        Self {
+            l1_batch_root_hash: 1,
+            miniblock_hash: 1,
+            extended_fields: 1,
+        }
+    }
+}
+
+impl ComparisonCadence {
+    fn is_due(cadence: u32, iteration: u64) -> bool {
+        iteration % u64::from(cadence.max(1)) == 0
+    }
+}
+
+/// Gauge reported by [`ReorgDetector`] via [`MetricsSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DetectorGauge {
+    /// Number of the last checked miniblock matching the main node.
+    LastCorrectMiniblock,
+    /// Number of the last checked L1 batch matching the main node.
+    LastCorrectL1Batch,
+    /// Number of the highest L1 batch with the root hash verified against the main node.
+    MaxVerifiedL1Batch,
+    /// Number of L1 batches between the last correct L1 batch and the diverged L1 batch for the last detected reorg.
+    ReorgDepth,
+    /// Number of seconds elapsed since the last successful consistency check.
+    SecondsSinceLastSuccessfulCheck,
+}
+
+/// Histogram reported by [`ReorgDetector`] via [`MetricsSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DetectorHistogram {
+    /// Latency of a single consistency check.
+    CheckLatency,
+    /// Latency of a single main node RPC call attempt, labeled by the [`MainNodeClient`] method. Attempts
+    /// that have timed out are not observed.
+    RpcLatency { method: &'static str },
+}
+
+/// Histogram of counts reported by [`ReorgDetector`] via [`MetricsSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DetectorCountHistogram {
+    /// Number of L1 batch root hash comparisons made during a single reorg localization.
+    LocalizationProbes,
+}
+
+/// Counter reported by [`ReorgDetector`] via [`MetricsSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DetectorCounter {
+    /// Number of detected and localized reorgs.
+    DetectedReorgs,
+    /// Number of consistency checks aborted by the watchdog.
+    WatchdogTimeouts,
+    /// Number of failures reacquiring a DB connection during reorg localization.
+    StorageReacquisitionFailures,
+    /// Number of root hash checks for L1 batches missing on the main node.
+    MissingRemoteL1Batches,
+}
+
+/// Backend receiving metrics emitted by [`ReorgDetector`].
+pub trait MetricsSink: fmt::Debug + Send + Sync {
+    /// Sets the gauge to the specified value.
+    fn record_gauge(&self, gauge: DetectorGauge, value: u64);
+    /// Records an observation for the histogram.
+    fn record_histogram(&self, histogram: DetectorHistogram, value: Duration);
+    /// Records an observation for the count histogram.
+    fn record_count_histogram(&self, histogram: DetectorCountHistogram, value: usize);
+    /// Increments the counter by 1.
+    fn increment_counter(&self, counter: DetectorCounter);
+}
+
+/// Default [`MetricsSink`] reporting to the global external node metrics.
+#[derive(Debug)]
+pub struct GlobalMetricsSink;
+
+impl MetricsSink for GlobalMetricsSink {
+    fn record_gauge(&self, gauge: DetectorGauge, value: u64) {
+        let gauge = match gauge {
+            DetectorGauge::LastCorrectMiniblock => {
+                &EN_METRICS.last_correct_miniblock[&CheckerComponent::ReorgDetector]
+            }
+            DetectorGauge::LastCorrectL1Batch => {
+                &EN_METRICS.last_correct_batch[&CheckerComponent::ReorgDetector]
+            }
+            DetectorGauge::MaxVerifiedL1Batch => &EN_METRICS.reorg_detector_max_verified_batch,
+            DetectorGauge::ReorgDepth => &EN_METRICS.reorg_depth[&CheckerComponent::ReorgDetector],
+            DetectorGauge::SecondsSinceLastSuccessfulCheck => {
+                &EN_METRICS.reorg_detector_seconds_since_last_successful_check
+            }
+        };
+        gauge.set(value);
+    }
+
+    fn record_histogram(&self, histogram: DetectorHistogram, value: Duration) {
+        match histogram {
+            DetectorHistogram::CheckLatency => {
+                EN_METRICS.reorg_detector_check_latency.observe(value);
+            }
+            DetectorHistogram::RpcLatency { method } => {
+                EN_METRICS.reorg_detector_rpc_latency[&method].observe(value);
+            }
+        }
+    }
+
+    fn record_count_histogram(&self, histogram: DetectorCountHistogram, value: usize) {
+        match histogram {
+            DetectorCountHistogram::LocalizationProbes => {
+                EN_METRICS.reorg_detector_localization_probes.observe(value);
+            }
+        }
+    }
+
+    fn increment_counter(&self, counter: DetectorCounter) {
+        match counter {
+            DetectorCounter::DetectedReorgs => {
+                EN_METRICS.reorg_detector_detected_reorgs.inc();
+            }
+            DetectorCounter::WatchdogTimeouts => {
+                EN_METRICS.reorg_detector_watchdog_timeouts.inc();
+            }
+            DetectorCounter::StorageReacquisitionFailures => {
+                EN_METRICS
+                    .reorg_detector_storage_reacquisition_failures
+                    .inc();
+            }
+            DetectorCounter::MissingRemoteL1Batches => {
+                EN_METRICS.missing_remote_l1_batches[&CheckerComponent::ReorgDetector].inc();
+            }
+        }
+    }
+}
+
+/// [`MetricsSink`] discarding all metrics.
+#[derive(Debug)]
+pub struct NoopMetricsSink;
+
+impl MetricsSink for NoopMetricsSink {
+    fn record_gauge(&self, _gauge: DetectorGauge, _value: u64) {
+        // Do nothing
+    }
+
+    fn record_histogram(&self, _histogram: DetectorHistogram, _value: Duration) {
+        // Do nothing
+    }
+
+    fn record_count_histogram(&self, _histogram: DetectorCountHistogram, _value: usize) {
+        // Do nothing
+    }
+
+    fn increment_counter(&self, _counter: DetectorCounter) {
+        // Do nothing
+    }
+}
+
+/// Timer used by [`ReorgDetector`] to sleep between consistency checks and before retries. Allows driving time
+/// deterministically in tests.
+#[async_trait]
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Sleeps for the specified duration.
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Default [`Clock`] based on the Tokio timer.
+#[derive(Debug)]
+pub struct TokioClock;
+
+#[async_trait]
+impl Clock for TokioClock {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Strategy selecting L1 batches to probe when localizing a reorg in [`ReorgDetector`].
+pub trait ProbeStrategy: fmt::Debug + Send + Sync {
+    /// Selects the next L1 batch to probe. `known_valid` is known to match the main node, and `diverged`
+    /// is known to diverge from it. The returned batch should lie strictly between these bounds;
+    /// if it doesn't, it will be clamped to them.
+    fn next_probe(&self, known_valid: L1BatchNumber, diverged: L1BatchNumber) -> L1BatchNumber;
+}
+
+/// Independent source of L1 batch root hashes (e.g., L1 or a trusted checkpoint) used by [`ReorgDetector`]
+/// to verify the data that the local node and the main node agree on.
+#[async_trait]
+pub trait L1BatchAnchor: fmt::Debug + Send + Sync {
+    /// Returns the root hash of the specified L1 batch, or `None` if the anchor has no data for it.
+    async fn l1_batch_root_hash(&self, number: L1BatchNumber) -> anyhow::Result<Option<H256>>;
+}
+
+/// Formatter for operator-facing messages produced by [`ReorgDetector`], e.g. to customize or translate
+/// the guidance in them. Default method implementations return the standard English messages.
+pub trait MessageFormatter: fmt::Debug + Send + Sync {
+    /// Formats a message for a fatal error returned from [`ReorgDetector::run()`]. The structured error
+    /// can still be obtained from the returned [`anyhow::Error`] by downcasting it to [`HashMatchError`].
+    fn format_error(&self, err: &HashMatchError) -> String {
+        err.to_string()
+    }
+
+    /// Formats a message for a detected and localized reorg.
+    fn format_reorg(&self, last_correct_l1_batch: L1BatchNumber) -> String {
+        format!("Reorg localized: last correct L1 batch is #{last_correct_l1_batch}")
+    }
+}
+
+#[derive(Debug)]
+struct DefaultMessageFormatter;
+
+impl MessageFormatter for DefaultMessageFormatter {}
+
+/// Callback invoked by [`ReorgDetector`] with the last correct L1 batch once a reorg is localized.
+struct ReorgCallback(Box<dyn Fn(L1BatchNumber) + Send + Sync>);
+
+impl fmt::Debug for ReorgCallback {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ReorgCallback(_)")
+    }
+}
+
+/// Classifier of errors occurring in [`ReorgDetector::run()`] into transient and fatal ones.
+struct ErrorClassifier(Box<dyn Fn(&Error) -> bool + Send + Sync>);
+
+impl fmt::Debug for ErrorClassifier {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ErrorClassifier(_)")
+    }
+}
+
+/// Wrapper displaying a hash in logs, optionally truncated to the specified number of leading and trailing bytes.
+#[derive(Debug, Clone, Copy)]
+struct DisplayHash {
+    hash: H256,
+    truncation: Option<usize>,
+}
+
+impl fmt::Display for DisplayHash {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.hash.as_bytes();
+        match self.truncation {
+            Some(len) if 2 * len < bytes.len() => write!(
+                formatter,
+                "0x{}..{}",
+                hex::encode(&bytes[..len]),
+                hex::encode(&bytes[bytes.len() - len..])
+            ),
+            _ => write!(formatter, "{:?}", self.hash),
+        }
+    }
+}
+
+/// Output of hash match methods in [`ReorgDetector`].
+#[derive(Debug)]
+enum MatchOutput {
+    Match,
+    Mismatch(DivergenceKind),
+    NoRemoteReference,
+}
+
+/// Reorg reported in the [dry-run mode](ReorgDetector::with_dry_run()). Used to not localize and report
+/// the same reorg on each check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DryRunReorg {
+    last_correct_l1_batch: L1BatchNumber,
+    /// Local and remote root hashes of the last correct and the first diverged L1 batches.
+    boundary_hashes: [(Option<H256>, Option<H256>); 2],
+}
+
+/// Result of a read-only check of the latest sealed blocks used in the concurrent check mode.
+#[derive(Debug, Clone, Copy)]
+struct SealedBlocksProbe {
+    l1_batch: L1BatchNumber,
+    miniblock: MiniblockNumber,
+    hashes_match: bool,
+}
+
+/// Latest local blocks to be checked against the main node. Read from a single DB snapshot, so that block numbers
+/// and hashes are mutually consistent even if new blocks are sealed concurrently.
+#[derive(Debug, Clone, Copy)]
+struct LocalSnapshot {
+    l1_batch: L1BatchNumber,
+    l1_batch_root_hash: H256,
+    miniblock: MiniblockNumber,
+    miniblock_hash: H256,
+    last_finalized_l1_batch: Option<L1BatchNumber>,
+}
+
+/// Main node response captured in the [RPC trace](ReorgDetector::recent_rpc_trace()).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RpcTraceResponse {
+    SealedMiniblockNumber(MiniblockNumber),
+    SealedL1BatchNumber(L1BatchNumber),
+    MiniblockHash {
+        number: MiniblockNumber,
+        hash: Option<H256>,
+    },
+    L1BatchRootHash {
+        number: L1BatchNumber,
+        hash: Option<H256>,
+    },
+}
+
+/// Entry of the [RPC trace](ReorgDetector::recent_rpc_trace()).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RpcTraceEntry {
+    /// Time at which the response was received.
+    pub timestamp: SystemTime,
+    pub response: RpcTraceResponse,
+}
+
+/// Bounded buffer of the latest main node responses.
+#[derive(Debug)]
+struct RpcTrace {
+    capacity: usize,
+    entries: Mutex<VecDeque<RpcTraceEntry>>,
+}
+
+impl RpcTrace {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn push(&self, response: RpcTraceResponse) {
+        let mut entries = self.entries.lock().expect("RPC trace is poisoned");
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(RpcTraceEntry {
+            timestamp: SystemTime::now(),
+            response,
+        });
+    }
+}
+
+/// Result of a [forensic scan](ReorgDetector::forensic_scan()) of the local DB.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DivergenceReport {
+    /// Earliest L1 batch with metadata in the local DB, or the earliest L1 batch within
+    /// the [max verified age](ReorgDetector::with_max_verify_age()) if it is set.
+    pub earliest_l1_batch: L1BatchNumber,
+    /// Latest L1 batch present both in the local DB and on the main node, which was used for the check.
+    pub checked_l1_batch: L1BatchNumber,
+    /// Last L1 batch matching the main node, or `None` if even the earliest local L1 batch has diverged.
+    pub last_correct_l1_batch: Option<L1BatchNumber>,
+    /// First L1 batch diverged from the main node, or `None` if all checked L1 batches match.
+    pub first_diverged_l1_batch: Option<L1BatchNumber>,
+    /// Hash that has diverged for the [first diverged L1 batch](Self::first_diverged_l1_batch).
+    pub diverged_hash: Option<DivergedHash>,
+}
+
+/// Difference between transactions in a miniblock in the local DB and on the main node.
+#[derive(Debug, Clone, PartialEq)]
+struct MiniblockTransactionsDiff {
+    miniblock: MiniblockNumber,
+    /// Transactions present only in the local DB.
+    local_only: Vec<H256>,
+    /// Transactions present only on the main node.
+    remote_only: Vec<H256>,
+    /// Whether transactions present both locally and on the main node are ordered differently.
+    reordered: bool,
+}
+
+impl MiniblockTransactionsDiff {
+    fn new(miniblock: MiniblockNumber, local_hashes: &[H256], remote_hashes: &[H256]) -> Self {
+        let local_only: Vec<_> = local_hashes
+            .iter()
+            .filter(|hash| !remote_hashes.contains(hash))
+            .copied()
+            .collect();
+        let remote_only: Vec<_> = remote_hashes
+            .iter()
+            .filter(|hash| !local_hashes.contains(hash))
+            .copied()
+            .collect();
+        let common_local = local_hashes
+            .iter()
+            .filter(|hash| !local_only.contains(hash));
+        let common_remote = remote_hashes
+            .iter()
+            .filter(|hash| !remote_only.contains(hash));
+        let reordered = !common_local.eq(common_remote);
+        Self {
+            miniblock,
+            local_only,
+            remote_only,
+            reordered,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.local_only.is_empty() && self.remote_only.is_empty() && !self.reordered
+    }
+}
+
+/// L1 batch hash compared with the main node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergedHash {
+    /// State root hash. Always compared.
+    RootHash,
+    /// L2-to-L1 logs Merkle tree root; see [`ReorgDetector::with_logs_tree_root_comparison()`].
+    LogsTreeRoot,
+    /// L1 batch commitment; see [`ReorgDetector::with_commitment_comparison()`].
+    Commitment,
+    /// Hash of the L1 batch pass-through data; see [`ReorgDetector::with_pass_through_data_comparison()`].
+    PassThroughData,
+    /// Number of miniblocks in the L1 batch (not a hash per se);
+    /// see [`ReorgDetector::with_miniblock_count_comparison()`].
+    MiniblockCount,
+}
+
+/// Hash divergence from the main node that has triggered reorg detection. Allows to categorize divergences,
+/// e.g. state divergence vs ordering of transactions in miniblocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// State root hash of an L1 batch has diverged.
+    RootHash {
+        l1_batch: L1BatchNumber,
+        local: H256,
+        remote: H256,
+    },
+    /// Miniblock hash has diverged.
+    MiniblockHash {
+        miniblock: MiniblockNumber,
+        local: H256,
+        remote: H256,
+    },
+    /// Pass-through data hash of an L1 batch has diverged; see [`ReorgDetector::with_pass_through_data_comparison()`].
+    PassThroughDataHash {
+        l1_batch: L1BatchNumber,
+        local: H256,
+        remote: H256,
+    },
+}
+
+/// This is a component that is responsible for detecting the batch re-orgs.
+/// Batch re-org is a rare event of manual intervention, when the node operator
+/// decides to revert some of the not yet finalized batches for some reason
+/// (e.g. inability to generate a proof), and then potentially
+/// re-organize transactions in them to fix the problem.
+///
+/// To detect them, we constantly check the latest sealed batch root hash,
+/// and in the event of mismatch, we know that there has been a re-org.
+/// We then perform a binary search to find the latest correct block
+/// and revert all batches after it, to keep being consistent with the main node.
+///
+/// This is the only component that is expected to finish its execution
+/// in the even of re-org, since we have to restart the node after a rollback is performed,
+/// and is special-cased in the `zksync_external_node` crate.
+///
+/// Unless [reorg persistence](Self::with_reorg_persistence()) is enabled, the detector only reads from the local DB,
+/// so it can be pointed to a read-only replica or a restored DB snapshot; see [`Self::forensic_scan()`].
+#[derive(Debug)]
+pub struct ReorgDetector {
+    client: Box<dyn MainNodeClient>,
+    event_handler: Box<dyn HandleReorgDetectorEvent>,
+    pool: ConnectionPool,
+    sleep_interval: Duration,
+    health_check: ReactiveHealthCheck,
+    compare_virtual_blocks: bool,
+    compare_pending_miniblock: bool,
+    /// If not set, standard binary search is used.
+    probe_strategy: Option<Box<dyn ProbeStrategy>>,
+    persist_reorgs: bool,
+    hash_display_truncation: Option<usize>,
+    catch_up_only: bool,
+    /// L1 batches up to this number (inclusive) are known to be contiguous in the local DB.
+    contiguous_l1_batch_number: Option<L1BatchNumber>,
+    /// Highest L1 batch number with a root hash verified against the main node.
+    max_verified_l1_batch_number: AtomicU32,
+    anchor: Option<Box<dyn L1BatchAnchor>>,
+    compare_logs_tree_roots: bool,
+    compare_commitments: bool,
+    compare_pass_through_data: bool,
+    compare_miniblock_counts: bool,
+    /// If set, transactions in the first diverged L1 batch are compared with the main node once a reorg is localized.
+    diagnose_divergence: bool,
+    startup_verification_depth: u32,
+    compare_proof_status: bool,
+    dry_run: bool,
+    /// If set, detected reorgs are rolled back by the detector itself using this reverter.
+    block_reverter: Option<BlockReverter>,
+    client_factory: Option<Box<dyn CreateMainNodeClient>>,
+    max_consecutive_client_failures: usize,
+    consecutive_client_failures: usize,
+    max_concurrent_checks: usize,
+    expected_fork_id: Option<H256>,
+    tolerate_tip_churn: bool,
+    /// Number of consecutive checks on which a reorg must be observed before it's reported.
+    confirmation_checks: u32,
+    /// First diverged L1 batch of the unconfirmed reorg and the number of consecutive checks it was observed on.
+    unconfirmed_reorg: Option<(L1BatchNumber, u32)>,
+    /// Last reorg reported in the dry-run mode; reset after a passing check.
+    dry_run_reorg: Option<DryRunReorg>,
+    compared_fields: FieldSet,
+    miniblock_sample_size: usize,
+    /// Timeout for a single consistency check.
+    watchdog_timeout: Option<Duration>,
+    /// Best known bounds (the last known valid and the first known diverged L1 batch) of a reorg localization
+    /// that was interrupted, e.g. by the watchdog. Used to resume localization on the next check.
+    localization_bounds: Mutex<Option<(L1BatchNumber, L1BatchNumber)>>,
+    metrics: Box<dyn MetricsSink>,
+    clock: Box<dyn Clock>,
+    /// Used to interrupt reorg localization on shutdown. Set when the detector is run.
+    stop_receiver: Option<watch::Receiver<bool>>,
+    /// If set and holding `true`, consistency checks in [`Self::run()`] are skipped.
+    pause_receiver: Option<watch::Receiver<bool>>,
+    /// Whether startup verification (the earliest L1 batch etc.) was performed.
+    startup_verified: bool,
+    /// If not set, standard English messages are used.
+    message_formatter: Option<Box<dyn MessageFormatter>>,
+    /// If set, invoked once a reorg is localized.
+    reorg_callback: Option<ReorgCallback>,
+    /// If not set, [`Error::is_transient()`] is used.
+    error_classifier: Option<ErrorClassifier>,
+    comparison_cadence: ComparisonCadence,
+    /// Number of performed sequential checks of sealed blocks; used together with `comparison_cadence`.
+    check_iteration: u64,
+    /// Last miniblock and L1 batch matching the main node. Used in place of skipped comparisons.
+    last_correct_blocks: Option<(MiniblockNumber, L1BatchNumber)>,
+    /// Last L1 batch matching the main node persisted in the local DB. Only used if reorg persistence is enabled.
+    last_correct_l1_batch_checkpoint: Option<L1BatchNumber>,
+    check_namespace_coherence: bool,
+    /// Max age of verified L1 batches relative to the latest local L1 batch with metadata.
+    max_verify_age: Option<u32>,
+    /// L1 batch below which L1 batches are trusted to be immutable during reorg localization.
+    trusted_l1_batch_floor: Option<L1BatchNumber>,
+    use_executed_l1_batch_floor: bool,
+    /// Number of recent L1 batches to start reorg localization from, assuming monotonic divergence.
+    fast_localization_window: Option<u32>,
+    /// If set, latest main node responses are captured for diagnostics.
+    rpc_trace: Option<RpcTrace>,
+    /// Timeout for each call to the main node.
+    rpc_timeout: Duration,
+    /// If set, limits the rate of calls to the main node.
+    rpc_rate_limiter: Option<RpcRateLimiter>,
+    rpc_retries: RpcRetries,
+    /// Cache for root hashes of finalized L1 batches fetched from the main node; disabled if not set.
+    root_hash_cache: Option<Mutex<LruCache<L1BatchNumber, H256>>>,
+    /// If not set, the sleep interval is used.
+    min_retry_backoff: Option<Duration>,
+    max_retry_backoff: Duration,
+    /// Current backoff for retries after transient errors; reset after a successful check.
+    retry_backoff: Option<Duration>,
+    /// If not set, transient errors are retried indefinitely.
+    max_transient_retries: Option<usize>,
+    consecutive_transient_errors: usize,
+    /// Time of the last successful consistency check, or of the detector start if no check has succeeded yet.
+    last_successful_check_at: Instant,
+}
+
+/// Builder for [`ReorgDetector`] allowing to configure basic detector params. Options are applied using
+/// the corresponding `ReorgDetector::with_*()` setters; unlike setters, the builder returns an error
+/// for invalid configuration instead of panicking.
+#[derive(Debug)]
+pub struct ReorgDetectorBuilder {
+    client: HttpClient,
+    pool: ConnectionPool,
+    sleep_interval: Duration,
+    health_check_name: &'static str,
+    retry_backoff: Option<(Duration, Duration)>,
+    cache_capacity: usize,
+    rpc_timeout: Duration,
+    rpc_rate_limit: Option<NonZeroU32>,
+    rpc_retries: Option<(usize, Duration)>,
+    block_reverter: Option<BlockReverter>,
+    event_handlers: Vec<Box<dyn HandleReorgDetectorEvent>>,
+}
+
+impl ReorgDetectorBuilder {
+    pub fn new(client: HttpClient, pool: ConnectionPool) -> Self {
+        Self {
+            client,
+            pool,
+            sleep_interval: ReorgDetector::DEFAULT_SLEEP_INTERVAL,
+            health_check_name: "reorg_detector",
+            retry_backoff: None,
+            cache_capacity: 0,
+            rpc_timeout: ReorgDetector::DEFAULT_RPC_TIMEOUT,
+            rpc_rate_limit: None,
+            rpc_retries: None,
+            block_reverter: None,
+            event_handlers: vec![],
+        }
+    }
+
+    /// Sets the interval between consecutive consistency checks. See [`ReorgDetector::with_sleep_interval()`].
+    pub fn sleep_interval(mut self, interval: Duration) -> Self {
+        self.sleep_interval = interval;
+        self
+    }
+
+    /// Sets the name of the detector health check. The default name is `reorg_detector`.
+    pub fn health_check_name(mut self, name: &'static str) -> Self {
+        self.health_check_name = name;
+        self
+    }
+
+    /// Sets the bounds for the retry backoff. See [`ReorgDetector::with_retry_backoff()`].
+    pub fn retry_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.retry_backoff = Some((min, max));
+        self
+    }
+
+    /// Sets the capacity of the root hash cache. See [`ReorgDetector::with_cache_capacity()`].
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// Sets the timeout for each call to the main node. See [`ReorgDetector::with_rpc_timeout()`].
+    pub fn rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.rpc_timeout = timeout;
+        self
+    }
+
+    /// Sets the max number of requests per second sent to the main node. See [`ReorgDetector::with_rpc_rate_limit()`].
+    pub fn rpc_rate_limit(mut self, requests_per_second: NonZeroU32) -> Self {
+        self.rpc_rate_limit = Some(requests_per_second);
+        self
+    }
+
+    /// Sets retries for each call to the main node. See [`ReorgDetector::with_rpc_retries()`].
+    pub fn rpc_retries(mut self, max_retries: usize, backoff: Duration) -> Self {
+        self.rpc_retries = Some((max_retries, backoff));
+        self
+    }
+
+    /// Enables the automatic rollback mode. See [`ReorgDetector::with_auto_revert()`].
+    ///
+    /// **This mode is destructive and unsafe for production external nodes.**
+    pub fn auto_revert(mut self, reverter: BlockReverter) -> Self {
+        self.block_reverter = Some(reverter);
+        self
+    }
+
+    /// Adds a handler for detector events. See [`ReorgDetector::with_event_handler()`].
+    pub fn event_handler(mut self, handler: Box<dyn HandleReorgDetectorEvent>) -> Self {
+        self.event_handlers.push(handler);
+        self
+    }
+
+    /// Builds the detector by applying the configured options via the corresponding `ReorgDetector::with_*()` setters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid, e.g. the sleep interval is zero.
+    pub fn build(self) -> anyhow::Result<ReorgDetector> {
+        anyhow::ensure!(
+            !self.sleep_interval.is_zero(),
+            "Reorg detector sleep interval must be positive"
+        );
+        anyhow::ensure!(
+            !self.rpc_timeout.is_zero(),
+            "Reorg detector RPC timeout must be positive"
+        );
+        let (min_retry_backoff, max_retry_backoff) = self.retry_backoff.unwrap_or((
+            self.sleep_interval,
+            ReorgDetector::DEFAULT_MAX_RETRY_BACKOFF,
+        ));
+        anyhow::ensure!(
+            !min_retry_backoff.is_zero(),
+            "Reorg detector min retry backoff must be positive"
+        );
+        anyhow::ensure!(
+            min_retry_backoff <= max_retry_backoff,
+            "Reorg detector min retry backoff ({min_retry_backoff:?}) must not exceed max retry backoff ({max_retry_backoff:?})"
+        );
+
+        let health_check = ReactiveHealthCheck::new(self.health_check_name);
+        let mut detector = ReorgDetector::with_health_check(self.client, self.pool, health_check)
+            .with_sleep_interval(self.sleep_interval)
+            .with_cache_capacity(self.cache_capacity)
+            .with_rpc_timeout(self.rpc_timeout);
+        if let Some((min, max)) = self.retry_backoff {
+            detector = detector.with_retry_backoff(min, max);
+        }
+        if let Some(requests_per_second) = self.rpc_rate_limit {
+            detector = detector.with_rpc_rate_limit(requests_per_second);
+        }
+        if let Some((max_retries, backoff)) = self.rpc_retries {
+            detector = detector.with_rpc_retries(max_retries, backoff);
+        }
+        if let Some(reverter) = self.block_reverter {
+            detector = detector.with_auto_revert(reverter);
+        }
+        for handler in self.event_handlers {
+            detector = detector.with_event_handler(handler);
+        }
+        Ok(detector)
+    }
+}
+
+impl ReorgDetector {
+    const DEFAULT_SLEEP_INTERVAL: Duration = Duration::from_secs(5);
+    const DEFAULT_MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+    const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+    /// Upper bound for the number of L1 batches verified on startup.
+    const MAX_STARTUP_VERIFICATION_DEPTH: u32 = 1_000;
+    /// Delay between root hash checks during startup verification, so that the main node isn't flooded with requests.
+    const STARTUP_VERIFICATION_DELAY: Duration = Duration::from_millis(50);
+    /// Max number of retries reacquiring a DB connection during reorg localization.
+    const MAX_STORAGE_REACQUISITION_RETRIES: usize = 5;
+    /// Initial backoff between retries reacquiring a DB connection; doubled after each retry.
+    const STORAGE_REACQUISITION_BACKOFF: Duration = Duration::from_millis(100);
+    /// Max number of L1 batches within reorg localization bounds for which remote root hashes are prefetched
+    /// using a single request.
+    const ROOT_HASH_PREFETCH_WINDOW: u32 = 32;
+    /// Max number of miniblocks within miniblock reorg localization bounds for which remote hashes are prefetched
+    /// using a single (potentially concurrent) request.
+    const MINIBLOCK_HASH_PREFETCH_WINDOW: u32 = 256;
+    /// Max number of L1 batches concurrently checked by [`Self::verify_range()`].
+    const MAX_CONCURRENT_RANGE_CHECKS: usize = 10;
+
+    pub fn new(client: HttpClient, pool: ConnectionPool) -> Self {
+        Self::builder(client, pool)
+            .build()
+            .expect("default reorg detector config is valid")
+    }
+
+    /// Returns a builder allowing to configure basic detector params.
+    pub fn builder(client: HttpClient, pool: ConnectionPool) -> ReorgDetectorBuilder {
+        ReorgDetectorBuilder::new(client, pool)
+    }
+
+    /// Returns a builder for the detector with the main node client created by the provided factory, e.g.
+    /// an [`HttpClientFactory`] with authentication headers. To keep these headers if the client is recreated
+    /// after failures, pass the same factory to [`Self::with_client_factory()`].
+    ///
+    /// # Errors
+    ///
+    /// Propagates errors creating the client.
+    pub fn builder_with_client_factory(
+        factory: &impl ClientFactory,
+        pool: ConnectionPool,
+    ) -> anyhow::Result<ReorgDetectorBuilder> {
+        let client = factory
+            .create_client()
+            .context("failed creating main node client")?;
+        Ok(ReorgDetectorBuilder::new(client, pool))
+    }
+
+    /// Creates a detector reporting its health via an externally created health check / updater pair.
+    /// This allows registering the health check (e.g., in `AppHealthCheck`) before the detector is created.
+    pub fn with_health_check(
+        client: HttpClient,
+        pool: ConnectionPool,
+        health_check: (ReactiveHealthCheck, HealthUpdater),
+    ) -> Self {
+        Self::from_parts_with_health_check(Box::new(client), pool, health_check)
+    }
+
+    /// Creates a detector using the provided main node client, e.g. a wrapper around [`HttpClient`].
+    pub fn from_parts(client: Box<dyn MainNodeClient>, pool: ConnectionPool) -> Self {
+        let health_check = ReactiveHealthCheck::new("reorg_detector");
+        Self::from_parts_with_health_check(client, pool, health_check)
+    }
+
+    fn from_parts_with_health_check(
+        client: Box<dyn MainNodeClient>,
+        pool: ConnectionPool,
+        (health_check, health_updater): (ReactiveHealthCheck, HealthUpdater),
+    ) -> Self {
+        Self {
+            client,
+            event_handler: Box::new(health_updater),
+            pool,
+            sleep_interval: Self::DEFAULT_SLEEP_INTERVAL,
+            health_check,
+            compare_virtual_blocks: false,
+            compare_pending_miniblock: false,
+            probe_strategy: None,
+            persist_reorgs: false,
+            hash_display_truncation: None,
+            catch_up_only: false,
+            contiguous_l1_batch_number: None,
+            max_verified_l1_batch_number: AtomicU32::new(0),
+            anchor: None,
+            compare_logs_tree_roots: false,
+            compare_commitments: false,
+            compare_pass_through_data: false,
+            compare_miniblock_counts: false,
+            diagnose_divergence: false,
+            startup_verification_depth: 0,
+            compare_proof_status: false,
+            dry_run: false,
+            block_reverter: None,
+            client_factory: None,
+            max_consecutive_client_failures: usize::MAX,
+            consecutive_client_failures: 0,
+            max_concurrent_checks: 1,
+            expected_fork_id: None,
+            tolerate_tip_churn: false,
+            confirmation_checks: 1,
+            unconfirmed_reorg: None,
+            dry_run_reorg: None,
+            compared_fields: FieldSet::empty(),
+            miniblock_sample_size: 0,
+            watchdog_timeout: None,
+            localization_bounds: Mutex::default(),
+            metrics: Box::new(GlobalMetricsSink),
+            clock: Box::new(TokioClock),
+            stop_receiver: None,
+            pause_receiver: None,
+            message_formatter: None,
+            reorg_callback: None,
+            error_classifier: None,
+            comparison_cadence: ComparisonCadence::default(),
+            check_iteration: 0,
+            last_correct_blocks: None,
+            last_correct_l1_batch_checkpoint: None,
+            check_namespace_coherence: false,
+            max_verify_age: None,
+            trusted_l1_batch_floor: None,
+            use_executed_l1_batch_floor: false,
+            fast_localization_window: None,
+            rpc_trace: None,
+            rpc_timeout: Self::DEFAULT_RPC_TIMEOUT,
+            rpc_rate_limiter: None,
+            rpc_retries: RpcRetries::default(),
+            root_hash_cache: None,
+            min_retry_backoff: None,
+            max_retry_backoff: Self::DEFAULT_MAX_RETRY_BACKOFF,
+            retry_backoff: None,
+            max_transient_retries: None,
+            consecutive_transient_errors: 0,
+            last_successful_check_at: Instant::now(),
+            startup_verified: false,
+        }
+    }
+
+    /// Sets the interval between consecutive consistency checks. The default value is 5 seconds; operators
+    /// running against a congested main node may want to increase it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is zero, consistently with [`ReorgDetectorBuilder::build()`] returning an error.
+    pub fn with_sleep_interval(mut self, interval: Duration) -> Self {
+        assert!(
+            !interval.is_zero(),
+            "Reorg detector sleep interval must be positive"
+        );
+        self.sleep_interval = interval;
+        self
+    }
+
+    /// Sets the max number of consecutive retries after transient errors (e.g., the main node being unreachable).
+    /// Once exceeded, the last transient error is returned from [`Self::run()`] as a fatal one, and the detector
+    /// health transitions to not ready, so that a misconfigured node is noticed by supervisors.
+    /// By default, transient errors are retried indefinitely.
+    pub fn with_max_transient_retries(mut self, max_retries: usize) -> Self {
+        self.max_transient_retries = Some(max_retries);
+        self
+    }
+
+    /// Sets the bounds for the exponential backoff used when retrying after transient errors. By default,
+    /// the backoff starts from the sleep interval and is capped at 60 seconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is zero or exceeds `max`, consistently with [`ReorgDetectorBuilder::build()`] returning an error.
+    pub fn with_retry_backoff(mut self, min: Duration, max: Duration) -> Self {
+        assert!(
+            !min.is_zero(),
+            "Reorg detector min retry backoff must be positive"
+        );
+        assert!(
+            min <= max,
+            "Reorg detector min retry backoff ({min:?}) must not exceed max retry backoff ({max:?})"
+        );
+        self.min_retry_backoff = Some(min);
+        self.max_retry_backoff = max;
+        self
+    }
+
+    /// Sets the capacity of the LRU cache for root hashes of finalized L1 batches fetched from the main node.
+    /// Only root hashes of L1 batches executed on Ethereum are cached since they cannot change. The default value
+    /// is 0, i.e., the cache is disabled.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.root_hash_cache =
+            NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity)));
+        self
+    }
+
+    /// Sets the timeout for each call to the main node. Timed out calls are treated as transient errors
+    /// and are retried. The default value is 30 seconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timeout is zero, consistently with [`ReorgDetectorBuilder::build()`] returning an error.
+    pub fn with_rpc_timeout(mut self, timeout: Duration) -> Self {
+        assert!(
+            !timeout.is_zero(),
+            "Reorg detector RPC timeout must be positive"
+        );
+        self.rpc_timeout = timeout;
+        self
+    }
+
+    /// Sets the max number of requests per second sent to the main node, e.g. to not overwhelm a shared main node
+    /// with bursts of requests during reorg localization. Requests exceeding the limit wait until they fit into it
+    /// rather than fail. Batched requests count as multiple requests. By default, requests are not rate-limited.
+    pub fn with_rpc_rate_limit(mut self, requests_per_second: NonZeroU32) -> Self {
+        self.rpc_rate_limiter = Some(RateLimiter::direct(Quota::per_second(requests_per_second)));
+        self
+    }
+
+    /// Sets the number of retries for each call to the main node failing with a transient error
+    /// (a transport error or a timeout), and the backoff before the first retry, which is doubled for each
+    /// following retry and capped at the max [retry backoff](Self::with_retry_backoff()). This allows not to abort
+    /// the entire consistency check because of a one-off RPC failure; errors persisting after all retries are handled
+    /// by the detector as usual (i.e., the check is retried with the retry backoff). By default, calls are not retried.
+    pub fn with_rpc_retries(mut self, max_retries: usize, backoff: Duration) -> Self {
+        self.rpc_retries = RpcRetries {
+            max_retries,
+            backoff,
+        };
+        self
+    }
+
+    /// Enables the automatic rollback mode. In this mode, the detector uses the provided `reverter` to roll back
+    /// Postgres data after the last correct L1 batch once a reorg is localized, and continues running instead
+    /// of returning. Disabled by default; ignored in the [dry-run mode](Self::with_dry_run()).
+    ///
+    /// The reverter should allow reverting L1 batches executed on L1
+    /// ([`L1ExecutedBatchesRevert::Allowed`](crate::block_reverter::L1ExecutedBatchesRevert::Allowed)),
+    /// since diverged batches may be marked as executed in the local DB.
+    ///
+    /// **This mode is destructive and unsafe for production external nodes.** The Merkle tree and the state keeper
+    /// cache are not rolled back, since the components owning them may be running concurrently with the detector;
+    /// the node state may thus become inconsistent. To roll back production nodes, let the detector return
+    /// the last correct L1 batch, stop other components and use the block reverter with all flags.
+    /// This mode is intended for tests and embedded setups.
+    pub fn with_auto_revert(mut self, reverter: BlockReverter) -> Self {
+        self.block_reverter = Some(reverter);
+        self
+    }
+
+    /// Adds a handler for detector events, e.g. to route them to custom telemetry. Events are still reported
+    /// to previously configured handlers (by default, the detector health check); the added handler is invoked
+    /// after them.
+    pub fn with_event_handler(mut self, handler: Box<dyn HandleReorgDetectorEvent>) -> Self {
+        self.event_handler = Box::new(CompositeEventHandler::new(vec![
+            self.event_handler,
+            handler,
+        ]));
+        self
+    }
+
+    /// Enables comparing the number of virtual blocks for the checked miniblock with the main node.
+    /// Mismatches are reported as reorg suspicions. Disabled by default.
+    pub fn with_virtual_blocks_comparison(mut self, enabled: bool) -> Self {
+        self.compare_virtual_blocks = enabled;
+        self
+    }
+
+    /// Enables comparing the pending miniblock on the main node with the same miniblock in the local DB, if the latter
+    /// is present. This allows noticing divergences before the corresponding miniblock is sealed on the main node.
+    /// Since pending state is volatile, mismatches are only reported as reorg suspicions and never trigger a rollback.
+    /// Disabled by default.
+    pub fn with_pending_miniblock_comparison(mut self, enabled: bool) -> Self {
+        self.compare_pending_miniblock = enabled;
+        self
+    }
+
+    /// Enables persisting detected reorgs in the local DB. If a reorg was detected, but the node was restarted
+    /// without rolling it back, the detector will return an error on start instead of detecting the same reorg again,
+    /// which prevents restart loops. The last L1 batch matching the main node is persisted as well, and is used
+    /// as the lower bound for reorg localization after restarts. Bounds of an in-progress reorg localization
+    /// are persisted after each probe, so that localization is resumed on start if the node was terminated
+    /// during it. Disabled by default.
+    pub fn with_reorg_persistence(mut self, enabled: bool) -> Self {
+        self.persist_reorgs = enabled;
+        self
+    }
+
+    /// Enables catch-up-only mode, in which only L1 batches and miniblocks committed on L1 according to the local DB
+    /// are checked; the tip that is being actively synced is skipped. This reduces false positives
+    /// during initial sync. Disabled by default.
+    pub fn with_catch_up_only(mut self, enabled: bool) -> Self {
+        self.catch_up_only = enabled;
+        self
+    }
+
+    /// Truncates hashes in logs to the specified number of leading and trailing bytes. Hashes are always
+    /// compared in full. By default, hashes are displayed in full.
+    pub fn with_hash_display_truncation(mut self, bytes: usize) -> Self {
+        self.hash_display_truncation = Some(bytes);
+        self
+    }
+
+    fn display_hash(&self, hash: H256) -> DisplayHash {
+        DisplayHash {
+            hash,
+            truncation: self.hash_display_truncation,
+        }
+    }
+
+    /// Sets the strategy used to select probed L1 batches when localizing a reorg.
+    /// By default, standard binary search is used.
+    pub fn with_probe_strategy(mut self, strategy: impl ProbeStrategy + 'static) -> Self {
+        self.probe_strategy = Some(Box::new(strategy));
+        self
+    }
+
+    /// Sets the factory used to recreate the main node client after the specified number of consecutive
+    /// RPC failures. By default, the client is never recreated.
+    pub fn with_client_factory(
+        mut self,
+        factory: impl ClientFactory + 'static,
+        max_consecutive_failures: usize,
+    ) -> Self {
+        self.client_factory = Some(Box::new(factory));
+        self.max_consecutive_client_failures = max_consecutive_failures.max(1);
+        self
+    }
+
+    /// Enables comparing the proof status of the checked L1 batch with the main node. Mismatches are reported
+    /// as informational signals rather than reorgs. Disabled by default.
+    pub fn with_proof_status_comparison(mut self, enabled: bool) -> Self {
+        self.compare_proof_status = enabled;
+        self
+    }
+
+    /// Enables checking on each iteration that the `eth` and `zks` namespaces of the main node report coherent
+    /// chain state, i.e., that the miniblocks of the latest sealed L1 batch reported by the `zks` namespace
+    /// are known to the `eth` namespace. Incoherent state is treated as a transient error. Disabled by default.
+    pub fn with_namespace_coherence_check(mut self, enabled: bool) -> Self {
+        self.check_namespace_coherence = enabled;
+        self
+    }
+
+    /// Allows up to the specified number of concurrent checks of the latest sealed L1 batch and miniblock, started
+    /// at staggered offsets within the sleep interval. This reduces the effective detection latency if checks
+    /// are I/O-bound. Concurrent checks are read-only; their results are deduplicated and applied sequentially,
+    /// and detected divergences are re-checked and localized sequentially as well. By default, checks are sequential.
+    pub fn with_concurrent_checks(mut self, max_concurrent_checks: usize) -> Self {
+        self.max_concurrent_checks = max_concurrent_checks.max(1);
+        self
+    }
+
+    /// Sets the expected identifier of the chain fork served by the main node. The fork ID is verified on startup
+    /// and on each iteration; if it differs, the detector fails with a fatal error. By default, the fork ID
+    /// is not verified.
+    pub fn with_expected_fork_id(mut self, fork_id: H256) -> Self {
+        self.expected_fork_id = Some(fork_id);
+        self
+    }
+
+    /// Enables treating reorgs localized to the local head (i.e., the last correct L1 batch is the last sealed
+    /// local L1 batch) as tip churn. Such divergences are reported as suspicions rather than reorgs, since there are
+    /// no L1 batches to roll back. Disabled by default.
+    pub fn with_tip_churn_tolerance(mut self, enabled: bool) -> Self {
+        self.tolerate_tip_churn = enabled;
+        self
+    }
+
+    /// Sets the number of consecutive consistency checks on which a reorg must be observed (with the first diverged
+    /// L1 batch staying the same or becoming earlier) before the detector reports it. Until then, the reorg is reported
+    /// as a suspicion, and the check is [skipped](CheckOutcome::Skipped). This reduces false positives caused
+    /// by races with the main node sealing blocks. The default value is 1, i.e., reorgs are reported immediately;
+    /// zero is treated as 1.
+    pub fn with_confirmation_checks(mut self, checks: u32) -> Self {
+        self.confirmation_checks = checks.max(1);
+        self
+    }
+
+    /// Sets the fields of L1 batch details to compare with the main node for each checked L1 batch. Details
+    /// are fetched once per check, and all diverging fields are reported together as a suspicion.
+    /// By default, no fields are compared.
+    pub fn with_field_comparison(mut self, fields: FieldSet) -> Self {
+        self.compared_fields = fields;
+        self
+    }
+
+    /// Sets a signal pausing the detector, e.g. during a maintenance window in which the main node is deliberately
+    /// rewound. While the signal holds `true`, [`Self::run()`] skips consistency checks and only sleeps;
+    /// once it flips back to `false`, checks resume normally. Unlike the stop signal, pausing doesn't terminate
+    /// the detector. By default, the detector is never paused.
+    pub fn with_pause_signal(mut self, pause_receiver: watch::Receiver<bool>) -> Self {
+        self.pause_receiver = Some(pause_receiver);
+        self
+    }
+
+    /// Sets the number of miniblocks randomly sampled from the checked L1 batch to compare with the main node
+    /// on each iteration, in addition to the latest miniblock. A divergence of any sampled miniblock is treated
+    /// as a divergence of the checked L1 batch. By default, no miniblocks are sampled.
+    pub fn with_miniblock_sampling(mut self, sample_size: usize) -> Self {
+        self.miniblock_sample_size = sample_size;
+        self
+    }
+
+    /// Enables a watchdog aborting consistency checks that take longer than `timeout`, e.g. because of a hang
+    /// not caught by RPC timeouts. An aborted check is logged and recorded in metrics, and the detector proceeds
+    /// to the next iteration. If reorg localization is aborted, its progress is retained, so that localization
+    /// taking longer than `timeout` still completes over several iterations. By default, checks are not time-limited.
+    pub fn with_watchdog(mut self, timeout: Duration) -> Self {
+        self.watchdog_timeout = Some(timeout);
+        self
+    }
+
+    /// Publishes lifecycle events of the detector to the provided broadcast channel, in addition to updating
+    /// the detector health. Publishing never blocks the detector; subscribers lagging behind lose the oldest events.
+    pub fn with_event_broadcast(mut self, sender: broadcast::Sender<ReorgEvent>) -> Self {
+        self.event_handler = Box::new(BroadcastingEventHandler {
+            inner: self.event_handler,
+            sender,
+        });
+        self
+    }
+
+    /// Sets the backend for metrics emitted by the detector. By default, metrics are reported
+    /// to the global external node metrics.
+    pub fn with_metrics_sink(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.metrics = Box::new(sink);
+        self
+    }
+
+    /// Sets the timer used to sleep between consistency checks and before retries. RPC and watchdog timeouts
+    /// are not affected. By default, the Tokio timer is used.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Sets the formatter for operator-facing messages, i.e., fatal errors returned from [`Self::run()`]
+    /// and detected reorgs. By default, standard English messages are used.
+    pub fn with_message_formatter(mut self, formatter: impl MessageFormatter + 'static) -> Self {
+        self.message_formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Sets a callback invoked with the last correct L1 batch as soon as a reorg is localized, before
+    /// the outcome is returned from [`Self::check_consistency()`]. Unlike health check updates, which are polled,
+    /// this allows pushing notifications about reorgs, e.g. to alerting systems. The callback must not block.
+    pub fn with_reorg_callback(
+        mut self,
+        callback: impl Fn(L1BatchNumber) + Send + Sync + 'static,
+    ) -> Self {
+        self.reorg_callback = Some(ReorgCallback(Box::new(callback)));
+        self
+    }
+
+    /// Sets a classifier deciding whether an error occurring in [`Self::run()`] is transient, i.e., whether
+    /// the failed check should be retried instead of terminating the detector. This allows treating errors
+    /// specific to the deployment (e.g., certain RPC error codes returned by a load balancer) as transient.
+    /// By default, [`Error::is_transient()`] is used.
+    pub fn with_error_classifier(
+        mut self,
+        classifier: impl Fn(&Error) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.error_classifier = Some(ErrorClassifier(Box::new(classifier)));
+        self
+    }
+
+    fn is_transient(&self, err: &Error) -> bool {
+        match &self.error_classifier {
+            Some(ErrorClassifier(classifier)) => classifier(err),
+            None => err.is_transient(),
+        }
+    }
+
+    /// Sets the cadence of each comparison type, allowing to perform heavier comparisons less frequently
+    /// than the main loop runs. Skipped comparisons are treated as matching the main node, with the last
+    /// correct L1 batch / miniblock retained from the previous comparison. The cadence only applies to sequential
+    /// checks. By default, all comparisons are performed on each iteration.
+    pub fn with_comparison_cadence(mut self, cadence: ComparisonCadence) -> Self {
+        self.comparison_cadence = cadence;
+        self
+    }
+
+    /// Enables capturing the specified number of latest main node responses (heads and hashes) for diagnostics;
+    /// see [`Self::recent_rpc_trace()`]. Disabled by default.
+    pub fn with_rpc_trace(mut self, capacity: usize) -> Self {
+        self.rpc_trace = (capacity > 0).then(|| RpcTrace::new(capacity));
+        self
+    }
+
+    /// Returns the captured latest main node responses, oldest first. If [RPC tracing](Self::with_rpc_trace())
+    /// is disabled, returns an empty trace.
+    pub fn recent_rpc_trace(&self) -> Vec<RpcTraceEntry> {
+        let Some(trace) = &self.rpc_trace else {
+            return vec![];
+        };
+        let entries = trace.entries.lock().expect("RPC trace is poisoned");
+        entries.iter().copied().collect()
+    }
+
+    fn trace_response(&self, response: RpcTraceResponse) {
+        if let Some(trace) = &self.rpc_trace {
+            trace.push(response);
+        }
+    }
+
+    fn reorg_message(&self, last_correct_l1_batch: L1BatchNumber) -> String {
+        match &self.message_formatter {
+            Some(formatter) => formatter.format_reorg(last_correct_l1_batch),
+            None => DefaultMessageFormatter.format_reorg(last_correct_l1_batch),
+        }
+    }
+
+    /// Converts a fatal error to an operator-facing one, keeping the original error downcastable.
+    fn operator_error(&self, err: HashMatchError) -> anyhow::Error {
+        match &self.message_formatter {
+            Some(formatter) => {
+                let message = formatter.format_error(&err);
+                anyhow::Error::new(err).context(message)
+            }
+            None => err.into(),
+        }
+    }
+
+    /// Enables the dry-run mode. In this mode, detected reorgs are still localized, and the last correct L1 batch
+    /// is reported via logs and health checks, but the detector keeps running instead of returning
+    /// (which would cause the node to roll back and restart). Reorgs are not persisted in this mode.
+    /// A detected reorg is localized and reported once; it's only localized again if local or remote root hashes
+    /// around its divergence point change, or after a passing check. Disabled by default.
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Sets the number of the most recent L1 batches to verify on startup before entering the main loop.
+    /// Any divergence among these batches is surfaced as an error. The depth is capped at 1,000 batches;
+    /// 0 (the default) disables startup verification.
+    pub fn with_startup_verification_depth(mut self, depth: u32) -> Self {
+        self.startup_verification_depth = depth;
+        self
+    }
+
+    /// Bounds the verified history to the specified number of L1 batches before the latest local L1 batch
+    /// with metadata. Older L1 batches are assumed to be finalized beyond any reorg possibility and are neither
+    /// used as the earliest L1 batch anchor nor covered by reorg localization and [forensic scans](Self::forensic_scan()).
+    /// This bounds audit cost on long chains. By default, the entire local history is verified.
+    pub fn with_max_verify_age(mut self, l1_batches: u32) -> Self {
+        self.max_verify_age = Some(l1_batches);
+        self
+    }
+
+    /// Sets the trusted L1 batch floor. L1 batches up to and including the floor are assumed to be immutable
+    /// (e.g., settled on L1), so reorg localization starts from the floor rather than the earliest L1 batch.
+    /// Before it is used, the root hash of the floor is checked against the main node; a mismatch is surfaced
+    /// as an error. By default, reorg localization covers the entire local history.
+    pub fn with_trusted_l1_batch_floor(mut self, floor: Option<L1BatchNumber>) -> Self {
+        self.trusted_l1_batch_floor = floor;
+        self
+    }
+
+    /// Uses the last L1 batch executed on L1 according to the main node as the trusted L1 batch floor (or the configured
+    /// [floor](Self::with_trusted_l1_batch_floor()), whichever is greater). Executed L1 batches are immutable, so this
+    /// is a stronger lower bound for reorg localization than the earliest local L1 batch. Like the configured floor,
+    /// the executed L1 batch is checked against the main node before it is used. Disabled by default.
+    pub fn with_executed_l1_batch_floor(mut self, enabled: bool) -> Self {
+        self.use_executed_l1_batch_floor = enabled;
+        self
+    }
+
+    /// Enables fast reorg localization. Normally, localization searches the entire local history, since the batch
+    /// status updater may mark diverged L1 batches as executed. If the batch status updater is trusted, divergence
+    /// can be assumed to be monotonic, and localization starts from the L1 batch `window` batches before
+    /// the diverged one. If this L1 batch unexpectedly doesn't match the main node, localization falls back
+    /// to the entire history. Disabled by default.
+    pub fn with_fast_localization(mut self, window: Option<u32>) -> Self {
+        self.fast_localization_window = window;
+        self
+    }
+
+    /// Returns the earliest L1 batch covered by verification.
+    fn bounded_earliest_l1_batch(
+        &self,
+        earliest_l1_batch_number: L1BatchNumber,
+        sealed_l1_batch_number: L1BatchNumber,
+    ) -> L1BatchNumber {
+        let Some(max_age) = self.max_verify_age else {
+            return earliest_l1_batch_number;
+        };
+        earliest_l1_batch_number.max(L1BatchNumber(
+            sealed_l1_batch_number.0.saturating_sub(max_age),
+        ))
+    }
+
+    /// Enables comparing L2-to-L1 logs Merkle tree roots for checked L1 batches with the main node, in addition
+    /// to state root hashes. This catches reorgs altering cross-chain messaging while preserving the state root.
+    /// Disabled by default.
+    pub fn with_logs_tree_root_comparison(mut self, enabled: bool) -> Self {
+        self.compare_logs_tree_roots = enabled;
+        self
+    }
+
+    /// Enables comparing commitments for checked L1 batches with the main node, in addition to state root hashes.
+    /// This catches reorgs altering the batch commitment (e.g., pubdata) while preserving the state root.
+    /// Disabled by default.
+    pub fn with_commitment_comparison(mut self, enabled: bool) -> Self {
+        self.compare_commitments = enabled;
+        self
+    }
+
+    /// Enables comparing pass-through data hashes for checked L1 batches with the main node, in addition to state
+    /// root hashes. Pass-through data can diverge independently of the state root, so this catches inconsistencies
+    /// on the data availability layer of rollup-mode nodes. Disabled by default.
+    pub fn with_pass_through_data_comparison(mut self, enabled: bool) -> Self {
+        self.compare_pass_through_data = enabled;
+        self
+    }
+
+    /// Enables comparing the number of miniblocks in checked L1 batches with the main node, in addition to state
+    /// root hashes. This catches corruption attributing miniblocks to a wrong L1 batch, which may leave the state root
+    /// intact. Disabled by default.
+    pub fn with_miniblock_count_comparison(mut self, enabled: bool) -> Self {
+        self.compare_miniblock_counts = enabled;
+        self
+    }
+
+    /// Enables divergence diagnostics. Once a reorg is localized, transactions in each miniblock of the first diverged
+    /// L1 batch are compared with the main node, and differing transactions are logged. Diagnostics are purely
+    /// informational and require an additional RPC call per miniblock. Disabled by default.
+    pub fn with_divergence_diagnostics(mut self, enabled: bool) -> Self {
+        self.diagnose_divergence = enabled;
+        self
+    }
+
+    /// Sets an independent anchor for L1 batch root hashes. If the local node and the main node agree
+    /// on a root hash, but it disagrees with the anchor, the detector fails with a fatal error.
+    pub fn with_anchor(mut self, anchor: impl L1BatchAnchor + 'static) -> Self {
+        self.anchor = Some(Box::new(anchor));
+        self
+    }
+
+    /// Returns a health check for this detector. The check can be retained after the detector is consumed by [`Self::run()`].
+    pub fn health_check(&self) -> ReactiveHealthCheck {
+        self.health_check.clone()
+    }
+
+    /// Returns the main node client with the configured RPC timeout, rate limit and retries applied to all calls.
+    fn client(&self) -> TimeoutClient<'_> {
+        TimeoutClient {
+            inner: self.client.as_ref(),
+            timeout: self.rpc_timeout,
+            rate_limiter: self.rpc_rate_limiter.as_ref(),
+            retries: self.rpc_retries,
+            max_backoff: self.max_retry_backoff,
+            clock: self.clock.as_ref(),
+            metrics: self.metrics.as_ref(),
+            stop_receiver: self.stop_receiver.as_ref(),
+        }
+    }
+
+    /// Returns the last checked miniblock and L1 batch matching the main node, or `None` if no check
+    /// has succeeded yet.
+    pub fn last_correct_block(&self) -> Option<(MiniblockNumber, L1BatchNumber)> {
+        self.last_correct_blocks
+    }
+
+    /// Compares hashes of the given local miniblock and the same miniblock from main node.
+    async fn miniblock_hashes_match(
+        &self,
+        miniblock_number: MiniblockNumber,
+    ) -> Result<MatchOutput, HashMatchError> {
+        self.miniblock_hashes_match_using(miniblock_number, None)
+            .await
+    }
+
+    /// Same as [`Self::miniblock_hashes_match()`], but uses the prefetched remote hash, if any.
+    async fn miniblock_hashes_match_using(
+        &self,
+        miniblock_number: MiniblockNumber,
+        prefetched_remote_hash: Option<Option<H256>>,
+    ) -> Result<MatchOutput, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_hash = storage
+            .blocks_dal()
+            .get_miniblock_header(miniblock_number)
+            .await?
+            .with_context(|| {
+                format!("Header does not exist for local miniblock #{miniblock_number}")
+            })?
+            .hash;
+        drop(storage);
+        self.compare_miniblock_hashes(miniblock_number, local_hash, prefetched_remote_hash)
+            .await
+    }
+
+    /// Compares the provided local miniblock hash with the hash from the main node.
+    async fn compare_miniblock_hashes(
+        &self,
+        miniblock_number: MiniblockNumber,
+        local_hash: H256,
+        prefetched_remote_hash: Option<Option<H256>>,
+    ) -> Result<MatchOutput, HashMatchError> {
+        let remote_hash = match prefetched_remote_hash {
+            Some(hash) => hash,
+            None => self.client().miniblock_hash(miniblock_number).await?,
+        };
+        self.trace_response(RpcTraceResponse::MiniblockHash {
+            number: miniblock_number,
+            hash: remote_hash,
+        });
+        let Some(remote_hash) = remote_hash else {
+            // Due to reorg, locally we may be ahead of the main node.
+            // Lack of the hash on the main node is treated as a hash match,
+            // We need to wait for our knowledge of main node to catch up.
+            return Ok(MatchOutput::NoRemoteReference);
+        };
+
+        if remote_hash == local_hash {
+            return Ok(MatchOutput::Match);
+        }
+        self.check_remote_miniblock_consistency(miniblock_number, remote_hash)
+            .await?;
+        tracing::warn!(
+            miniblock = miniblock_number.0,
+            local_hash = %self.display_hash(local_hash),
+            remote_hash = %self.display_hash(remote_hash),
+            "Reorg detected: local miniblock hash doesn't match the hash from main node"
+        );
+        Ok(MatchOutput::Mismatch(DivergenceKind::MiniblockHash {
+            miniblock: miniblock_number,
+            local: local_hash,
+            remote: remote_hash,
+        }))
+    }
+
+    /// Compares hashes of randomly sampled miniblocks in the specified L1 batch with the main node.
+    async fn sampled_miniblocks_match(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<bool, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let miniblock_range = storage
+            .blocks_dal()
+            .get_miniblock_range_of_l1_batch(l1_batch_number)
+            .await?;
+        drop(storage);
+        let Some((first_miniblock, last_miniblock)) = miniblock_range else {
+            return Ok(true); // May happen after snapshot recovery
+        };
+
+        let range_len = (last_miniblock.0 - first_miniblock.0) as usize + 1;
+        let sample_size = self.miniblock_sample_size.min(range_len);
+        let mut offsets =
+            rand::seq::index::sample(&mut rand::thread_rng(), range_len, sample_size).into_vec();
+        offsets.sort_unstable();
+        for offset in offsets {
+            let miniblock_number = first_miniblock + offset as u32;
+            if let MatchOutput::Mismatch(_) = self.miniblock_hashes_match(miniblock_number).await? {
+                tracing::info!(
+                    miniblock = miniblock_number.0,
+                    l1_batch = l1_batch_number.0,
+                    "Sampled miniblock in L1 batch has diverged"
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Checks that the miniblock hash returned by the main node is consistent with the parent hash
+    /// of the following miniblock (if the main node has one). An inconsistency is impossible on a consistent node
+    /// and signals that the main node serves stale (e.g., cached) responses.
+    async fn check_remote_miniblock_consistency(
+        &self,
+        miniblock_number: MiniblockNumber,
+        remote_hash: H256,
+    ) -> Result<(), HashMatchError> {
+        let next_miniblock_number = miniblock_number + 1;
+        let Some(next_parent_hash) = self
+            .client()
+            .miniblock_parent_hash(next_miniblock_number)
+            .await?
+        else {
+            return Ok(());
+        };
+        if next_parent_hash != remote_hash {
+            return Err(HashMatchError::RemoteCachingArtifact(miniblock_number));
+        }
+        Ok(())
+    }
+
+    /// Checks hash correspondence for the latest miniblock sealed both locally and on the main node.
+    /// Returns the checked miniblock and the diverged hashes, or `None` if the hashes match.
+    async fn check_sealed_miniblock_hash(
+        &self,
+        snapshot: &LocalSnapshot,
+    ) -> Result<(MiniblockNumber, Option<DivergenceKind>), HashMatchError> {
+        let sealed_miniblock_number = snapshot.miniblock;
+        let mut main_node_sealed_miniblock_number = sealed_miniblock_number;
+        loop {
+            let checked_number = sealed_miniblock_number.min(main_node_sealed_miniblock_number);
+            let output = if checked_number == sealed_miniblock_number {
+                self.compare_miniblock_hashes(checked_number, snapshot.miniblock_hash, None)
+                    .await?
+            } else {
+                self.miniblock_hashes_match(checked_number).await?
+            };
+            match output {
+                MatchOutput::Match => break Ok((checked_number, None)),
+                MatchOutput::Mismatch(divergence) => break Ok((checked_number, Some(divergence))),
+                MatchOutput::NoRemoteReference => {
+                    tracing::info!(
+                        miniblock = checked_number.0,
+                        "Main node has no miniblock; will check last miniblock on the main node"
+                    );
+                    main_node_sealed_miniblock_number =
+                        self.client().sealed_miniblock_number().await?;
+                    self.trace_response(RpcTraceResponse::SealedMiniblockNumber(
+                        main_node_sealed_miniblock_number,
+                    ));
+                    tracing::debug!(
+                        "Fetched last miniblock on the main node: #{main_node_sealed_miniblock_number}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Compares the number of virtual blocks for the given local miniblock and the same miniblock from the main node.
+    async fn check_virtual_blocks(
+        &mut self,
+        miniblock_number: MiniblockNumber,
+    ) -> Result<(), HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_virtual_blocks = storage
+            .blocks_dal()
+            .get_virtual_blocks_for_miniblock(miniblock_number)
+            .await?
+            .with_context(|| format!("Local miniblock #{miniblock_number} disappeared"))?;
+        drop(storage);
+
+        let remote_virtual_blocks = self
+            .client()
+            .miniblock_virtual_blocks(miniblock_number)
+            .await?;
+        let Some(remote_virtual_blocks) = remote_virtual_blocks else {
+            // The main node doesn't expose virtual blocks for this miniblock; nothing to compare.
+            return Ok(());
+        };
+        if remote_virtual_blocks != local_virtual_blocks {
+            self.event_handler
+                .report_suspicion(ReorgSuspicion::VirtualBlocksMismatch {
+                    miniblock: miniblock_number,
+                    local: local_virtual_blocks,
+                    remote: remote_virtual_blocks,
+                })
+                .await;
+        }
+        Ok(())
+    }
+
+    async fn check_pending_miniblock(&mut self) -> Result<(), HashMatchError> {
+        let Some((miniblock_number, remote_hash)) = self.client().pending_miniblock().await? else {
+            return Ok(());
+        };
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_header = storage
+            .blocks_dal()
+            .get_miniblock_header(miniblock_number)
+            .await?;
+        drop(storage);
+        let Some(local_header) = local_header else {
+            // The local node doesn't have the pending miniblock yet; nothing to compare.
+            return Ok(());
+        };
+        if local_header.hash != remote_hash {
+            self.event_handler
+                .report_suspicion(ReorgSuspicion::PendingMiniblockMismatch {
+                    miniblock: miniblock_number,
+                    local_hash: local_header.hash,
+                    remote_hash,
+                })
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Compares the local proof status of the given L1 batch with the status on the main node.
+    async fn check_proof_status(
+        &mut self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<(), HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let last_proven_l1_batch = storage
+            .blocks_dal()
+            .get_number_of_last_l1_batch_proven_on_eth()
+            .await?;
+        drop(storage);
+        let local_proven = last_proven_l1_batch.map_or(false, |number| number >= l1_batch_number);
+
+        let Some(remote_proven) = self.client().l1_batch_proven(l1_batch_number).await? else {
+            return Ok(());
+        };
+        if remote_proven != local_proven {
+            self.event_handler
+                .report_suspicion(ReorgSuspicion::DivergentProofStatus {
+                    l1_batch: l1_batch_number,
+                    local_proven,
+                    remote_proven,
+                })
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Compares root hashes of the latest local batch and of the same batch from the main node.
+    async fn root_hashes_match(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<MatchOutput, HashMatchError> {
+        let storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        self.root_hashes_match_using(storage, l1_batch_number, None)
+            .await
+    }
+
+    /// Fetches the root hash of the specified L1 batch from the main node, or gets it from the root hash cache.
+    /// Root hashes of L1 batches up to and including `last_finalized_l1_batch` are put into the cache.
+    async fn remote_root_hash(
+        &self,
+        l1_batch_number: L1BatchNumber,
+        last_finalized_l1_batch: Option<L1BatchNumber>,
+    ) -> Result<Option<H256>, HashMatchError> {
+        if let Some(cache) = &self.root_hash_cache {
+            let cached_hash = cache
+                .lock()
+                .expect("root hash cache is poisoned")
+                .get(&l1_batch_number)
+                .copied();
+            if cached_hash.is_some() {
+                return Ok(cached_hash);
+            }
+        }
+
+        let hash = self.client().l1_batch_root_hash(l1_batch_number).await?;
+        self.trace_response(RpcTraceResponse::L1BatchRootHash {
+            number: l1_batch_number,
+            hash,
+        });
+        if let (Some(cache), Some(hash)) = (&self.root_hash_cache, hash) {
+            if last_finalized_l1_batch.map_or(false, |last| l1_batch_number <= last) {
+                cache
+                    .lock()
+                    .expect("root hash cache is poisoned")
+                    .put(l1_batch_number, hash);
+            }
+        }
+        Ok(hash)
+    }
+
+    /// Returns the remote root hash for the specified L1 batch probed during reorg localization. If the search bounds
+    /// are narrow enough, all root hashes within them are prefetched using a single request. Returns `None` if
+    /// the root hash is not prefetched and should be requested separately.
+    async fn prefetched_root_hash(
+        &self,
+        l1_batch_number: L1BatchNumber,
+        (left, right): (u32, u32),
+        prefetched_hashes: &Mutex<HashMap<L1BatchNumber, Option<H256>>>,
+    ) -> Result<Option<Option<H256>>, HashMatchError> {
+        let prefetched_hash = prefetched_hashes
+            .lock()
+            .expect("prefetched hashes are poisoned")
+            .get(&l1_batch_number)
+            .copied();
+        if prefetched_hash.is_some() || right - left - 1 > Self::ROOT_HASH_PREFETCH_WINDOW {
+            return Ok(prefetched_hash);
+        }
+
+        let range = L1BatchNumber(left + 1)..=L1BatchNumber(right - 1);
+        tracing::debug!("Prefetching root hashes for L1 batches {range:?}");
+        let hashes = self.client().l1_batch_root_hashes(range).await?;
+        let mut prefetched_hashes = prefetched_hashes
+            .lock()
+            .expect("prefetched hashes are poisoned");
+        for (number, hash) in (left + 1..right).zip(hashes) {
+            let number = L1BatchNumber(number);
+            self.trace_response(RpcTraceResponse::L1BatchRootHash { number, hash });
+            prefetched_hashes.insert(number, hash);
+        }
+        Ok(prefetched_hashes.get(&l1_batch_number).copied())
+    }
+
+    /// Same as [`Self::root_hashes_match()`], but uses the provided DB connection to get the local root hash,
+    /// and the prefetched remote root hash, if any.
+    async fn root_hashes_match_using(
+        &self,
+        mut storage: StorageProcessor<'_>,
+        l1_batch_number: L1BatchNumber,
+        prefetched_remote_hash: Option<Option<H256>>,
+    ) -> Result<MatchOutput, HashMatchError> {
+        let local_hash = storage
+            .blocks_dal()
+            .get_l1_batch_state_root(l1_batch_number)
+            .await?
+            .with_context(|| {
+                format!("Root hash does not exist for local batch #{l1_batch_number}")
+            })?;
+        let last_finalized_l1_batch = if self.root_hash_cache.is_some() {
+            storage
+                .blocks_dal()
+                .get_number_of_last_l1_batch_executed_on_eth()
+                .await?
+        } else {
+            None
+        };
+        drop(storage);
+        self.compare_root_hashes(
+            l1_batch_number,
+            local_hash,
+            last_finalized_l1_batch,
+            prefetched_remote_hash,
+        )
+        .await
+    }
+
+    /// Compares the provided local root hash of an L1 batch with the root hash from the main node.
+    async fn compare_root_hashes(
+        &self,
+        l1_batch_number: L1BatchNumber,
+        local_hash: H256,
+        last_finalized_l1_batch: Option<L1BatchNumber>,
+        prefetched_remote_hash: Option<Option<H256>>,
+    ) -> Result<MatchOutput, HashMatchError> {
+        let remote_hash = match prefetched_remote_hash {
+            Some(hash) => hash,
+            None => {
+                self.remote_root_hash(l1_batch_number, last_finalized_l1_batch)
+                    .await?
+            }
+        };
+        let Some(remote_hash) = remote_hash else {
+            // Due to reorg, locally we may be ahead of the main node.
+            // Lack of the root hash on the main node is treated as a hash match,
+            // We need to wait for our knowledge of main node to catch up.
+            self.metrics
+                .increment_counter(DetectorCounter::MissingRemoteL1Batches);
+            return Ok(MatchOutput::NoRemoteReference);
+        };
+
+        if remote_hash == local_hash {
+            self.record_verified_l1_batch(l1_batch_number);
+            return Ok(MatchOutput::Match);
+        }
+        tracing::warn!(
+            l1_batch = l1_batch_number.0,
+            local_hash = %self.display_hash(local_hash),
+            remote_hash = %self.display_hash(remote_hash),
+            "Reorg detected: local root hash doesn't match the state hash from main node"
+        );
+        Ok(MatchOutput::Mismatch(DivergenceKind::RootHash {
+            l1_batch: l1_batch_number,
+            local: local_hash,
+            remote: remote_hash,
+        }))
+    }
+
+    /// Compares L2-to-L1 logs tree roots of the specified L1 batch locally and on the main node. The main node
+    /// doesn't expose the root directly, so it's obtained from a log proof for a transaction in the batch
+    /// that has emitted an L2-to-L1 log. If the local batch has no such logs, the roots are not compared.
+    async fn logs_tree_roots_match(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<bool, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let Some(local_root) = storage
+            .blocks_dal()
+            .get_l1_batch_l2_l1_merkle_root(l1_batch_number)
+            .await?
+        else {
+            tracing::debug!(
+                "L1 batch #{l1_batch_number} has no L2-to-L1 logs tree root yet; skipping its comparison"
+            );
+            return Ok(true);
+        };
+        let logs = storage
+            .blocks_web3_dal()
+            .get_l2_to_l1_logs(l1_batch_number)
+            .await?;
+        let Some(first_log) = logs.first() else {
+            tracing::debug!(
+                "L1 batch #{l1_batch_number} has no L2-to-L1 logs; skipping logs tree root comparison"
+            );
+            return Ok(true);
+        };
+        let tx_index = first_log.tx_number_in_block.into();
+        let tx_hash = storage
+            .transactions_web3_dal()
+            .get_transaction_hash_by_l1_batch_position(l1_batch_number, tx_index)
+            .await?
+            .with_context(|| {
+                format!("Transaction #{tx_index} does not exist for local batch #{l1_batch_number}")
+            })?;
+        drop(storage);
+
+        let remote_root = self.client().l2_to_l1_logs_root_hash(tx_hash).await?;
+        if remote_root != Some(local_root) {
+            let remote_root = remote_root.map_or_else(
+                || "(none)".to_owned(),
+                |root| self.display_hash(root).to_string(),
+            );
+            tracing::warn!(
+                l1_batch = l1_batch_number.0,
+                local_root = %self.display_hash(local_root),
+                %remote_root,
+                "Reorg detected: local L2-to-L1 logs tree root doesn't match the root from main node"
+            );
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Compares commitments of the specified L1 batch locally and on the main node. If either of commitments
+    /// is not computed yet, they are not compared.
+    async fn commitments_match(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<bool, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_commitment = storage
+            .blocks_dal()
+            .get_l1_batch_commitment(l1_batch_number)
+            .await?;
+        drop(storage);
+        let Some(local_commitment) = local_commitment else {
+            tracing::debug!(
+                "L1 batch #{l1_batch_number} has no commitment yet; skipping its comparison"
+            );
+            return Ok(true);
+        };
+
+        let Some(remote_commitment) = self.client().l1_batch_commitment(l1_batch_number).await?
+        else {
+            tracing::debug!(
+                "Main node has no commitment for L1 batch #{l1_batch_number}; skipping its comparison"
+            );
+            return Ok(true);
+        };
+        if remote_commitment != local_commitment {
+            tracing::warn!(
+                l1_batch = l1_batch_number.0,
+                local_commitment = %self.display_hash(local_commitment),
+                remote_commitment = %self.display_hash(remote_commitment),
+                "Reorg detected: local commitment doesn't match the commitment from main node"
+            );
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Compares pass-through data hashes of the specified L1 batch locally and on the main node. If either of hashes
+    /// is not computed yet, they are not compared. Returns the diverged hashes, if any.
+    async fn pass_through_data_divergence(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<DivergenceKind>, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_hash = storage
+            .blocks_dal()
+            .get_l1_batch_pass_through_data_hash(l1_batch_number)
+            .await?;
+        drop(storage);
+        let Some(local_hash) = local_hash else {
+            tracing::debug!(
+                "L1 batch #{l1_batch_number} has no pass-through data hash yet; skipping its comparison"
+            );
+            return Ok(None);
+        };
+
+        let Some(remote_hash) = self
+            .client()
+            .l1_batch_pass_through_data_hash(l1_batch_number)
+            .await?
+        else {
+            tracing::debug!(
+                "Main node has no pass-through data hash for L1 batch #{l1_batch_number}; skipping its comparison"
+            );
+            return Ok(None);
+        };
+        if remote_hash == local_hash {
+            return Ok(None);
+        }
+        tracing::warn!(
+            l1_batch = l1_batch_number.0,
+            local_hash = %self.display_hash(local_hash),
+            remote_hash = %self.display_hash(remote_hash),
+            "Reorg detected: local pass-through data hash doesn't match the hash from main node"
+        );
+        Ok(Some(DivergenceKind::PassThroughDataHash {
+            l1_batch: l1_batch_number,
+            local: local_hash,
+            remote: remote_hash,
+        }))
+    }
+
+    /// Compares the number of miniblocks in the specified L1 batch locally and on the main node. If the batch
+    /// is missing either locally or on the main node, counts are not compared.
+    async fn miniblock_counts_match(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<bool, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_range = storage
+            .blocks_dal()
+            .get_miniblock_range_of_l1_batch(l1_batch_number)
+            .await?;
+        drop(storage);
+        let Some((local_first, local_last)) = local_range else {
+            tracing::debug!(
+                "L1 batch #{l1_batch_number} has no miniblocks locally; skipping miniblock count comparison"
+            );
+            return Ok(true);
+        };
+        let Some((remote_first, remote_last)) = self
+            .client()
+            .l1_batch_miniblock_range(l1_batch_number)
+            .await?
+        else {
+            tracing::debug!(
+                "Main node has no miniblock range for L1 batch #{l1_batch_number}; skipping miniblock count comparison"
+            );
+            return Ok(true);
+        };
+
+        let local_count = local_last.0.saturating_sub(local_first.0) + 1;
+        let remote_count = remote_last.0.saturating_sub(remote_first.0) + 1;
+        if local_count != remote_count {
+            tracing::warn!(
+                l1_batch = l1_batch_number.0,
+                local_count,
+                remote_count,
+                local_range = ?(local_first.0..=local_last.0),
+                remote_range = ?(remote_first.0..=remote_last.0),
+                "Reorg detected: local number of miniblocks in L1 batch doesn't match the number on main node"
+            );
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Compares optional L1 batch hashes (i.e., ones other than the state root hash) enabled for the detector.
+    /// Returns the first diverged hash, if any.
+    async fn diverged_optional_hash(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<DivergedHash>, HashMatchError> {
+        if self.compare_logs_tree_roots && !self.logs_tree_roots_match(l1_batch_number).await? {
+            return Ok(Some(DivergedHash::LogsTreeRoot));
+        }
+        if self.compare_commitments && !self.commitments_match(l1_batch_number).await? {
+            return Ok(Some(DivergedHash::Commitment));
+        }
+        if self.compare_pass_through_data
+            && self
+                .pass_through_data_divergence(l1_batch_number)
+                .await?
+                .is_some()
+        {
+            return Ok(Some(DivergedHash::PassThroughData));
+        }
+        if self.compare_miniblock_counts && !self.miniblock_counts_match(l1_batch_number).await? {
+            return Ok(Some(DivergedHash::MiniblockCount));
+        }
+        Ok(None)
+    }
+
+    /// Returns the hash that has diverged for the specified L1 batch, or `None` if the batch matches the main node.
+    async fn diverged_hash(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<DivergedHash>, HashMatchError> {
+        match self.root_hashes_match(l1_batch_number).await? {
+            MatchOutput::Match => self.diverged_optional_hash(l1_batch_number).await,
+            MatchOutput::Mismatch(_) => Ok(Some(DivergedHash::RootHash)),
+            MatchOutput::NoRemoteReference => Ok(None),
+        }
+    }
+
+    /// Compares transactions in miniblocks of the specified L1 batch with the main node. Returns only differing miniblocks.
+    async fn diagnose_divergence(
+        &self,
+        diverged_l1_batch: L1BatchNumber,
+    ) -> Result<Vec<MiniblockTransactionsDiff>, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let Some((first_miniblock, last_miniblock)) = storage
+            .blocks_dal()
+            .get_miniblock_range_of_l1_batch(diverged_l1_batch)
+            .await?
+        else {
+            return Ok(vec![]); // The diverged L1 batch is not sealed locally
+        };
+        drop(storage);
+
+        let mut diffs = vec![];
+        for number in first_miniblock.0..=last_miniblock.0 {
+            let miniblock = MiniblockNumber(number);
+            let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+            let local_hashes = storage
+                .blocks_dal()
+                .get_miniblock_transaction_hashes(miniblock)
+                .await?;
+            drop(storage);
+            let remote_hashes = self
+                .client()
+                .miniblock_transaction_hashes(miniblock)
+                .await?
+                .unwrap_or_default();
+            let diff = MiniblockTransactionsDiff::new(miniblock, &local_hashes, &remote_hashes);
+            if !diff.is_empty() {
+                diffs.push(diff);
+            }
+        }
+        Ok(diffs)
+    }
+
+    /// Logs the [divergence diagnostics](Self::with_divergence_diagnostics()) for the specified L1 batch if enabled.
+    /// Errors are logged rather than returned since diagnostics are informational.
+    async fn log_divergence_diagnostics(&self, diverged_l1_batch: L1BatchNumber) {
+        if !self.diagnose_divergence {
+            return;
+        }
+        match self.diagnose_divergence(diverged_l1_batch).await {
+            Ok(diffs) if diffs.is_empty() => {
+                tracing::info!(
+                    diverged_l1_batch = diverged_l1_batch.0,
+                    "Transactions in the first diverged L1 batch match the main node"
+                );
+            }
+            Ok(diffs) => {
+                for diff in diffs {
+                    tracing::warn!(
+                        diverged_l1_batch = diverged_l1_batch.0,
+                        miniblock = diff.miniblock.0,
+                        local_only = ?diff.local_only,
+                        remote_only = ?diff.remote_only,
+                        reordered = diff.reordered,
+                        "Transactions in miniblock differ from the main node"
+                    );
+                }
+            }
+            Err(err) => {
+                tracing::warn!(
+                    diverged_l1_batch = diverged_l1_batch.0,
+                    ?err,
+                    "Failed diagnosing divergence"
+                );
+            }
+        }
+    }
+
+    /// Updates the highest verified L1 batch number (both head checks and scans contribute to it).
+    fn record_verified_l1_batch(&self, l1_batch_number: L1BatchNumber) {
+        let prev_max = self
+            .max_verified_l1_batch_number
+            .fetch_max(l1_batch_number.0, Ordering::Relaxed);
+        if l1_batch_number.0 > prev_max {
+            self.metrics
+                .record_gauge(DetectorGauge::MaxVerifiedL1Batch, l1_batch_number.0.into());
+        }
+    }
+
+    /// Checks hash correspondence for the latest L1 batch sealed and having metadata both locally and on the main node.
+    /// Returns the checked L1 batch and the diverged hashes, or `None` if the hashes match.
+    async fn check_sealed_l1_batch_root_hash(
+        &self,
+        snapshot: &LocalSnapshot,
+    ) -> Result<(L1BatchNumber, Option<DivergenceKind>), HashMatchError> {
+        let sealed_l1_batch_number = snapshot.l1_batch;
+        let mut main_node_sealed_l1_batch_number = sealed_l1_batch_number;
+        loop {
+            let checked_number = sealed_l1_batch_number.min(main_node_sealed_l1_batch_number);
+            let output = if checked_number == sealed_l1_batch_number {
+                self.compare_root_hashes(
+                    checked_number,
+                    snapshot.l1_batch_root_hash,
+                    snapshot.last_finalized_l1_batch,
+                    None,
+                )
+                .await?
+            } else {
+                self.root_hashes_match(checked_number).await?
+            };
+            match output {
+                MatchOutput::Match => break Ok((checked_number, None)),
+                MatchOutput::Mismatch(divergence) => break Ok((checked_number, Some(divergence))),
+                MatchOutput::NoRemoteReference => {
+                    tracing::info!(
+                        l1_batch = checked_number.0,
+                        "Main node has no L1 batch; will check last L1 batch on the main node"
+                    );
+                    let fetched_number = self.client().sealed_l1_batch_number().await?;
+                    self.trace_response(RpcTraceResponse::SealedL1BatchNumber(fetched_number));
+                    tracing::debug!("Fetched last L1 batch on the main node: #{fetched_number}");
+                    let number_changed = fetched_number != main_node_sealed_l1_batch_number;
+                    main_node_sealed_l1_batch_number = fetched_number;
+
+                    if !number_changed {
+                        // May happen if the main node has an L1 batch, but its state root hash is not computed yet.
+                        tracing::debug!("Last L1 batch number on the main node has not changed; waiting until its state hash is computed");
+                        self.clock.sleep(self.sleep_interval / 10).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that all L1 batches in the specified range have metadata in the local DB. Since batches obtain
+    /// metadata sequentially, a gap means that the local DB is inconsistent, and comparing hashes
+    /// may anchor on a wrong batch.
+    async fn check_local_batch_continuity(
+        storage: &mut StorageProcessor<'_>,
+        first_l1_batch: L1BatchNumber,
+        last_l1_batch: L1BatchNumber,
+    ) -> Result<(), HashMatchError> {
+        if last_l1_batch < first_l1_batch {
+            return Ok(());
+        }
+        let l1_batch_count = storage
+            .blocks_dal()
+            .count_l1_batches_with_metadata(first_l1_batch..=last_l1_batch)
+            .await?;
+
+        let expected_count = u64::from(last_l1_batch.0 - first_l1_batch.0) + 1;
+        if l1_batch_count != expected_count {
+            return Err(HashMatchError::LocalBatchGap(first_l1_batch, last_l1_batch));
+        }
+        Ok(())
+    }
+
+    /// Compares the latest protocol version known locally with the one on the main node, reporting a mismatch.
+    async fn check_protocol_version(&mut self) -> Result<(), HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_version = storage.protocol_versions_dal().last_version_id().await;
+        drop(storage);
+        let Some(local_version) = local_version else {
+            return Ok(());
+        };
+
+        let local_version = local_version as u16;
+        let remote_version = self.client().protocol_version().await?;
+        if local_version != remote_version {
+            self.event_handler
+                .report_suspicion(ReorgSuspicion::ProtocolVersionMismatch {
+                    local: local_version,
+                    remote: remote_version,
+                })
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Checks that the `eth` and `zks` namespaces of the main node report coherent chain state.
+    async fn check_remote_namespace_coherence(&self) -> Result<(), HashMatchError> {
+        // The L1 batch number is fetched first, so that the miniblock number fetched afterwards should cover it.
+        let sealed_l1_batch_number = self.client().sealed_l1_batch_number().await?;
+        self.trace_response(RpcTraceResponse::SealedL1BatchNumber(
+            sealed_l1_batch_number,
+        ));
+        let sealed_miniblock_number = self.client().sealed_miniblock_number().await?;
+        self.trace_response(RpcTraceResponse::SealedMiniblockNumber(
+            sealed_miniblock_number,
+        ));
+        let miniblock_range = self
+            .client()
+            .l1_batch_miniblock_range(sealed_l1_batch_number)
+            .await?;
+
+        let is_coherent =
+            miniblock_range.map_or(false, |(_, last)| last <= sealed_miniblock_number);
+        if !is_coherent {
+            tracing::warn!(
+                l1_batch = sealed_l1_batch_number.0,
+                ?miniblock_range,
+                miniblock = sealed_miniblock_number.0,
+                "Main node namespaces are incoherent: sealed L1 batch doesn't cover the sealed miniblock"
+            );
+            return Err(HashMatchError::IncoherentNamespaces {
+                l1_batch: sealed_l1_batch_number,
+                miniblock: sealed_miniblock_number,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the main node serves the expected chain fork, if one is configured.
+    async fn check_fork_id(&self) -> Result<(), HashMatchError> {
+        let Some(expected) = self.expected_fork_id else {
+            return Ok(());
+        };
+        let actual = self.client().fork_id().await?;
+        if actual != Some(expected) {
+            return Err(HashMatchError::ForkMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Checks whether a previously detected reorg was rolled back. If it was, resets the persisted reorg info.
+    async fn check_prior_reorg(&self) -> Result<(), HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let Some(diverged_l1_batch) = storage.reorg_detector_dal().get_diverged_l1_batch().await?
+        else {
+            return Ok(());
+        };
+        let sealed_l1_batch = storage
+            .blocks_dal()
+            .get_sealed_l1_batch_number()
+            .await?
+            .context("L1 batches table unexpectedly emptied")?;
+        if sealed_l1_batch >= diverged_l1_batch {
+            return Err(HashMatchError::UnresolvedPriorReorg(diverged_l1_batch));
+        }
+
+        tracing::info!(
+            diverged_l1_batch = diverged_l1_batch.0,
+            sealed_l1_batch = sealed_l1_batch.0,
+            "Reorg was rolled back in the local DB"
+        );
+        storage
+            .reorg_detector_dal()
+            .set_diverged_l1_batch(None)
+            .await?;
+        Ok(())
+    }
+
+    async fn persist_reorg(&self, diverged_l1_batch: L1BatchNumber) -> Result<(), HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        storage
+            .reorg_detector_dal()
+            .set_diverged_l1_batch(Some(diverged_l1_batch))
+            .await?;
+        storage
+            .reorg_detector_dal()
+            .set_reorg_search_bounds(None)
+            .await?;
+        Ok(())
+    }
+
+    /// Rolls back Postgres data after the specified L1 batch using the block reverter. Used
+    /// in the [automatic rollback mode](Self::with_auto_revert()). Other node state (the Merkle tree
+    /// and the state keeper cache) is not touched since it may be in use by concurrently running components.
+    async fn revert_l1_batches(
+        &mut self,
+        last_correct_l1_batch: L1BatchNumber,
+    ) -> Result<(), HashMatchError> {
+        tracing::warn!(
+            last_correct_l1_batch = last_correct_l1_batch.0,
+            "Automatically rolling back L1 batches after the last correct one in the local DB"
+        );
+        let reverter = self
+            .block_reverter
+            .as_ref()
+            .context("block reverter is not configured")?;
+        reverter
+            .rollback_db(last_correct_l1_batch, BlockReverterFlags::POSTGRES)
+            .await;
+
+        // Forget about rolled back blocks.
+        self.last_correct_blocks = None;
+        self.reset_localization_bounds();
+        self.contiguous_l1_batch_number = self
+            .contiguous_l1_batch_number
+            .map(|number| number.min(last_correct_l1_batch));
+        self.max_verified_l1_batch_number
+            .fetch_min(last_correct_l1_batch.0, Ordering::Relaxed);
+        if let Some(cache) = &self.root_hash_cache {
+            cache.lock().expect("root hash cache is poisoned").clear();
+        }
+        if self.persist_reorgs {
+            // Clears the persisted reorg since it's rolled back.
+            self.check_prior_reorg().await?;
+            if let Some(checkpoint) = self.last_correct_l1_batch_checkpoint {
+                if checkpoint > last_correct_l1_batch {
+                    self.persist_last_correct_l1_batch(last_correct_l1_batch)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_last_correct_l1_batch_checkpoint(&mut self) -> Result<(), HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        self.last_correct_l1_batch_checkpoint = storage
+            .reorg_detector_dal()
+            .get_last_correct_l1_batch()
+            .await?;
+        if let Some(checkpoint) = self.last_correct_l1_batch_checkpoint {
+            tracing::info!(
+                last_correct_l1_batch = checkpoint.0,
+                "Loaded persisted last correct L1 batch"
+            );
+        }
+        Ok(())
+    }
+
+    async fn persist_last_correct_l1_batch(
+        &mut self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<(), HashMatchError> {
+        if self.last_correct_l1_batch_checkpoint == Some(l1_batch_number) {
+            return Ok(());
+        }
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        storage
+            .reorg_detector_dal()
+            .set_last_correct_l1_batch(Some(l1_batch_number))
+            .await?;
+        self.last_correct_l1_batch_checkpoint = Some(l1_batch_number);
+        Ok(())
+    }
+
+    /// Returns the known valid L1 batch to start reorg localization from. This is the start of the fast localization
+    /// window, or the persisted last correct L1 batch, or the trusted L1 batch floor, or the earliest L1 batch,
+    /// whichever is the greatest among the ones that are set, within the search range and match the main node.
+    async fn localization_lower_bound(
+        &self,
+        earliest_l1_batch_number: L1BatchNumber,
+        diverged_l1_batch_number: L1BatchNumber,
+    ) -> Result<L1BatchNumber, HashMatchError> {
+        let lower_bound = self
+            .trusted_floor_lower_bound(earliest_l1_batch_number, diverged_l1_batch_number)
+            .await?;
+        let lower_bound = self
+            .checkpoint_lower_bound(lower_bound, diverged_l1_batch_number)
+            .await?;
+        self.fast_localization_lower_bound(lower_bound, diverged_l1_batch_number)
+            .await
+    }
+
+    /// Returns the persisted last correct L1 batch if it is within the search range and still matches the main node,
+    /// or `lower_bound` otherwise.
+    async fn checkpoint_lower_bound(
+        &self,
+        lower_bound: L1BatchNumber,
+        diverged_l1_batch_number: L1BatchNumber,
+    ) -> Result<L1BatchNumber, HashMatchError> {
+        let Some(checkpoint) = self.last_correct_l1_batch_checkpoint else {
+            return Ok(lower_bound);
+        };
+        if checkpoint <= lower_bound || checkpoint >= diverged_l1_batch_number {
+            return Ok(lower_bound);
+        }
+        // The checkpoint may be affected by a reorg deeper than the checkpoint itself, so it must be rechecked.
+        Ok(match self.root_hashes_match(checkpoint).await? {
+            MatchOutput::Match => {
+                tracing::info!(
+                    known_valid_l1_batch = checkpoint.0,
+                    "Starting reorg localization from persisted last correct L1 batch"
+                );
+                checkpoint
+            }
+            MatchOutput::Mismatch(_) | MatchOutput::NoRemoteReference => lower_bound,
+        })
+    }
+
+    /// Returns the start of the fast localization window if fast localization is enabled, the start is within
+    /// the search range and matches the main node, or `lower_bound` otherwise.
+    async fn fast_localization_lower_bound(
+        &self,
+        lower_bound: L1BatchNumber,
+        diverged_l1_batch_number: L1BatchNumber,
+    ) -> Result<L1BatchNumber, HashMatchError> {
+        let Some(window) = self.fast_localization_window else {
+            return Ok(lower_bound);
+        };
+        let window_start = L1BatchNumber(diverged_l1_batch_number.0.saturating_sub(window));
+        if window_start <= lower_bound {
+            return Ok(lower_bound);
+        }
+        Ok(match self.root_hashes_match(window_start).await? {
+            MatchOutput::Match => {
+                tracing::info!(
+                    known_valid_l1_batch = window_start.0,
+                    diverged_l1_batch = diverged_l1_batch_number.0,
+                    window,
+                    "Starting fast reorg localization"
+                );
+                window_start
+            }
+            MatchOutput::Mismatch(_) => {
+                tracing::warn!(
+                    window_start = window_start.0,
+                    known_valid_l1_batch = lower_bound.0,
+                    "L1 batch starting the fast localization window has diverged; falling back to full localization"
+                );
+                lower_bound
+            }
+            MatchOutput::NoRemoteReference => lower_bound,
+        })
+    }
+
+    /// Returns the trusted L1 batch floor (the configured one, or the last executed L1 batch) if it is set and within
+    /// the search range, or the earliest L1 batch otherwise.
+    async fn trusted_floor_lower_bound(
+        &self,
+        earliest_l1_batch_number: L1BatchNumber,
+        diverged_l1_batch_number: L1BatchNumber,
+    ) -> Result<L1BatchNumber, HashMatchError> {
+        let executed_l1_batch = if self.use_executed_l1_batch_floor {
+            self.client().last_executed_l1_batch().await?
+        } else {
+            None
+        };
+        let (floor, is_executed) = match (self.trusted_l1_batch_floor, executed_l1_batch) {
+            (Some(floor), Some(executed)) if floor >= executed => (floor, false),
+            (_, Some(executed)) => (executed, true),
+            (Some(floor), None) => (floor, false),
+            (None, None) => return Ok(earliest_l1_batch_number),
+        };
+        if floor <= earliest_l1_batch_number || floor >= diverged_l1_batch_number {
+            return Ok(earliest_l1_batch_number);
+        }
+        Ok(match self.root_hashes_match(floor).await? {
+            MatchOutput::Match => {
+                tracing::info!(
+                    known_valid_l1_batch = floor.0,
+                    is_executed,
+                    "Starting reorg localization from trusted L1 batch floor"
+                );
+                floor
+            }
+            MatchOutput::Mismatch(_) if is_executed => {
+                return Err(HashMatchError::ExecutedL1BatchMismatch(floor));
+            }
+            MatchOutput::Mismatch(_) => return Err(HashMatchError::TrustedFloorMismatch(floor)),
+            MatchOutput::NoRemoteReference => earliest_l1_batch_number,
+        })
+    }
+
+    /// Narrows the reorg localization range to the bounds retained from an interrupted localization,
+    /// provided that they are within the range.
+    fn narrow_localization_range(
+        &self,
+        known_valid: L1BatchNumber,
+        diverged: L1BatchNumber,
+    ) -> (L1BatchNumber, L1BatchNumber) {
+        let bounds = *self
+            .localization_bounds
+            .lock()
+            .expect("localization bounds are poisoned");
+        match bounds {
+            Some((retained_valid, retained_diverged))
+                if known_valid <= retained_valid && retained_diverged <= diverged =>
+            {
+                tracing::info!(
+                    known_valid_l1_batch = retained_valid.0,
+                    diverged_l1_batch = retained_diverged.0,
+                    "Resuming interrupted reorg localization"
+                );
+                (retained_valid, retained_diverged)
+            }
+            _ => (known_valid, diverged),
+        }
+    }
+
+    fn reset_localization_bounds(&self) {
+        *self
+            .localization_bounds
+            .lock()
+            .expect("localization bounds are poisoned") = None;
+    }
+
+    async fn persist_search_bounds(
+        &self,
+        known_valid: L1BatchNumber,
+        known_diverged: L1BatchNumber,
+    ) -> Result<(), HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        storage
+            .reorg_detector_dal()
+            .set_reorg_search_bounds(Some((known_valid, known_diverged)))
+            .await?;
+        Ok(())
+    }
+
+    /// Localizes a re-org: performs search (by default, binary one) to determine the last non-diverged L1 batch
+    /// in the `known_valid_l1_batch..diverged_l1_batch` range. This is the same search [`Self::run()`] performs
+    /// once a divergence is detected; L1 batches missing on the main node are treated as matching.
+    ///
+    /// The caller is responsible for ensuring that `known_valid_l1_batch` matches the main node and
+    /// `diverged_l1_batch` doesn't. These L1 batches are not probed, so if this invariant is violated,
+    /// the returned L1 batch is meaningless.
+    pub async fn detect_reorg(
+        &self,
+        known_valid_l1_batch: L1BatchNumber,
+        diverged_l1_batch: L1BatchNumber,
+    ) -> Result<L1BatchNumber, Error> {
+        Ok(self
+            .detect_reorg_inner(known_valid_l1_batch, diverged_l1_batch)
+            .await?)
+    }
+
+    async fn detect_reorg_inner(
+        &self,
+        known_valid_l1_batch: L1BatchNumber,
+        diverged_l1_batch: L1BatchNumber,
+    ) -> Result<L1BatchNumber, HashMatchError> {
+        match self
+            .detect_reorg_cancellable(known_valid_l1_batch, diverged_l1_batch, None, false)
+            .await?
+        {
+            SearchResult::Complete(last_correct_l1_batch) => Ok(last_correct_l1_batch),
+            SearchResult::Partial { .. } => unreachable!("search cannot be cancelled"),
+        }
+    }
+
+    /// Localizes the last miniblock matching the main node in the `known_valid..diverged` range
+    /// using binary search over miniblock hashes. If the range is small enough, remote hashes are fetched
+    /// for the entire range at once.
+    async fn detect_miniblock_reorg(
+        &self,
+        known_valid_miniblock: MiniblockNumber,
+        diverged_miniblock: MiniblockNumber,
+    ) -> Result<MiniblockNumber, HashMatchError> {
+        let mut prefetched_hashes = HashMap::new();
+        let range_len = diverged_miniblock
+            .0
+            .saturating_sub(known_valid_miniblock.0)
+            .saturating_sub(1);
+        if range_len > 0 && range_len <= Self::MINIBLOCK_HASH_PREFETCH_WINDOW {
+            let range = MiniblockNumber(known_valid_miniblock.0 + 1)
+                ..=MiniblockNumber(diverged_miniblock.0 - 1);
+            tracing::debug!("Prefetching hashes for miniblocks {range:?}");
+            let hashes = self.client().miniblock_hashes(range.clone()).await?;
+            let numbers = (range.start().0..=range.end().0).map(MiniblockNumber);
+            prefetched_hashes.extend(numbers.zip(hashes));
+        }
+
+        let prefetched_hashes = &prefetched_hashes;
+        let predicate = |number| async move {
+            let number = MiniblockNumber(number);
+            let prefetched_hash = prefetched_hashes.get(&number).copied();
+            let output = self
+                .miniblock_hashes_match_using(number, prefetched_hash)
+                .await?;
+            Ok::<_, HashMatchError>(!matches!(output, MatchOutput::Mismatch(_)))
+        };
+        let last_correct_miniblock =
+            binary_search_with(known_valid_miniblock.0, diverged_miniblock.0, predicate).await?;
+        Ok(MiniblockNumber(last_correct_miniblock))
+    }
+
+    /// Localizes the last correct miniblock after a miniblock-level divergence, starting from the last miniblock
+    /// in the last correct L1 batch. Returns `None` if the L1 batch has no miniblocks locally.
+    async fn localize_miniblock_reorg(
+        &self,
+        last_correct_l1_batch: L1BatchNumber,
+        diverged_miniblock: MiniblockNumber,
+    ) -> Result<Option<MiniblockNumber>, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let miniblock_range = storage
+            .blocks_dal()
+            .get_miniblock_range_of_l1_batch(last_correct_l1_batch)
+            .await?;
+        drop(storage);
+        let Some((_, known_valid_miniblock)) = miniblock_range else {
+            return Ok(None);
+        };
+        if known_valid_miniblock >= diverged_miniblock {
+            return Ok(None);
+        }
+
+        tracing::info!(
+            known_valid_miniblock = known_valid_miniblock.0,
+            diverged_miniblock = diverged_miniblock.0,
+            "Searching for the first diverged miniblock"
+        );
+        let last_correct_miniblock = self
+            .detect_miniblock_reorg(known_valid_miniblock, diverged_miniblock)
+            .await?;
+        tracing::info!(
+            last_correct_miniblock = last_correct_miniblock.0,
+            "Localized last correct miniblock"
+        );
+        Ok(Some(last_correct_miniblock))
+    }
+
+    /// Same as [`Self::detect_reorg()`], but interrupts the search if a stop signal is received,
+    /// returning the best known search bounds. An in-flight probe (e.g., a slow call to the main node) is aborted
+    /// rather than awaited, so that the node can shut down promptly. If `persist_bounds` is set, search bounds are persisted
+    /// after each probe, so that the search can be resumed if the node is terminated abruptly; the persisted bounds
+    /// are reset once the search completes.
+    #[tracing::instrument(
+        name = "detect_reorg",
+        skip_all,
+        fields(
+            known_valid_l1_batch = known_valid_l1_batch.0,
+            diverged_l1_batch = diverged_l1_batch.0,
+        )
+    )]
+    async fn detect_reorg_cancellable(
+        &self,
+        known_valid_l1_batch: L1BatchNumber,
+        diverged_l1_batch: L1BatchNumber,
+        stop_receiver: Option<&watch::Receiver<bool>>,
+        persist_bounds: bool,
+    ) -> Result<SearchResult, HashMatchError> {
+        // TODO (BFT-176, BFT-181): We have to look through the whole history, since batch status updater may mark
+        //   a block as executed even if the state diverges for it.
+        let search_bounds = Mutex::new((known_valid_l1_batch.0, diverged_l1_batch.0));
+        let search_bounds = &search_bounds;
+        let prefetched_hashes = Mutex::default();
+        let prefetched_hashes = &prefetched_hashes;
+        let probe_count = &AtomicUsize::new(0);
+        let predicate = |number| {
+            let span =
+                tracing::debug_span!("probe", l1_batch = number, is_valid = tracing::field::Empty);
+            async move {
+                probe_count.fetch_add(1, Ordering::Relaxed);
+                let number = L1BatchNumber(number);
+                let bounds = *search_bounds.lock().expect("search bounds are poisoned");
+                let remote_hash = self
+                    .prefetched_root_hash(number, bounds, prefetched_hashes)
+                    .await?;
+                let storage = self.reacquire_storage().await?;
+                let output = self
+                    .root_hashes_match_using(storage, number, remote_hash)
+                    .await?;
+                let is_valid = match output {
+                    MatchOutput::Match => self.diverged_optional_hash(number).await?.is_none(),
+                    MatchOutput::NoRemoteReference => true,
+                    MatchOutput::Mismatch(_) => false,
+                };
+                let (left, right) = bounds;
+                let (known_valid, known_diverged) = if is_valid {
+                    (number, L1BatchNumber(right))
+                } else {
+                    (L1BatchNumber(left), number)
+                };
+                *search_bounds.lock().expect("search bounds are poisoned") =
+                    (known_valid.0, known_diverged.0);
+                *self
+                    .localization_bounds
+                    .lock()
+                    .expect("localization bounds are poisoned") =
+                    Some((known_valid, known_diverged));
+                if persist_bounds {
+                    self.persist_search_bounds(known_valid, known_diverged)
+                        .await?;
+                }
+                tracing::Span::current().record("is_valid", is_valid);
+                Ok::<_, HashMatchError>(is_valid)
+            }
+            .instrument(span)
+        };
+        let select_probe = |left, right| {
+            *search_bounds.lock().expect("search bounds are poisoned") = (left, right);
+            match &self.probe_strategy {
+                Some(strategy) => {
+                    strategy
+                        .next_probe(L1BatchNumber(left), L1BatchNumber(right))
+                        .0
+                }
+                None => (left + right) / 2,
+            }
+        };
+        let is_cancelled = || stop_receiver.map_or(false, |receiver| *receiver.borrow());
+        let search = search_with_cancellation(
+            known_valid_l1_batch.0,
+            diverged_l1_batch.0,
+            select_probe,
+            predicate,
+            is_cancelled,
+        );
+        let outcome = if let Some(stop_receiver) = stop_receiver {
+            let mut stop_receiver = stop_receiver.clone();
+            let stop_signal = async move {
+                if stop_receiver.wait_for(|&stop| stop).await.is_err() {
+                    // The stop sender is dropped, so the search can no longer be interrupted.
+                    future::pending::<()>().await;
+                }
+            };
+            tokio::select! {
+                outcome = search => outcome?,
+                () = stop_signal => {
+                    let (left, right) = *search_bounds.lock().expect("search bounds are poisoned");
+                    SearchOutcome::Cancelled { left, right }
+                }
+            }
+        } else {
+            search.await?
+        };
+        let outcome = match outcome {
+            SearchOutcome::Complete(number) => {
+                self.metrics.record_count_histogram(
+                    DetectorCountHistogram::LocalizationProbes,
+                    probe_count.load(Ordering::Relaxed),
+                );
+                let last_correct_l1_batch = self
+                    .verify_localized_reorg(
+                        known_valid_l1_batch,
+                        L1BatchNumber(number),
+                        diverged_l1_batch,
+                    )
+                    .await?;
+                SearchOutcome::Complete(last_correct_l1_batch.0)
+            }
+            cancelled @ SearchOutcome::Cancelled { .. } => cancelled,
+        };
+        if matches!(outcome, SearchOutcome::Complete(_)) {
+            self.reset_localization_bounds();
+        }
+        if persist_bounds && matches!(outcome, SearchOutcome::Complete(_)) {
+            let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+            storage
+                .reorg_detector_dal()
+                .set_reorg_search_bounds(None)
+                .await?;
+        }
+        Ok(match outcome {
+            SearchOutcome::Complete(number) => SearchResult::Complete(L1BatchNumber(number)),
+            SearchOutcome::Cancelled { left, right } => SearchResult::Partial {
+                known_valid: L1BatchNumber(left),
+                known_diverged: L1BatchNumber(right),
+            },
+        })
+    }
+
+    /// Checks that the L1 batch following the localized last correct one has really diverged. Localization assumes
+    /// that divergences are monotonic (all L1 batches up to a certain one match the main node, and all following
+    /// ones diverge); if this check fails, the assumption is violated, and the first diverged L1 batch is determined
+    /// by a linear scan of the entire search range instead.
+    async fn verify_localized_reorg(
+        &self,
+        known_valid_l1_batch: L1BatchNumber,
+        last_correct_l1_batch: L1BatchNumber,
+        diverged_l1_batch: L1BatchNumber,
+    ) -> Result<L1BatchNumber, HashMatchError> {
+        let first_diverged_l1_batch = last_correct_l1_batch + 1;
+        // The diverged search bound is provided by the caller and is not probed.
+        if first_diverged_l1_batch >= diverged_l1_batch
+            || self.diverged_hash(first_diverged_l1_batch).await?.is_some()
+        {
+            return Ok(last_correct_l1_batch);
+        }
+
+        tracing::error!(
+            known_valid_l1_batch = known_valid_l1_batch.0,
+            diverged_l1_batch = diverged_l1_batch.0,
+            last_correct_l1_batch = last_correct_l1_batch.0,
+            "Reorg localization is inconsistent: L1 batch following the localized last correct one matches \
+             the main node. Divergences are not monotonic, which should never happen; falling back to a linear scan"
+        );
+        let first_diverged_l1_batch = self
+            .first_diverged_l1_batch(known_valid_l1_batch + 1, diverged_l1_batch - 1)
+            .await?
+            .unwrap_or(diverged_l1_batch);
+        Ok(first_diverged_l1_batch - 1)
+    }
+
+    /// Reacquires a DB connection during reorg localization. Since the connection is dropped for RPC calls,
+    /// reacquiring it may fail under heavy pool contention even if the initial acquisition has succeeded.
+    /// Such failures are retried with exponential backoff, so that a transient pool blip doesn't abort localization.
+    async fn reacquire_storage(&self) -> Result<StorageProcessor<'_>, HashMatchError> {
+        let mut backoff = Self::STORAGE_REACQUISITION_BACKOFF;
+        let mut retry_count = 0;
+        loop {
+            let err = match self.pool.access_storage_tagged("reorg_detector").await {
+                Ok(storage) => return Ok(storage),
+                Err(err) => err,
+            };
+            self.metrics
+                .increment_counter(DetectorCounter::StorageReacquisitionFailures);
+            if retry_count == Self::MAX_STORAGE_REACQUISITION_RETRIES {
+                return Err(err
+                    .context("failed reacquiring DB connection during reorg localization")
+                    .into());
+            }
+            retry_count += 1;
+            tracing::warn!(
+                "Failed reacquiring DB connection during reorg localization; retrying in {backoff:?} \
+                 (retry {retry_count}/{}): {err:#}",
+                Self::MAX_STORAGE_REACQUISITION_RETRIES
+            );
+            self.clock.sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    /// Resumes reorg localization from the bounds persisted after it was interrupted (see [`SearchResult::Partial`]),
+    /// rather than searching from the earliest L1 batch. Returns the last correct L1 batch, or `None` if there are
+    /// no persisted bounds. Once localization completes, the persisted bounds are reset, and, if
+    /// [reorg persistence](Self::with_reorg_persistence()) is enabled, the reorg is persisted.
+    ///
+    /// If reorg persistence is enabled, [`Self::run()`] resumes localization automatically on start.
+    pub async fn resume_localization(&self) -> Result<Option<L1BatchNumber>, Error> {
+        let output = self.resume_localization_inner().await?;
+        Ok(output.map(|(last_correct_l1_batch, _)| last_correct_l1_batch))
+    }
+
+    /// Returns the last correct L1 batch and the first known diverged L1 batch from the persisted bounds.
+    async fn resume_localization_inner(
+        &self,
+    ) -> Result<Option<(L1BatchNumber, L1BatchNumber)>, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let bounds = storage
+            .reorg_detector_dal()
+            .get_reorg_search_bounds()
+            .await?;
+        let Some((known_valid, known_diverged)) = bounds else {
+            return Ok(None);
+        };
+        let last_l1_batch_with_metadata = storage
+            .blocks_dal()
+            .get_last_l1_batch_number_with_metadata()
+            .await?;
+        if last_l1_batch_with_metadata.map_or(true, |number| number < known_diverged) {
+            // The local DB was rolled back since the bounds were persisted, so they are no longer meaningful.
+            tracing::info!(
+                known_valid_l1_batch = known_valid.0,
+                diverged_l1_batch = known_diverged.0,
+                sealed_l1_batch = ?last_l1_batch_with_metadata,
+                "Discarding persisted reorg search bounds since they exceed the local DB"
+            );
+            storage
+                .reorg_detector_dal()
+                .set_reorg_search_bounds(None)
+                .await?;
+            return Ok(None);
+        }
+        drop(storage);
+
+        tracing::info!(
+            known_valid_l1_batch = known_valid.0,
+            diverged_l1_batch = known_diverged.0,
+            "Resuming reorg localization"
+        );
+        let persist_bounds = self.persist_reorgs && !self.dry_run;
+        let search_result = self
+            .detect_reorg_cancellable(known_valid, known_diverged, None, persist_bounds)
+            .await?;
+        let SearchResult::Complete(last_correct_l1_batch) = search_result else {
+            unreachable!("search cannot be cancelled");
+        };
+        tracing::info!("{}", self.reorg_message(last_correct_l1_batch));
+        if self.persist_reorgs && !self.dry_run {
+            self.persist_reorg(last_correct_l1_batch + 1).await?;
+        } else {
+            let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+            storage
+                .reorg_detector_dal()
+                .set_reorg_search_bounds(None)
+                .await?;
+        }
+        Ok(Some((last_correct_l1_batch, known_diverged)))
+    }
+
+    /// Checks every L1 batch in the specified range (inclusive) against the main node and returns the first diverged
+    /// L1 batch, or `None` if all batches match. Unlike reorg localization, this doesn't assume that divergences
+    /// are monotonic, so it can be used for a full audit of a suspicious range. L1 batches are checked with bounded
+    /// concurrency; L1 batches missing on the main node are not considered diverged. Returns `None` for an empty range.
+    pub async fn verify_range(
+        &self,
+        from: L1BatchNumber,
+        to: L1BatchNumber,
+    ) -> Result<Option<L1BatchNumber>, Error> {
+        Ok(self.first_diverged_l1_batch(from, to).await?)
+    }
+
+    async fn first_diverged_l1_batch(
+        &self,
+        from: L1BatchNumber,
+        to: L1BatchNumber,
+    ) -> Result<Option<L1BatchNumber>, HashMatchError> {
+        let checks = stream::iter(from.0..=to.0).map(|number| async move {
+            let number = L1BatchNumber(number);
+            let diverged_hash = self.diverged_hash(number).await?;
+            Ok::<_, HashMatchError>((number, diverged_hash))
+        });
+        // `buffered()` preserves the order of checks, so the first found divergence has the lowest number.
+        // Dropping the stream on return cancels the remaining in-flight checks.
+        let mut checks = checks.buffered(Self::MAX_CONCURRENT_RANGE_CHECKS);
+        while let Some((number, diverged_hash)) = checks.try_next().await? {
+            if let Some(diverged_hash) = diverged_hash {
+                tracing::info!(
+                    l1_batch = number.0,
+                    ?diverged_hash,
+                    "Found diverged L1 batch during range verification"
+                );
+                return Ok(Some(number));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Determines where the local DB (e.g., a restored historical DB snapshot) has diverged from the main node.
+    /// Unlike [`Self::run()`], this performs a single check and has no side effects: it doesn't wait for new L1 batches
+    /// and doesn't update health or metrics.
+    pub async fn forensic_scan(&self) -> anyhow::Result<DivergenceReport> {
+        self.forensic_scan_inner().await.map_err(|err| match err {
+            HashMatchError::Internal(err) => err,
+            err => err.into(),
+        })
+    }
+
+    /// Performs full reorg detection once, returning the last correct L1 batch if the local DB has diverged
+    /// from the main node, or `None` otherwise. Like [`Self::forensic_scan()`], this has no side effects;
+    /// in particular, a detected reorg doesn't lead to an error, so it's up to the caller how to act on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if even the earliest L1 batch has diverged, since there is no correct L1 batch to roll back to.
+    pub async fn find_divergence(&self) -> Result<Option<L1BatchNumber>, Error> {
+        let report = self.forensic_scan_inner().await?;
+        if report.first_diverged_l1_batch.is_none() {
+            return Ok(None);
+        }
+        let last_correct_l1_batch =
+            report
+                .last_correct_l1_batch
+                .ok_or(HashMatchError::EarliestHashMismatch(
+                    report.earliest_l1_batch,
+                ))?;
+        Ok(Some(last_correct_l1_batch))
+    }
+
+    async fn forensic_scan_inner(&self) -> Result<DivergenceReport, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let earliest_l1_batch = storage
+            .blocks_dal()
+            .get_earliest_l1_batch_number_with_metadata()
+            .await?
+            .context("local DB has no L1 batches with metadata")?;
+        let sealed_l1_batch = storage
+            .blocks_dal()
+            .get_last_l1_batch_number_with_metadata()
+            .await?
+            .context("L1 batches table unexpectedly emptied")?;
+        drop(storage);
+        let earliest_l1_batch = self.bounded_earliest_l1_batch(earliest_l1_batch, sealed_l1_batch);
+
+        let main_node_sealed_l1_batch = self.client().sealed_l1_batch_number().await?;
+        self.trace_response(RpcTraceResponse::SealedL1BatchNumber(
+            main_node_sealed_l1_batch,
+        ));
+        let mut checked_l1_batch = sealed_l1_batch.min(main_node_sealed_l1_batch);
+        let mut report = DivergenceReport {
+            earliest_l1_batch,
+            checked_l1_batch,
+            last_correct_l1_batch: None,
+            first_diverged_l1_batch: Some(earliest_l1_batch),
+            diverged_hash: Some(DivergedHash::RootHash),
+        };
+        if checked_l1_batch < earliest_l1_batch {
+            return Err(HashMatchError::EarliestL1BatchTruncated(earliest_l1_batch));
+        }
+
+        match self.root_hashes_match(earliest_l1_batch).await? {
+            MatchOutput::Match => {
+                if let Some(diverged_hash) = self.diverged_optional_hash(earliest_l1_batch).await? {
+                    report.diverged_hash = Some(diverged_hash);
+                    return Ok(report);
+                }
+            }
+            MatchOutput::Mismatch(_) => return Ok(report),
+            MatchOutput::NoRemoteReference => {
+                return Err(HashMatchError::EarliestL1BatchTruncated(earliest_l1_batch));
+            }
+        }
+
+        let checked_l1_batch_matches = loop {
+            match self.root_hashes_match(checked_l1_batch).await? {
+                MatchOutput::Match => {
+                    break self
+                        .diverged_optional_hash(checked_l1_batch)
+                        .await?
+                        .is_none();
+                }
+                MatchOutput::Mismatch(_) => break false,
+                // The main node may not have computed the root hash for its latest L1 batch yet.
+                MatchOutput::NoRemoteReference => checked_l1_batch -= 1,
+            }
+        };
+        report.checked_l1_batch = checked_l1_batch;
+
+        if checked_l1_batch_matches {
+            report.last_correct_l1_batch = Some(checked_l1_batch);
+            report.first_diverged_l1_batch = None;
+            report.diverged_hash = None;
+        } else {
+            let last_correct_l1_batch = self
+                .detect_reorg_inner(earliest_l1_batch, checked_l1_batch)
+                .await?;
+            let first_diverged_l1_batch = last_correct_l1_batch + 1;
+            report.last_correct_l1_batch = Some(last_correct_l1_batch);
+            report.first_diverged_l1_batch = Some(first_diverged_l1_batch);
+            report.diverged_hash = self.diverged_hash(first_diverged_l1_batch).await?;
+        }
+        Ok(report)
+    }
+
+    /// Checks hash correspondence for the specified sealed L1 batch and miniblock (or, if the main node doesn't have them,
+    /// for the latest L1 batch / miniblock on the main node). If a reorg is detected, localizes it.
+    async fn check_sealed_blocks(
+        &mut self,
+        earliest_l1_batch_number: L1BatchNumber,
+        snapshot: LocalSnapshot,
+    ) -> Result<CheckOutcome, HashMatchError> {
+        let (sealed_l1_batch_number, sealed_miniblock_number) =
+            (snapshot.l1_batch, snapshot.miniblock);
+        tracing::trace!(
+            "Checking for reorgs - L1 batch #{sealed_l1_batch_number}, \
+             miniblock number #{sealed_miniblock_number}"
+        );
+        let iteration = self.check_iteration;
+        self.check_iteration += 1;
+        let cadence = self.comparison_cadence;
+        // Comparisons are never skipped until there are correct blocks to fall back to.
+        let last_correct_blocks = self.last_correct_blocks;
+        let is_due = |cadence| {
+            last_correct_blocks.is_none() || ComparisonCadence::is_due(cadence, iteration)
+        };
+        let check_root_hash = is_due(cadence.l1_batch_root_hash);
+        let check_miniblock_hash = is_due(cadence.miniblock_hash);
+        let check_fields = is_due(cadence.extended_fields);
+
+        let this = &*self;
+        let check_l1_batch = async {
+            let (checked_l1_batch_number, root_hashes_match, divergence) = match last_correct_blocks
+            {
+                Some((_, l1_batch)) if !check_root_hash => {
+                    (l1_batch.min(sealed_l1_batch_number), true, None)
+                }
+                _ => {
+                    let (checked_l1_batch_number, divergence) =
+                        this.check_sealed_l1_batch_root_hash(&snapshot).await?;
+                    if divergence.is_some() {
+                        (checked_l1_batch_number, false, divergence)
+                    } else {
+                        this.check_anchor(checked_l1_batch_number).await?;
+                        let diverged_hash =
+                            this.diverged_optional_hash(checked_l1_batch_number).await?;
+                        // Pass-through data hashes are re-fetched to report them; this only happens on divergence.
+                        let divergence = if diverged_hash == Some(DivergedHash::PassThroughData) {
+                            this.pass_through_data_divergence(checked_l1_batch_number)
+                                .await?
+                        } else {
+                            None
+                        };
+                        (checked_l1_batch_number, diverged_hash.is_none(), divergence)
+                    }
+                }
+            };
+            // A diverged miniblock in the checked L1 batch means that the batch itself has diverged.
+            let root_hashes_match =
+                if root_hashes_match && check_miniblock_hash && this.miniblock_sample_size > 0 {
+                    this.sampled_miniblocks_match(checked_l1_batch_number)
+                        .await?
+                } else {
+                    root_hashes_match
+                };
+            Ok::<_, HashMatchError>((checked_l1_batch_number, root_hashes_match, divergence))
+        };
+        let check_miniblock = async {
+            match last_correct_blocks {
+                Some((miniblock, _)) if !check_miniblock_hash => {
+                    Ok((miniblock.min(sealed_miniblock_number), None))
+                }
+                _ => this.check_sealed_miniblock_hash(&snapshot).await,
+            }
+        };
+        // L1 batch and miniblock checks are independent and acquire separate DB connections,
+        // so they can run concurrently.
+        let (
+            (checked_l1_batch_number, root_hashes_match, l1_batch_divergence),
+            (checked_miniblock_number, miniblock_divergence),
+        ) = tokio::try_join!(check_l1_batch, check_miniblock)?;
+        let miniblock_hashes_match = miniblock_divergence.is_none();
+        let span = tracing::Span::current();
+        span.record("checked_l1_batch", checked_l1_batch_number.0);
+        span.record("checked_miniblock", checked_miniblock_number.0);
+        span.record("root_hashes_match", root_hashes_match);
+        span.record("miniblock_hashes_match", miniblock_hashes_match);
+
+        // The only event that triggers re-org detection and node rollback is if the
+        // hash mismatch at the same block height is detected, be it miniblocks or batches.
+        //
+        // In other cases either there is only a height mismatch which means that one of
+        // the nodes needs to do catching up; however, it is not certain that there is actually
+        // a re-org taking place.
+        if root_hashes_match && miniblock_hashes_match {
+            if self.compare_virtual_blocks {
+                self.check_virtual_blocks(checked_miniblock_number).await?;
+            }
+            if self.compare_pending_miniblock {
+                self.check_pending_miniblock().await?;
+            }
+            if self.compare_proof_status {
+                self.check_proof_status(checked_l1_batch_number).await?;
+            }
+            if check_fields && !self.compared_fields.is_empty() {
+                if let Some(divergence) = self.compare_fields(checked_l1_batch_number).await? {
+                    self.event_handler
+                        .report_suspicion(ReorgSuspicion::FieldDivergence(divergence))
+                        .await;
+                }
+            }
+            self.unconfirmed_reorg = None;
+            self.dry_run_reorg = None;
+            self.reset_localization_bounds();
+            self.update_correct_block(checked_miniblock_number, checked_l1_batch_number)
+                .await;
+            if self.persist_reorgs && !self.dry_run {
+                self.persist_last_correct_l1_batch(checked_l1_batch_number)
+                    .await?;
+            }
+            Ok(CheckOutcome::Consistent {
+                l1_batch: checked_l1_batch_number,
+                miniblock: checked_miniblock_number,
+            })
+        } else {
+            let diverged_l1_batch_number =
+                Self::diverged_l1_batch_number(checked_l1_batch_number, root_hashes_match)?;
+            span.record("diverged_l1_batch", diverged_l1_batch_number.0);
+            if let Some(reorg) = self.dry_run_reorg {
+                let boundary_hashes = self
+                    .reorg_boundary_hashes(reorg.last_correct_l1_batch)
+                    .await?;
+                if boundary_hashes == reorg.boundary_hashes {
+                    tracing::debug!(
+                        last_correct_l1_batch = reorg.last_correct_l1_batch.0,
+                        "Reorg in dry-run mode is already reported; skipping its localization"
+                    );
+                    return Ok(CheckOutcome::ReorgDetected {
+                        last_correct_l1_batch: reorg.last_correct_l1_batch,
+                        divergence: l1_batch_divergence.or(miniblock_divergence),
+                    });
+                }
+                self.dry_run_reorg = None;
+            }
+            self.event_handler
+                .report_divergence(diverged_l1_batch_number)
+                .await;
+
+            tracing::info!("Searching for the first diverged L1 batch");
+            let known_valid_l1_batch_number = self
+                .localization_lower_bound(earliest_l1_batch_number, diverged_l1_batch_number)
+                .await?;
+            let (known_valid_l1_batch_number, known_diverged_l1_batch_number) = self
+                .narrow_localization_range(known_valid_l1_batch_number, diverged_l1_batch_number);
+            let search_result = self
+                .detect_reorg_cancellable(
+                    known_valid_l1_batch_number,
+                    known_diverged_l1_batch_number,
+                    self.stop_receiver.as_ref(),
+                    self.persist_reorgs && !self.dry_run,
+                )
+                .await?;
+            let last_correct_l1_batch = match search_result {
+                SearchResult::Complete(number) => number,
+                SearchResult::Partial {
+                    known_valid,
+                    known_diverged,
+                } => {
+                    tracing::info!(
+                        known_valid_l1_batch = known_valid.0,
+                        diverged_l1_batch = known_diverged.0,
+                        "Reorg localization interrupted by stop signal"
+                    );
+                    if self.persist_reorgs && !self.dry_run {
+                        self.persist_search_bounds(known_valid, known_diverged)
+                            .await?;
+                    }
+                    return Ok(CheckOutcome::LocalizationInterrupted {
+                        known_valid,
+                        known_diverged,
+                    });
+                }
+            };
+            if self.tolerate_tip_churn && last_correct_l1_batch >= sealed_l1_batch_number {
+                self.event_handler
+                    .report_suspicion(ReorgSuspicion::TipChurn {
+                        l1_batch: last_correct_l1_batch,
+                    })
+                    .await;
+                return Ok(CheckOutcome::Skipped);
+            }
+            if !self.confirm_reorg(last_correct_l1_batch + 1).await {
+                return Ok(CheckOutcome::Skipped);
+            }
+            let last_correct_miniblock = if miniblock_hashes_match {
+                None
+            } else {
+                self.localize_miniblock_reorg(last_correct_l1_batch, checked_miniblock_number)
+                    .await?
+            };
+            tracing::info!("{}", self.reorg_message(last_correct_l1_batch));
+            self.log_divergence_diagnostics(last_correct_l1_batch + 1)
+                .await;
+            self.report_localized_reorg(
+                diverged_l1_batch_number,
+                last_correct_l1_batch,
+                last_correct_miniblock,
+            )
+            .await;
+            if self.persist_reorgs && !self.dry_run {
+                self.persist_reorg(last_correct_l1_batch + 1).await?;
+            }
+            if self.dry_run {
+                tracing::warn!(
+                    last_correct_l1_batch = last_correct_l1_batch.0,
+                    "Reorg detected in dry-run mode; continuing without rolling back"
+                );
+                let boundary_hashes = self.reorg_boundary_hashes(last_correct_l1_batch).await?;
+                self.dry_run_reorg = Some(DryRunReorg {
+                    last_correct_l1_batch,
+                    boundary_hashes,
+                });
+            }
+            Ok(CheckOutcome::ReorgDetected {
+                last_correct_l1_batch,
+                divergence: l1_batch_divergence.or(miniblock_divergence),
+            })
+        }
+    }
+
+    /// Returns local and remote root hashes of the specified last correct L1 batch and the following (diverged)
+    /// L1 batch. A change in these hashes means that the reorg may have changed, so it should be localized again.
+    async fn reorg_boundary_hashes(
+        &self,
+        last_correct_l1_batch: L1BatchNumber,
+    ) -> Result<[(Option<H256>, Option<H256>); 2], HashMatchError> {
+        let mut hashes = [(None, None); 2];
+        for (l1_batch_number, hashes) in [last_correct_l1_batch, last_correct_l1_batch + 1]
+            .into_iter()
+            .zip(&mut hashes)
+        {
+            let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+            let local_hash = storage
+                .blocks_dal()
+                .get_l1_batch_state_root(l1_batch_number)
+                .await?;
+            drop(storage);
+            let remote_hash = self.client().l1_batch_root_hash(l1_batch_number).await?;
+            *hashes = (local_hash, remote_hash);
+        }
+        Ok(hashes)
+    }
+
+    /// Records an observation of a reorg with the specified first diverged L1 batch. Returns `true` if the reorg
+    /// is [confirmed](Self::with_confirmation_checks()), i.e., should be reported.
+    async fn confirm_reorg(&mut self, diverged_l1_batch: L1BatchNumber) -> bool {
+        let observations = match self.unconfirmed_reorg {
+            Some((prev_diverged_l1_batch, observations))
+                if diverged_l1_batch <= prev_diverged_l1_batch =>
+            {
+                observations + 1
+            }
+            _ => 1,
+        };
+        if observations >= self.confirmation_checks {
+            self.unconfirmed_reorg = None;
+            return true;
+        }
+
+        self.unconfirmed_reorg = Some((diverged_l1_batch, observations));
+        self.event_handler
+            .report_suspicion(ReorgSuspicion::UnconfirmedReorg {
+                diverged_l1_batch,
+                observations,
+                required_observations: self.confirmation_checks,
+            })
+            .await;
+        false
+    }
+
+    /// Reports a localized reorg via metrics, the event handler and the reorg callback.
+    async fn report_localized_reorg(
+        &mut self,
+        diverged_l1_batch: L1BatchNumber,
+        last_correct_l1_batch: L1BatchNumber,
+        last_correct_miniblock: Option<MiniblockNumber>,
+    ) {
+        self.metrics.record_gauge(
+            DetectorGauge::ReorgDepth,
+            (diverged_l1_batch.0 - last_correct_l1_batch.0).into(),
+        );
+        self.metrics
+            .increment_counter(DetectorCounter::DetectedReorgs);
+        self.event_handler
+            .report_localized_reorg(last_correct_l1_batch, last_correct_miniblock)
+            .await;
+        if let Some(ReorgCallback(callback)) = &self.reorg_callback {
+            callback(last_correct_l1_batch);
+        }
     }
 
-    async fn run_inner(
+    /// Compares the configured fields of the local and main node details for the specified L1 batch.
+    async fn compare_fields(
+        &self,
+        l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<FieldDivergence>, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_details = storage
+            .blocks_web3_dal()
+            .get_l1_batch_details(l1_batch_number)
+            .await?
+            .with_context(|| format!("Local L1 batch #{l1_batch_number} disappeared"))?;
+        drop(storage);
+
+        let Some(remote_details) = self.client().l1_batch_details(l1_batch_number).await? else {
+            return Ok(None);
+        };
+        let fields = self
+            .compared_fields
+            .diverged_fields(&local_details.base, &remote_details.base);
+        Ok((!fields.is_empty()).then_some(FieldDivergence {
+            l1_batch: l1_batch_number,
+            fields,
+        }))
+    }
+
+    async fn update_correct_block(
+        &mut self,
+        last_correct_miniblock: MiniblockNumber,
+        last_correct_l1_batch: L1BatchNumber,
+    ) {
+        self.event_handler
+            .update_correct_block(last_correct_miniblock, last_correct_l1_batch)
+            .await;
+        self.last_correct_blocks = Some((last_correct_miniblock, last_correct_l1_batch));
+        self.metrics.record_gauge(
+            DetectorGauge::LastCorrectMiniblock,
+            last_correct_miniblock.0.into(),
+        );
+        self.metrics.record_gauge(
+            DetectorGauge::LastCorrectL1Batch,
+            last_correct_l1_batch.0.into(),
+        );
+    }
+
+    /// Checks the root hash of an L1 batch that the local node and the main node agree on against the anchor, if any.
+    async fn check_anchor(&self, l1_batch_number: L1BatchNumber) -> Result<(), HashMatchError> {
+        let Some(anchor) = &self.anchor else {
+            return Ok(());
+        };
+        let Some(anchor_hash) = anchor
+            .l1_batch_root_hash(l1_batch_number)
+            .await
+            .with_context(|| {
+                format!("failed getting root hash for L1 batch #{l1_batch_number} from anchor")
+            })?
+        else {
+            return Ok(());
+        };
+
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let local_hash = storage
+            .blocks_dal()
+            .get_l1_batch_state_root(l1_batch_number)
+            .await?
+            .with_context(|| {
+                format!("Root hash does not exist for local batch #{l1_batch_number}")
+            })?;
+        drop(storage);
+
+        if local_hash != anchor_hash {
+            tracing::error!(
+                "Local root hash {} for L1 batch #{l1_batch_number} matches the main node, \
+                 but doesn't match the anchor hash {}",
+                self.display_hash(local_hash),
+                self.display_hash(anchor_hash)
+            );
+            return Err(HashMatchError::ConsensusWithAnchorMismatch(l1_batch_number));
+        }
+        Ok(())
+    }
+
+    /// Returns the last L1 batch with metadata and the last miniblock in the local DB.
+    /// Returns `None` if the local DB is only partially populated, e.g. during snapshot recovery, when it may have
+    /// L1 batches with metadata but no miniblocks, or vice versa.
+    async fn sealed_block_numbers(
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<Option<(L1BatchNumber, MiniblockNumber)>, HashMatchError> {
+        let sealed_l1_batch_number = storage
+            .blocks_dal()
+            .get_last_l1_batch_number_with_metadata()
+            .await?;
+        let sealed_miniblock_number = storage.blocks_dal().get_sealed_miniblock_number().await?;
+        match (sealed_l1_batch_number, sealed_miniblock_number) {
+            (Some(l1_batch_number), Some(miniblock_number)) => {
+                Ok(Some((l1_batch_number, miniblock_number)))
+            }
+            (l1_batch_number, miniblock_number) => {
+                tracing::debug!(
+                    l1_batch_number = l1_batch_number.map(|number| number.0),
+                    miniblock_number = miniblock_number.map(|number| number.0),
+                    "Local DB is only partially populated; skipping reorg check"
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the last L1 batch and miniblock that are considered fully synced, i.e. are committed on L1
+    /// according to the local DB. Returns `None` if there are no such L1 batches after the specified earliest batch.
+    async fn sync_watermark(
+        storage: &mut StorageProcessor<'_>,
+        earliest_l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<(L1BatchNumber, MiniblockNumber)>, HashMatchError> {
+        let Some(l1_batch_number) = storage
+            .blocks_dal()
+            .get_number_of_last_l1_batch_committed_on_eth()
+            .await?
+        else {
+            return Ok(None);
+        };
+        if l1_batch_number < earliest_l1_batch_number {
+            return Ok(None);
+        }
+        // The L1 batch may have no miniblocks in the local DB if it was restored from a snapshot.
+        let Some((_, last_miniblock_number)) = storage
+            .blocks_dal()
+            .get_miniblock_range_of_l1_batch(l1_batch_number)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some((l1_batch_number, last_miniblock_number)))
+    }
+
+    /// Reads hashes of the specified local L1 batch and miniblock. Should be called in the same DB transaction
+    /// as the one used to determine block numbers.
+    async fn read_local_snapshot(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        l1_batch_number: L1BatchNumber,
+        miniblock_number: MiniblockNumber,
+    ) -> Result<LocalSnapshot, HashMatchError> {
+        let l1_batch_root_hash = storage
+            .blocks_dal()
+            .get_l1_batch_state_root(l1_batch_number)
+            .await?
+            .with_context(|| {
+                format!("Root hash does not exist for local batch #{l1_batch_number}")
+            })?;
+        let miniblock_hash = storage
+            .blocks_dal()
+            .get_miniblock_header(miniblock_number)
+            .await?
+            .with_context(|| {
+                format!("Header does not exist for local miniblock #{miniblock_number}")
+            })?
+            .hash;
+        let last_finalized_l1_batch = if self.root_hash_cache.is_some() {
+            storage
+                .blocks_dal()
+                .get_number_of_last_l1_batch_executed_on_eth()
+                .await?
+        } else {
+            None
+        };
+        Ok(LocalSnapshot {
+            l1_batch: l1_batch_number,
+            l1_batch_root_hash,
+            miniblock: miniblock_number,
+            miniblock_hash,
+            last_finalized_l1_batch,
+        })
+    }
+
+    /// Performs a single consistency check of the latest local L1 batch and miniblock against the main node,
+    /// localizing a reorg if one is detected. Unlike [`Self::run()`], this doesn't wait for L1 batches to appear
+    /// in the local DB and doesn't check the earliest L1 batch.
+    pub async fn check_consistency(&mut self) -> Result<CheckOutcome, Error> {
+        let Some(earliest_l1_batch_number) = self.earliest_l1_batch_number().await? else {
+            tracing::debug!(
+                "No L1 batches with metadata in the local DB yet; skipping reorg check"
+            );
+            return Ok(CheckOutcome::Skipped);
+        };
+        Ok(self
+            .check_consistency_inner(earliest_l1_batch_number, true)
+            .await?)
+    }
+
+    /// Same as [`Self::check_consistency()`], but bypasses all caches and short-circuits, always querying
+    /// the local DB and the main node in full. Useful if a cache is suspected to mask a real divergence.
+    pub async fn check_consistency_uncached(&mut self) -> Result<CheckOutcome, Error> {
+        let Some(earliest_l1_batch_number) = self.earliest_l1_batch_number().await? else {
+            tracing::debug!(
+                "No L1 batches with metadata in the local DB yet; skipping reorg check"
+            );
+            return Ok(CheckOutcome::Skipped);
+        };
+        Ok(self
+            .check_consistency_inner(earliest_l1_batch_number, false)
+            .await?)
+    }
+
+    /// Returns the earliest L1 batch covered by verification, or `None` if there are no L1 batches with metadata
+    /// in the local DB yet.
+    async fn earliest_l1_batch_number(&self) -> Result<Option<L1BatchNumber>, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let Some(number) = storage
+            .blocks_dal()
+            .get_earliest_l1_batch_number_with_metadata()
+            .await?
+        else {
+            return Ok(None);
+        };
+        if self.max_verify_age.is_none() {
+            return Ok(Some(number));
+        }
+        let sealed_number = storage
+            .blocks_dal()
+            .get_last_l1_batch_number_with_metadata()
+            .await?
+            .unwrap_or(number);
+        Ok(Some(self.bounded_earliest_l1_batch(number, sealed_number)))
+    }
+
+    #[tracing::instrument(
+        name = "check_consistency",
+        skip_all,
+        fields(
+            checked_l1_batch = tracing::field::Empty,
+            checked_miniblock = tracing::field::Empty,
+            root_hashes_match = tracing::field::Empty,
+            miniblock_hashes_match = tracing::field::Empty,
+            diverged_l1_batch = tracing::field::Empty,
+        )
+    )]
+    async fn check_consistency_inner(
+        &mut self,
+        earliest_l1_batch_number: L1BatchNumber,
+        use_caches: bool,
+    ) -> Result<CheckOutcome, HashMatchError> {
+        let started_at = Instant::now();
+        let outcome = self
+            .check_latest_blocks(earliest_l1_batch_number, use_caches)
+            .await;
+        self.metrics
+            .record_histogram(DetectorHistogram::CheckLatency, started_at.elapsed());
+        outcome
+    }
+
+    async fn check_latest_blocks(
+        &mut self,
+        earliest_l1_batch_number: L1BatchNumber,
+        use_caches: bool,
+    ) -> Result<CheckOutcome, HashMatchError> {
+        if self.check_namespace_coherence {
+            self.check_remote_namespace_coherence().await?;
+        }
+
+        // All local data needed for the check is read in a single read-only transaction, so that it reflects
+        // the same DB snapshot. The connection is released before calling the main node.
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let mut transaction = storage.start_repeatable_read_transaction().await?;
+        let Some((sealed_l1_batch_number, sealed_miniblock_number)) =
+            Self::sealed_block_numbers(&mut transaction).await?
+        else {
+            return Ok(CheckOutcome::Skipped);
+        };
+
+        // If possible, only check the newly added batches to keep the check cheap. The earliest L1 batch
+        // has metadata by definition, so it doesn't need to be checked.
+        let first_unchecked_l1_batch = match self.contiguous_l1_batch_number {
+            Some(number) if use_caches => number.max(earliest_l1_batch_number) + 1,
+            _ => earliest_l1_batch_number + 1,
+        };
+        Self::check_local_batch_continuity(
+            &mut transaction,
+            first_unchecked_l1_batch,
+            sealed_l1_batch_number,
+        )
+        .await?;
+        self.contiguous_l1_batch_number = Some(
+            self.contiguous_l1_batch_number
+                .map_or(sealed_l1_batch_number, |number| {
+                    number.max(sealed_l1_batch_number)
+                }),
+        );
+
+        let checked_numbers = if self.catch_up_only {
+            Self::sync_watermark(&mut transaction, earliest_l1_batch_number)
+                .await?
+                .map(|(l1_batch_number, miniblock_number)| {
+                    (
+                        sealed_l1_batch_number.min(l1_batch_number),
+                        sealed_miniblock_number.min(miniblock_number),
+                    )
+                })
+        } else {
+            Some((sealed_l1_batch_number, sealed_miniblock_number))
+        };
+        let Some((sealed_l1_batch_number, sealed_miniblock_number)) = checked_numbers else {
+            tracing::debug!("No synced L1 batches in the local DB yet; skipping reorg check");
+            return Ok(CheckOutcome::Skipped);
+        };
+        let snapshot = self
+            .read_local_snapshot(
+                &mut transaction,
+                sealed_l1_batch_number,
+                sealed_miniblock_number,
+            )
+            .await?;
+        transaction.commit().await?;
+        drop(storage);
+
+        self.check_sealed_blocks(earliest_l1_batch_number, snapshot)
+            .await
+    }
+
+    /// Performs a read-only check of hashes for the latest sealed L1 batch and miniblock.
+    async fn probe_sealed_blocks(
+        &self,
+        earliest_l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<SealedBlocksProbe>, HashMatchError> {
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let mut transaction = storage.start_repeatable_read_transaction().await?;
+        let Some((sealed_l1_batch_number, sealed_miniblock_number)) =
+            Self::sealed_block_numbers(&mut transaction).await?
+        else {
+            return Ok(None);
+        };
+        let (sealed_l1_batch_number, sealed_miniblock_number) = if self.catch_up_only {
+            let Some((l1_batch_number, miniblock_number)) =
+                Self::sync_watermark(&mut transaction, earliest_l1_batch_number).await?
+            else {
+                return Ok(None);
+            };
+            (
+                sealed_l1_batch_number.min(l1_batch_number),
+                sealed_miniblock_number.min(miniblock_number),
+            )
+        } else {
+            (sealed_l1_batch_number, sealed_miniblock_number)
+        };
+        let snapshot = self
+            .read_local_snapshot(
+                &mut transaction,
+                sealed_l1_batch_number,
+                sealed_miniblock_number,
+            )
+            .await?;
+        transaction.commit().await?;
+        drop(storage);
+
+        let (l1_batch, l1_batch_divergence) =
+            self.check_sealed_l1_batch_root_hash(&snapshot).await?;
+        let (miniblock, miniblock_divergence) = self.check_sealed_miniblock_hash(&snapshot).await?;
+        Ok(Some(SealedBlocksProbe {
+            l1_batch,
+            miniblock,
+            hashes_match: l1_batch_divergence.is_none() && miniblock_divergence.is_none(),
+        }))
+    }
+
+    /// Performs concurrent read-only checks at staggered offsets, and then applies their results sequentially,
+    /// so that the detector state is only mutated from a single place.
+    async fn check_consistency_concurrently(
+        &mut self,
+        earliest_l1_batch_number: L1BatchNumber,
+    ) -> Result<CheckOutcome, HashMatchError> {
+        let stagger_interval = self.sleep_interval / self.max_concurrent_checks as u32;
+        let this = &*self;
+        let probes = (0..this.max_concurrent_checks).map(|i| async move {
+            this.clock.sleep(stagger_interval * i as u32).await;
+            this.probe_sealed_blocks(earliest_l1_batch_number).await
+        });
+        let probes = futures::future::try_join_all(probes).await?;
+
+        let mut outcome = CheckOutcome::Skipped;
+        // Probes are ordered by their start, so they observe non-decreasing local state.
+        for probe in probes.into_iter().flatten() {
+            if let CheckOutcome::Consistent {
+                l1_batch,
+                miniblock,
+            } = outcome
+            {
+                if probe.l1_batch <= l1_batch && probe.miniblock <= miniblock {
+                    continue; // Deduplicate probes not advancing the last correct blocks
+                }
+            }
+
+            if !probe.hashes_match {
+                tracing::info!(
+                    l1_batch = probe.l1_batch.0,
+                    miniblock = probe.miniblock.0,
+                    "Concurrent check found a divergence; re-checking sequentially"
+                );
+                return self
+                    .check_consistency_inner(earliest_l1_batch_number, true)
+                    .await;
+            }
+            self.update_correct_block(probe.miniblock, probe.l1_batch)
+                .await;
+            outcome = CheckOutcome::Consistent {
+                l1_batch: probe.l1_batch,
+                miniblock: probe.miniblock,
+            };
+        }
+        Ok(outcome)
+    }
+
+    pub async fn run(
+        mut self,
+        stop_receiver: watch::Receiver<bool>,
+    ) -> anyhow::Result<Option<L1BatchNumber>> {
+        self.event_handler.initialize().await;
+        let result = self.run_with_retries(stop_receiver).await;
+        if let Err(err) = &result {
+            tracing::error!("Reorg detector failed: {err:#}");
+            self.event_handler.report_failure(&format!("{err:#}")).await;
+        }
+        result
+    }
+
+    async fn run_with_retries(
+        &mut self,
+        mut stop_receiver: watch::Receiver<bool>,
+    ) -> anyhow::Result<Option<L1BatchNumber>> {
+        self.last_successful_check_at = Instant::now();
+        loop {
+            let err = match self.run_inner(&mut stop_receiver).await {
+                Ok(l1_batch_number) => return Ok(l1_batch_number),
+                Err(err) => Error::HashMatch(err),
+            };
+            let is_transient = self.is_transient(&err);
+            let Error::HashMatch(err) = err;
+            match err {
+                err if is_transient => {
+                    if *stop_receiver.borrow() {
+                        // The error may be caused by RPC retries aborted because of the stop signal.
+                        tracing::info!("Shutting down reorg detector after transient error: {err}");
+                        self.event_handler.start_shutting_down().await;
+                        return Ok(None);
+                    }
+                    tracing::warn!("Following transient error occurred: {err}");
+                    self.consecutive_transient_errors += 1;
+                    if let Some(max_retries) = self.max_transient_retries {
+                        if self.consecutive_transient_errors > max_retries {
+                            return Err(anyhow::Error::new(err).context(format!(
+                                "reorg detector exceeded {max_retries} consecutive retries after transient errors"
+                            )));
+                        }
+                    }
+                    self.event_handler
+                        .report_transient_failure(
+                            self.consecutive_transient_errors,
+                            self.last_correct_blocks,
+                        )
+                        .await;
+                    self.record_time_since_last_successful_check();
+                    if matches!(err, HashMatchError::Rpc(_)) {
+                        self.recreate_client_if_necessary()?;
+                    }
+                    let delay = self.next_retry_delay();
+                    tracing::info!("Trying again after {delay:?}");
+                    self.clock.sleep(delay).await;
+                }
+                HashMatchError::Internal(err) => return Err(err),
+                err => return Err(self.operator_error(err)),
+            }
+        }
+    }
+
+    /// Performs a single consistency check without entering the [`Self::run()`] loop. On the first call,
+    /// this waits for the local DB to have L1 batches and performs the same startup verification as `run()`
+    /// (the earliest L1 batch, protocol version etc.). Returns `Ok(None)` if a stop signal was received
+    /// while waiting for L1 batches.
+    ///
+    /// Unlike `run()`, this method doesn't retry on transient errors and doesn't roll back detected reorgs.
+    pub async fn run_once(
         &mut self,
         stop_receiver: &mut watch::Receiver<bool>,
-    ) -> Result<Option<L1BatchNumber>, HashMatchError> {
+    ) -> Result<Option<CheckOutcome>, Error> {
         let earliest_l1_batch_number =
-            wait_for_l1_batch_with_metadata(&self.pool, self.sleep_interval, stop_receiver).await?;
-
-        let Some(earliest_l1_batch_number) = earliest_l1_batch_number else {
+            wait_for_l1_batch_with_metadata(&self.pool, self.sleep_interval, stop_receiver)
+                .await
+                .map_err(HashMatchError::Internal)?;
+        if earliest_l1_batch_number.is_none() {
             return Ok(None); // Stop signal received
+        }
+        // We've just waited for an L1 batch with metadata to appear, so it should be present.
+        let earliest_l1_batch_number = self
+            .earliest_l1_batch_number()
+            .await?
+            .context("local DB has no L1 batches with metadata")
+            .map_err(HashMatchError::Internal)?;
+        self.stop_receiver = Some(stop_receiver.clone());
+        if self.startup_verified {
+            self.check_fork_id().await?;
+        } else if let Some(outcome) = self.verify_on_startup(earliest_l1_batch_number).await? {
+            return Ok(Some(outcome));
+        }
+        let outcome = self
+            .check_consistency_once(earliest_l1_batch_number)
+            .await?;
+        Ok(Some(outcome))
+    }
+
+    fn record_time_since_last_successful_check(&self) {
+        let elapsed = self.last_successful_check_at.elapsed();
+        self.metrics.record_gauge(
+            DetectorGauge::SecondsSinceLastSuccessfulCheck,
+            elapsed.as_secs(),
+        );
+    }
+
+    /// Advances the exponential backoff for retries after transient errors and returns the next retry delay.
+    /// The delay is randomly jittered to avoid multiple nodes reconnecting to the main node at the same time.
+    fn next_retry_delay(&mut self) -> Duration {
+        let min_backoff = self.min_retry_backoff.unwrap_or(self.sleep_interval);
+        let max_backoff = self.max_retry_backoff.max(min_backoff);
+        let backoff = self
+            .retry_backoff
+            .map_or(min_backoff, |backoff| (backoff * 2).min(max_backoff));
+        self.retry_backoff = Some(backoff);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
+    /// Returns the L1 batch that has diverged according to a consistency check. If root hashes of the checked
+    /// L1 batch match, it's the next (non-sealed) L1 batch that has diverged; there's no such batch if the checked
+    /// batch is the last representable one.
+    fn diverged_l1_batch_number(
+        checked_l1_batch_number: L1BatchNumber,
+        root_hashes_match: bool,
+    ) -> Result<L1BatchNumber, HashMatchError> {
+        if !root_hashes_match {
+            return Ok(checked_l1_batch_number);
+        }
+        let next_number = checked_l1_batch_number.0.checked_add(1).with_context(|| {
+            format!(
+                "miniblock hash diverged after L1 batch #{checked_l1_batch_number}, \
+                 but there can be no L1 batches after it"
+            )
+        })?;
+        Ok(L1BatchNumber(next_number))
+    }
+
+    /// Performs a consistency check, aborting it if the watchdog is enabled and the check takes too long.
+    /// Returns `None` if the check was aborted.
+    async fn check_consistency_with_watchdog(
+        &mut self,
+        earliest_l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<CheckOutcome>, HashMatchError> {
+        let Some(timeout) = self.watchdog_timeout else {
+            return self
+                .check_consistency_once(earliest_l1_batch_number)
+                .await
+                .map(Some);
+        };
+        let check_result = tokio::time::timeout(
+            timeout,
+            self.check_consistency_once(earliest_l1_batch_number),
+        )
+        .await;
+        match check_result {
+            Ok(outcome) => outcome.map(Some),
+            Err(_) => {
+                tracing::error!(
+                    "Consistency check hasn't completed in {timeout:?}; aborting it and restarting the iteration"
+                );
+                self.metrics
+                    .increment_counter(DetectorCounter::WatchdogTimeouts);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn check_consistency_once(
+        &mut self,
+        earliest_l1_batch_number: L1BatchNumber,
+    ) -> Result<CheckOutcome, HashMatchError> {
+        if self.max_concurrent_checks > 1 {
+            self.check_consistency_concurrently(earliest_l1_batch_number)
+                .await
+        } else {
+            self.check_consistency_inner(earliest_l1_batch_number, true)
+                .await
+        }
+    }
+
+    /// Recreates the main node client using the configured factory if the client has failed too many times in a row.
+    fn recreate_client_if_necessary(&mut self) -> anyhow::Result<()> {
+        let Some(factory) = &self.client_factory else {
+            return Ok(());
         };
+        self.consecutive_client_failures += 1;
+        if self.consecutive_client_failures >= self.max_consecutive_client_failures {
+            tracing::info!(
+                "Recreating main node client after {} consecutive failures",
+                self.consecutive_client_failures
+            );
+            self.client = factory
+                .create()
+                .context("failed recreating main node client")?;
+            self.consecutive_client_failures = 0;
+        }
+        Ok(())
+    }
+
+    /// Verifies root hashes for the configured number of the most recent local L1 batches.
+    async fn verify_recent_l1_batches(
+        &self,
+        earliest_l1_batch_number: L1BatchNumber,
+    ) -> Result<(), HashMatchError> {
+        let depth = self
+            .startup_verification_depth
+            .min(Self::MAX_STARTUP_VERIFICATION_DEPTH);
+        if depth == 0 {
+            return Ok(());
+        }
+
+        let mut storage = self.pool.access_storage_tagged("reorg_detector").await?;
+        let last_l1_batch_number = storage
+            .blocks_dal()
+            .get_last_l1_batch_number_with_metadata()
+            .await?
+            .context("L1 batches table unexpectedly emptied")?;
+        drop(storage);
+
+        // The earliest L1 batch is checked separately.
+        let first_l1_batch_number = (earliest_l1_batch_number + 1).max(L1BatchNumber(
+            last_l1_batch_number.0.saturating_sub(depth - 1),
+        ));
+        tracing::info!(
+            first_l1_batch = first_l1_batch_number.0,
+            last_l1_batch = last_l1_batch_number.0,
+            "Verifying root hashes for recent L1 batches on startup"
+        );
+        for number in first_l1_batch_number.0..=last_l1_batch_number.0 {
+            let number = L1BatchNumber(number);
+            match self.root_hashes_match(number).await? {
+                MatchOutput::Match => { /* we're good */ }
+                MatchOutput::Mismatch(_) => return Err(HashMatchError::StartupDivergence(number)),
+                MatchOutput::NoRemoteReference => {
+                    tracing::info!(
+                        l1_batch = number.0,
+                        "Main node has no L1 batch; finishing startup verification"
+                    );
+                    break;
+                }
+            }
+            self.clock.sleep(Self::STARTUP_VERIFICATION_DELAY).await;
+        }
+        Ok(())
+    }
+
+    /// Performs verification on detector start: checks the fork ID, the earliest L1 batch, the protocol version
+    /// and recent L1 batches, and resumes interrupted reorg localization if reorg persistence is enabled.
+    /// Returns the outcome of resumed localization, if any.
+    async fn verify_on_startup(
+        &mut self,
+        earliest_l1_batch_number: L1BatchNumber,
+    ) -> Result<Option<CheckOutcome>, HashMatchError> {
+        self.check_fork_id().await?;
+        if self.persist_reorgs {
+            self.check_prior_reorg().await?;
+            self.load_last_correct_l1_batch_checkpoint().await?;
+        }
         tracing::debug!(
             "Checking root hash match for earliest L1 batch #{earliest_l1_batch_number}"
         );
         match self.root_hashes_match(earliest_l1_batch_number).await? {
             MatchOutput::Match => { /* we're good */ }
-            MatchOutput::Mismatch => {
+            MatchOutput::Mismatch(_) => {
                 return Err(HashMatchError::EarliestHashMismatch(
                     earliest_l1_batch_number,
                 ))
@@ -416,72 +5714,121 @@ impl ReorgDetector {
                 ))
             }
         }
+        self.check_protocol_version().await?;
+        self.verify_recent_l1_batches(earliest_l1_batch_number)
+            .await?;
+        self.startup_verified = true;
 
-        loop {
-            let should_stop = *stop_receiver.borrow();
-            if should_stop {
-                self.event_handler.start_shutting_down();
+        // Resume reorg localization interrupted by a previous detector run, if any.
+        if self.persist_reorgs {
+            if let Some((last_correct_l1_batch, known_diverged)) =
+                self.resume_localization_inner().await?
+            {
+                self.log_divergence_diagnostics(last_correct_l1_batch + 1)
+                    .await;
+                self.report_localized_reorg(known_diverged, last_correct_l1_batch, None)
+                    .await;
+                return Ok(Some(CheckOutcome::ReorgDetected {
+                    last_correct_l1_batch,
+                    divergence: None,
+                }));
             }
+        }
+        Ok(None)
+    }
 
-            // At this point, we are guaranteed to have L1 batches and miniblocks in the storage.
-            let mut storage = self.pool.access_storage().await?;
-            let sealed_l1_batch_number = storage
-                .blocks_dal()
-                .get_last_l1_batch_number_with_metadata()
-                .await?
-                .context("L1 batches table unexpectedly emptied")?;
-            let sealed_miniblock_number = storage
-                .blocks_dal()
-                .get_sealed_miniblock_number()
-                .await?
-                .context("miniblocks table unexpectedly emptied")?;
-            drop(storage);
+    async fn run_inner(
+        &mut self,
+        stop_receiver: &mut watch::Receiver<bool>,
+    ) -> Result<Option<L1BatchNumber>, HashMatchError> {
+        let earliest_l1_batch_number =
+            wait_for_l1_batch_with_metadata(&self.pool, self.sleep_interval, stop_receiver).await?;
 
-            tracing::trace!(
-                "Checking for reorgs - L1 batch #{sealed_l1_batch_number}, \
-                 miniblock number #{sealed_miniblock_number}"
-            );
+        if earliest_l1_batch_number.is_none() {
+            return Ok(None); // Stop signal received
+        }
+        // We've just waited for an L1 batch with metadata to appear, so it should be present.
+        let earliest_l1_batch_number = self
+            .earliest_l1_batch_number()
+            .await?
+            .context("local DB has no L1 batches with metadata")?;
+        self.stop_receiver = Some(stop_receiver.clone());
+        // Startup verification is performed once per detector, rather than on each retry after a transient error.
+        let mut resumed_outcome = if self.startup_verified {
+            None
+        } else {
+            self.verify_on_startup(earliest_l1_batch_number).await?
+        };
+        let mut was_paused = false;
 
-            let (checked_l1_batch_number, root_hashes_match) = self
-                .check_sealed_l1_batch_root_hash(sealed_l1_batch_number)
-                .await?;
-            let (checked_miniblock_number, miniblock_hashes_match) = self
-                .check_sealed_miniblock_hash(sealed_miniblock_number)
-                .await?;
+        loop {
+            let should_stop = *stop_receiver.borrow();
+            if should_stop {
+                self.event_handler.start_shutting_down().await;
+            }
 
-            // The only event that triggers re-org detection and node rollback is if the
-            // hash mismatch at the same block height is detected, be it miniblocks or batches.
-            //
-            // In other cases either there is only a height mismatch which means that one of
-            // the nodes needs to do catching up; however, it is not certain that there is actually
-            // a re-org taking place.
-            if root_hashes_match && miniblock_hashes_match {
-                self.event_handler
-                    .update_correct_block(checked_miniblock_number, checked_l1_batch_number);
-            } else {
-                let diverged_l1_batch_number = if root_hashes_match {
-                    checked_l1_batch_number + 1 // Non-sealed L1 batch has diverged
+            let is_paused = !should_stop
+                && self
+                    .pause_receiver
+                    .as_ref()
+                    .map_or(false, |receiver| *receiver.borrow());
+            if is_paused != was_paused {
+                if is_paused {
+                    tracing::info!("Reorg detector is paused; skipping consistency checks");
                 } else {
-                    checked_l1_batch_number
-                };
-                self.event_handler
-                    .report_divergence(diverged_l1_batch_number);
+                    tracing::info!("Reorg detector is resumed");
+                }
+                was_paused = is_paused;
+            }
+            if is_paused {
+                self.clock.sleep(self.sleep_interval).await;
+                continue;
+            }
 
-                tracing::info!("Searching for the first diverged L1 batch");
-                let last_correct_l1_batch = self
-                    .detect_reorg(earliest_l1_batch_number, diverged_l1_batch_number)
-                    .await?;
-                tracing::info!(
-                    "Reorg localized: last correct L1 batch is #{last_correct_l1_batch}"
-                );
-                return Ok(Some(last_correct_l1_batch));
+            self.check_fork_id().await?;
+            let outcome = match resumed_outcome.take() {
+                Some(outcome) => Some(outcome),
+                None => {
+                    self.check_consistency_with_watchdog(earliest_l1_batch_number)
+                        .await?
+                }
+            };
+            match outcome {
+                Some(CheckOutcome::ReorgDetected { .. }) if self.dry_run => {
+                    // The reorg is reported (once) by the consistency check; continue checking
+                }
+                Some(CheckOutcome::ReorgDetected {
+                    last_correct_l1_batch,
+                    ..
+                }) if self.block_reverter.is_some() => {
+                    self.revert_l1_batches(last_correct_l1_batch).await?;
+                }
+                Some(CheckOutcome::ReorgDetected {
+                    last_correct_l1_batch,
+                    ..
+                }) => return Ok(Some(last_correct_l1_batch)),
+                Some(CheckOutcome::LocalizationInterrupted { .. }) => {
+                    tracing::info!("Shutting down reorg detector");
+                    return Ok(None);
+                }
+                Some(CheckOutcome::Consistent { .. } | CheckOutcome::Skipped) | None => {
+                    // continue checking
+                }
             }
+            self.consecutive_client_failures = 0;
+            self.retry_backoff = None;
+            self.consecutive_transient_errors = 0;
+            self.last_successful_check_at = Instant::now();
+            self.record_time_since_last_successful_check();
 
             if should_stop {
                 tracing::info!("Shutting down reorg detector");
                 return Ok(None);
             }
-            tokio::time::sleep(self.sleep_interval).await;
+            // In the concurrent mode, the bulk of the sleep interval is spent staggering checks.
+            self.clock
+                .sleep(self.sleep_interval / self.max_concurrent_checks as u32)
+                .await;
         }
     }
 }
@@ -0,0 +1,267 @@
+//! Test utilities for the reorg detector. Available in downstream crates with the `test-util` feature,
+//! e.g. to test how a node supervisor reacts to reorgs and main node failures.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use zksync_types::{api::L1BatchDetails, L1BatchNumber, MiniblockNumber, ProtocolVersionId, H256};
+use zksync_web3_decl::{
+    error::{EnrichedClientError, EnrichedClientResult},
+    jsonrpsee::core::ClientError as RpcError,
+};
+
+use super::MainNodeClient;
+
+/// Error injected into [`MockMainNodeClient`] calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectedError {
+    /// Transient error (a request timeout). The detector retries after such errors.
+    Transient,
+    /// Fatal error. The detector terminates after such errors.
+    Fatal,
+}
+
+impl From<InjectedError> for RpcError {
+    fn from(error: InjectedError) -> Self {
+        match error {
+            InjectedError::Transient => Self::RequestTimeout,
+            InjectedError::Fatal => Self::HttpNotImplemented,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    sealed_miniblock_number: MiniblockNumber,
+    sealed_l1_batch_number: L1BatchNumber,
+    miniblock_hashes: HashMap<MiniblockNumber, H256>,
+    l1_batch_root_hashes: HashMap<L1BatchNumber, H256>,
+    protocol_version: Option<u16>,
+    injected_errors: HashMap<&'static str, VecDeque<InjectedError>>,
+    persistent_error: Option<InjectedError>,
+}
+
+/// Scriptable [`MainNodeClient`] implementation. Can be passed to [`ReorgDetector::from_parts()`].
+///
+/// The mock serves scripted miniblock hashes and L1 batch root hashes; methods used for optional comparisons
+/// return `None` (i.e., the corresponding data is treated as missing on the main node). Clones share the scripted
+/// state, so responses and errors can be changed after the client is passed to the detector.
+///
+/// [`ReorgDetector::from_parts()`]: super::ReorgDetector::from_parts()
+#[derive(Debug, Clone, Default)]
+pub struct MockMainNodeClient(Arc<Mutex<MockState>>);
+
+impl MockMainNodeClient {
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.0.lock().expect("`MockMainNodeClient` is poisoned")
+    }
+
+    /// Sets the hash of the specified miniblock. The sealed miniblock number on the main node is bumped
+    /// to `number` if necessary.
+    pub fn set_miniblock_hash(&self, number: MiniblockNumber, hash: H256) {
+        let mut state = self.state();
+        state.miniblock_hashes.insert(number, hash);
+        state.sealed_miniblock_number = state.sealed_miniblock_number.max(number);
+    }
+
+    /// Sets the state root hash of the specified L1 batch. The sealed L1 batch number on the main node is bumped
+    /// to `number` if necessary.
+    pub fn set_l1_batch_root_hash(&self, number: L1BatchNumber, hash: H256) {
+        let mut state = self.state();
+        state.l1_batch_root_hashes.insert(number, hash);
+        state.sealed_l1_batch_number = state.sealed_l1_batch_number.max(number);
+    }
+
+    /// Sets the sealed miniblock and L1 batch numbers on the main node, e.g. to emulate the main node
+    /// lagging behind the local node.
+    pub fn set_sealed_numbers(&self, miniblock: MiniblockNumber, l1_batch: L1BatchNumber) {
+        let mut state = self.state();
+        state.sealed_miniblock_number = miniblock;
+        state.sealed_l1_batch_number = l1_batch;
+    }
+
+    /// Sets the latest protocol version on the main node. By default, the latest supported version is returned.
+    pub fn set_protocol_version(&self, version: u16) {
+        self.state().protocol_version = Some(version);
+    }
+
+    /// Makes the next call of the specified [`MainNodeClient`] method (e.g., `"l1_batch_root_hash"`) fail
+    /// with the provided error. Errors injected for the same method are returned for consecutive calls
+    /// in the injection order.
+    pub fn inject_error(&self, method: &'static str, error: InjectedError) {
+        self.state()
+            .injected_errors
+            .entry(method)
+            .or_default()
+            .push_back(error);
+    }
+
+    /// Makes all calls fail with the provided error until it's reset with `None`, e.g. to emulate
+    /// the main node outage. Takes precedence over errors injected with [`Self::inject_error()`].
+    pub fn set_persistent_error(&self, error: Option<InjectedError>) {
+        self.state().persistent_error = error;
+    }
+
+    fn check_error(&self, method: &'static str) -> EnrichedClientResult<()> {
+        let mut state = self.state();
+        let error = state.persistent_error.or_else(|| {
+            state
+                .injected_errors
+                .get_mut(method)
+                .and_then(VecDeque::pop_front)
+        });
+        match error {
+            Some(error) => Err(EnrichedClientError::new(error.into(), method)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl MainNodeClient for MockMainNodeClient {
+    async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber> {
+        self.check_error("sealed_miniblock_number")?;
+        Ok(self.state().sealed_miniblock_number)
+    }
+
+    async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber> {
+        self.check_error("sealed_l1_batch_number")?;
+        Ok(self.state().sealed_l1_batch_number)
+    }
+
+    async fn miniblock_hash(&self, number: MiniblockNumber) -> EnrichedClientResult<Option<H256>> {
+        self.check_error("miniblock_hash")?;
+        Ok(self.state().miniblock_hashes.get(&number).copied())
+    }
+
+    async fn miniblock_hashes(
+        &self,
+        range: RangeInclusive<MiniblockNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        self.check_error("miniblock_hashes")?;
+        let state = self.state();
+        let hashes = (range.start().0..=range.end().0).map(|number| {
+            state
+                .miniblock_hashes
+                .get(&MiniblockNumber(number))
+                .copied()
+        });
+        Ok(hashes.collect())
+    }
+
+    async fn miniblock_parent_hash(
+        &self,
+        _number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.check_error("miniblock_parent_hash")?;
+        Ok(None)
+    }
+
+    async fn l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.check_error("l1_batch_root_hash")?;
+        Ok(self.state().l1_batch_root_hashes.get(&number).copied())
+    }
+
+    async fn l1_batch_root_hashes(
+        &self,
+        range: RangeInclusive<L1BatchNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        self.check_error("l1_batch_root_hashes")?;
+        let state = self.state();
+        let hashes = (range.start().0..=range.end().0).map(|number| {
+            state
+                .l1_batch_root_hashes
+                .get(&L1BatchNumber(number))
+                .copied()
+        });
+        Ok(hashes.collect())
+    }
+
+    async fn l1_batch_miniblock_range(
+        &self,
+        _number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<(MiniblockNumber, MiniblockNumber)>> {
+        self.check_error("l1_batch_miniblock_range")?;
+        Ok(None)
+    }
+
+    async fn protocol_version(&self) -> EnrichedClientResult<u16> {
+        self.check_error("protocol_version")?;
+        Ok(self
+            .state()
+            .protocol_version
+            .unwrap_or(ProtocolVersionId::latest() as u16))
+    }
+
+    async fn fork_id(&self) -> EnrichedClientResult<Option<H256>> {
+        self.check_error("fork_id")?;
+        Ok(None)
+    }
+
+    async fn miniblock_virtual_blocks(
+        &self,
+        _number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<u32>> {
+        self.check_error("miniblock_virtual_blocks")?;
+        Ok(None)
+    }
+
+    async fn l1_batch_details(
+        &self,
+        _number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<L1BatchDetails>> {
+        self.check_error("l1_batch_details")?;
+        Ok(None)
+    }
+
+    async fn l1_batch_proven(&self, _number: L1BatchNumber) -> EnrichedClientResult<Option<bool>> {
+        self.check_error("l1_batch_proven")?;
+        Ok(None)
+    }
+
+    async fn l2_to_l1_logs_root_hash(&self, _tx_hash: H256) -> EnrichedClientResult<Option<H256>> {
+        self.check_error("l2_to_l1_logs_root_hash")?;
+        Ok(None)
+    }
+
+    async fn l1_batch_commitment(
+        &self,
+        _number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.check_error("l1_batch_commitment")?;
+        Ok(None)
+    }
+
+    async fn miniblock_transaction_hashes(
+        &self,
+        _number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<Vec<H256>>> {
+        self.check_error("miniblock_transaction_hashes")?;
+        Ok(None)
+    }
+
+    async fn pending_miniblock(&self) -> EnrichedClientResult<Option<(MiniblockNumber, H256)>> {
+        self.check_error("pending_miniblock")?;
+        Ok(None)
+    }
+
+    async fn last_executed_l1_batch(&self) -> EnrichedClientResult<Option<L1BatchNumber>> {
+        self.check_error("last_executed_l1_batch")?;
+        Ok(None)
+    }
+
+    async fn l1_batch_pass_through_data_hash(
+        &self,
+        _number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        self.check_error("l1_batch_pass_through_data_hash")?;
+        Ok(None)
+    }
+}
@@ -6,18 +6,34 @@ use std::{
 };
 
 use assert_matches::assert_matches;
+use futures::future;
 use test_casing::{test_casing, Product};
 use tokio::sync::mpsc;
 use zksync_dal::StorageProcessor;
+use zksync_health_check::CheckHealth;
 use zksync_types::{
+    aggregated_operations::AggregatedActionType,
     block::{MiniblockHasher, MiniblockHeader},
-    L2ChainId, ProtocolVersion,
+    fee::TransactionExecutionMetrics,
+    get_code_key,
+    l2_to_l1_log::{L2ToL1Log, UserL2ToL1Log},
+    tokens::{TokenInfo, TokenMetadata},
+    tx::IncludedTxLocation,
+    Address, L2ChainId, ProtocolVersion, ProtocolVersionId, StorageLog, VmEvent,
 };
+use zksync_web3_decl::jsonrpsee::http_client::HttpClientBuilder;
 
 use super::*;
 use crate::{
+    block_reverter::L1ExecutedBatchesRevert,
     genesis::{ensure_genesis_state, GenesisParams},
-    utils::testonly::{create_l1_batch, create_miniblock},
+    utils::{
+        binary_search_with,
+        testonly::{
+            create_l1_batch, create_l1_batch_metadata, create_l2_transaction, create_miniblock,
+            execute_l2_transaction, l1_batch_metadata_to_commitment_artifacts,
+        },
+    },
 };
 
 async fn store_miniblock(storage: &mut StorageProcessor<'_>, number: u32, hash: H256) {
@@ -61,228 +77,5063 @@ async fn binary_search_with_simple_predicate() {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum RpcErrorKind {
-    Transient,
-    Fatal,
+/// Probe strategy using the specified probes in order and falling back to binary search afterwards.
+#[derive(Debug, Default)]
+struct FixedOrderProbes {
+    probes: Mutex<Vec<L1BatchNumber>>,
+    observed_bounds: Arc<Mutex<Vec<(L1BatchNumber, L1BatchNumber)>>>,
 }
 
-impl From<RpcErrorKind> for RpcError {
-    fn from(kind: RpcErrorKind) -> Self {
-        match kind {
-            RpcErrorKind::Transient => Self::RequestTimeout,
-            RpcErrorKind::Fatal => Self::HttpNotImplemented,
+impl ProbeStrategy for FixedOrderProbes {
+    fn next_probe(&self, known_valid: L1BatchNumber, diverged: L1BatchNumber) -> L1BatchNumber {
+        self.observed_bounds
+            .lock()
+            .unwrap()
+            .push((known_valid, diverged));
+        let mut probes = self.probes.lock().unwrap();
+        if probes.is_empty() {
+            L1BatchNumber((known_valid.0 + diverged.0) / 2)
+        } else {
+            probes.remove(0)
         }
     }
 }
 
-#[derive(Debug, Default)]
-struct MockMainNodeClient {
-    latest_miniblock_response: Option<MiniblockNumber>,
-    latest_l1_batch_response: Option<L1BatchNumber>,
-    miniblock_hash_responses: HashMap<MiniblockNumber, H256>,
-    l1_batch_root_hash_responses: HashMap<L1BatchNumber, H256>,
-    error_kind: Arc<Mutex<Option<RpcErrorKind>>>,
+#[tokio::test]
+async fn custom_probe_strategy_is_honored() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let probes = [9, 2, 7, 5].map(L1BatchNumber);
+    let strategy = FixedOrderProbes {
+        probes: Mutex::new(probes.to_vec()),
+        ..FixedOrderProbes::default()
+    };
+    let observed_bounds = strategy.observed_bounds.clone();
+    let detector = create_mock_detector(client, pool).with_probe_strategy(strategy);
+    let last_correct_l1_batch = detector
+        .detect_reorg(L1BatchNumber(0), L1BatchNumber(10))
+        .await
+        .unwrap();
+    assert_eq!(last_correct_l1_batch, L1BatchNumber(6));
+
+    let observed_bounds = observed_bounds.lock().unwrap().clone();
+    let expected_bounds = [(0, 10), (0, 9), (2, 9), (2, 7), (5, 7)]
+        .map(|(left, right)| (L1BatchNumber(left), L1BatchNumber(right)));
+    assert_eq!(observed_bounds, expected_bounds);
 }
 
-#[async_trait]
-impl MainNodeClient for MockMainNodeClient {
-    async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber> {
-        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
-            return Err(EnrichedClientError::new(
-                error_kind.into(),
-                "sealed_miniblock_number",
-            ));
+/// Binary search strategy sending a stop signal on the specified probe.
+#[derive(Debug)]
+struct StoppingProbes {
+    stop_sender: watch::Sender<bool>,
+    remaining_probes: Mutex<usize>,
+}
+
+impl ProbeStrategy for StoppingProbes {
+    fn next_probe(&self, known_valid: L1BatchNumber, diverged: L1BatchNumber) -> L1BatchNumber {
+        let mut remaining_probes = self.remaining_probes.lock().unwrap();
+        *remaining_probes -= 1;
+        if *remaining_probes == 0 {
+            self.stop_sender.send_replace(true);
         }
-        Ok(self
-            .latest_miniblock_response
-            .expect("unexpected `sealed_miniblock_number` request"))
+        L1BatchNumber((known_valid.0 + diverged.0) / 2)
     }
+}
 
-    async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber> {
-        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
-            return Err(EnrichedClientError::new(
-                error_kind.into(),
-                "sealed_l1_batch_number",
-            ));
+#[tokio::test]
+async fn interrupted_reorg_localization_returns_partial_bounds() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let strategy = StoppingProbes {
+        stop_sender,
+        remaining_probes: Mutex::new(2),
+    };
+    let mut detector = create_mock_detector(client, pool.clone())
+        .with_probe_strategy(strategy)
+        .with_reorg_persistence(true);
+    detector.stop_receiver = Some(stop_receiver);
+
+    // The search is stopped after probing L1 batches #5 (valid) and #7 (diverged).
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(
+        outcome,
+        CheckOutcome::LocalizationInterrupted {
+            known_valid: L1BatchNumber(5),
+            known_diverged: L1BatchNumber(7),
         }
-        Ok(self
-            .latest_l1_batch_response
-            .expect("unexpected `sealed_l1_batch_number` request"))
+    );
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let persisted_bounds = storage
+        .reorg_detector_dal()
+        .get_reorg_search_bounds()
+        .await
+        .unwrap();
+    assert_eq!(persisted_bounds, Some((L1BatchNumber(5), L1BatchNumber(7))));
+    let diverged_l1_batch = storage
+        .reorg_detector_dal()
+        .get_diverged_l1_batch()
+        .await
+        .unwrap();
+    assert_eq!(diverged_l1_batch, None);
+}
+
+#[tokio::test]
+async fn in_flight_probe_is_aborted_on_stop_signal() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    for number in 1..=10 {
+        seal_l1_batch(&mut storage, number, H256::from_low_u64_be(number.into())).await;
     }
+    drop(storage);
 
-    async fn miniblock_hash(&self, number: MiniblockNumber) -> EnrichedClientResult<Option<H256>> {
-        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
-            return Err(
-                EnrichedClientError::new(error_kind.into(), "miniblock_hash")
-                    .with_arg("number", &number),
-            );
+    let client = MockMainNodeClient {
+        hang_l1_batch_root_hash_requests: true,
+        ..MockMainNodeClient::default()
+    };
+    let detector = create_mock_detector(client, pool);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let search = detector.detect_reorg_cancellable(
+        L1BatchNumber(0),
+        L1BatchNumber(10),
+        Some(&stop_receiver),
+        false,
+    );
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stop_sender.send_replace(true);
+    };
+    let (search_result, ()) =
+        tokio::time::timeout(Duration::from_secs(5), future::join(search, stop))
+            .await
+            .expect("search was not aborted");
+    assert_eq!(
+        search_result.unwrap(),
+        SearchResult::Partial {
+            known_valid: L1BatchNumber(0),
+            known_diverged: L1BatchNumber(10),
         }
+    );
+}
 
-        if let Some(response) = self.miniblock_hash_responses.get(&number) {
-            Ok(Some(*response))
+#[tokio::test]
+async fn reorg_localization_is_resumed_from_persisted_bounds() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
         } else {
-            Ok(None)
-        }
+            H256::repeat_byte(0xff)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
     }
 
-    async fn l1_batch_root_hash(
-        &self,
-        number: L1BatchNumber,
-    ) -> EnrichedClientResult<Option<H256>> {
-        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
-            return Err(
-                EnrichedClientError::new(error_kind.into(), "l1_batch_root_hash")
-                    .with_arg("number", &number),
-            );
+    // Without persisted bounds, resuming is a no-op.
+    let detector = create_mock_detector(client.clone(), pool.clone());
+    assert_eq!(detector.resume_localization().await.unwrap(), None);
+
+    let full_search_strategy = FixedOrderProbes::default();
+    let full_search_bounds = full_search_strategy.observed_bounds.clone();
+    let detector = create_mock_detector(client.clone(), pool.clone())
+        .with_probe_strategy(full_search_strategy);
+    let last_correct_l1_batch = detector
+        .detect_reorg(L1BatchNumber(0), L1BatchNumber(10))
+        .await
+        .unwrap();
+    assert_eq!(last_correct_l1_batch, L1BatchNumber(6));
+
+    storage
+        .reorg_detector_dal()
+        .set_reorg_search_bounds(Some((L1BatchNumber(5), L1BatchNumber(8))))
+        .await
+        .unwrap();
+    let resumed_search_strategy = FixedOrderProbes::default();
+    let resumed_search_bounds = resumed_search_strategy.observed_bounds.clone();
+    let detector = create_mock_detector(client, pool.clone())
+        .with_probe_strategy(resumed_search_strategy)
+        .with_reorg_persistence(true);
+    let last_correct_l1_batch = detector.resume_localization().await.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(6)));
+
+    let full_search_probes = full_search_bounds.lock().unwrap().len();
+    let resumed_search_probes = resumed_search_bounds.lock().unwrap().len();
+    assert!(
+        resumed_search_probes < full_search_probes,
+        "{resumed_search_probes} >= {full_search_probes}"
+    );
+
+    let bounds = storage
+        .reorg_detector_dal()
+        .get_reorg_search_bounds()
+        .await
+        .unwrap();
+    assert_eq!(bounds, None);
+    let diverged_l1_batch = storage
+        .reorg_detector_dal()
+        .get_diverged_l1_batch()
+        .await
+        .unwrap();
+    assert_eq!(diverged_l1_batch, Some(L1BatchNumber(7)));
+}
+
+/// Binary search probe strategy panicking after the specified number of probes, which emulates abrupt node termination.
+#[derive(Debug)]
+struct TerminatingProbes(Mutex<usize>);
+
+impl ProbeStrategy for TerminatingProbes {
+    fn next_probe(&self, known_valid: L1BatchNumber, diverged: L1BatchNumber) -> L1BatchNumber {
+        let mut remaining_probes = self.0.lock().unwrap();
+        if *remaining_probes == 0 {
+            panic!("emulated node termination");
         }
+        *remaining_probes -= 1;
+        L1BatchNumber((known_valid.0 + diverged.0) / 2)
+    }
+}
 
-        if let Some(response) = self.l1_batch_root_hash_responses.get(&number) {
-            Ok(Some(*response))
+#[tokio::test]
+async fn reorg_localization_is_resumed_after_abrupt_termination() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
         } else {
-            Ok(None)
-        }
+            H256::repeat_byte(0xff)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
     }
+
+    let detector = create_mock_detector(client.clone(), pool.clone())
+        .with_probe_strategy(TerminatingProbes(Mutex::new(2)))
+        .with_reorg_persistence(true);
+    let search_task = tokio::spawn(async move {
+        detector
+            .detect_reorg_cancellable(L1BatchNumber(0), L1BatchNumber(10), None, true)
+            .await
+    });
+    assert!(search_task.await.unwrap_err().is_panic());
+    // Probes: #5 (matches), #7 (diverged)
+    let bounds = storage
+        .reorg_detector_dal()
+        .get_reorg_search_bounds()
+        .await
+        .unwrap();
+    assert_eq!(bounds, Some((L1BatchNumber(5), L1BatchNumber(7))));
+
+    let probe_strategy = FixedOrderProbes::default();
+    let observed_bounds = probe_strategy.observed_bounds.clone();
+    let detector = create_mock_detector(client, pool.clone())
+        .with_probe_strategy(probe_strategy)
+        .with_reorg_persistence(true);
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let last_correct_l1_batch = detector.run(stop_receiver).await.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(6)));
+    assert_eq!(
+        *observed_bounds.lock().unwrap(),
+        [(L1BatchNumber(5), L1BatchNumber(7))]
+    );
+
+    let bounds = storage
+        .reorg_detector_dal()
+        .get_reorg_search_bounds()
+        .await
+        .unwrap();
+    assert_eq!(bounds, None);
+    let diverged_l1_batch = storage
+        .reorg_detector_dal()
+        .get_diverged_l1_batch()
+        .await
+        .unwrap();
+    assert_eq!(diverged_l1_batch, Some(L1BatchNumber(7)));
 }
 
-impl HandleReorgDetectorEvent for mpsc::UnboundedSender<(MiniblockNumber, L1BatchNumber)> {
-    fn initialize(&mut self) {
-        // Do nothing
+#[tokio::test]
+async fn reorg_localization_starts_from_persisted_last_correct_l1_batch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        store_miniblock(&mut storage, number, local_hash).await;
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), local_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), local_hash);
     }
 
-    fn update_correct_block(
-        &mut self,
-        last_correct_miniblock: MiniblockNumber,
-        last_correct_l1_batch: L1BatchNumber,
-    ) {
-        self.send((last_correct_miniblock, last_correct_l1_batch))
-            .ok();
+    let mut detector =
+        create_mock_detector(client.clone(), pool.clone()).with_reorg_persistence(true);
+    detector.check_consistency().await.unwrap();
+    let checkpoint = storage
+        .reorg_detector_dal()
+        .get_last_correct_l1_batch()
+        .await
+        .unwrap();
+    assert_eq!(checkpoint, Some(L1BatchNumber(10)));
+
+    // Simulate a reorg after L1 batch #6, with the detector having persisted L1 batch #5 as correct.
+    for number in 7..=10 {
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(0xff));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(0xff));
     }
+    storage
+        .reorg_detector_dal()
+        .set_last_correct_l1_batch(Some(L1BatchNumber(5)))
+        .await
+        .unwrap();
+    drop(storage);
+
+    let search_strategy = FixedOrderProbes::default();
+    let search_bounds = search_strategy.observed_bounds.clone();
+    let mut detector = create_mock_detector(client, pool)
+        .with_probe_strategy(search_strategy)
+        .with_reorg_persistence(true);
+    detector
+        .load_last_correct_l1_batch_checkpoint()
+        .await
+        .unwrap();
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(6),
+            divergence: Some(DivergenceKind::RootHash {
+                l1_batch: L1BatchNumber(10),
+                local: H256::from_low_u64_be(10),
+                remote: H256::repeat_byte(0xff),
+            }),
+        }
+    );
+    let search_bounds = search_bounds.lock().unwrap();
+    assert_eq!(
+        search_bounds[0],
+        (L1BatchNumber(5), L1BatchNumber(10)),
+        "{search_bounds:?}"
+    );
+}
+
+#[tokio::test]
+async fn reorg_localization_starts_from_trusted_l1_batch_floor() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        store_miniblock(&mut storage, number, local_hash).await;
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let search_strategy = FixedOrderProbes::default();
+    let search_bounds = search_strategy.observed_bounds.clone();
+    let mut detector = create_mock_detector(client.clone(), pool.clone())
+        .with_probe_strategy(search_strategy)
+        .with_trusted_l1_batch_floor(Some(L1BatchNumber(4)));
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(6),
+            ..
+        }
+    );
+    let first_search_bounds = search_bounds.lock().unwrap()[0];
+    assert_eq!(first_search_bounds, (L1BatchNumber(4), L1BatchNumber(10)));
+
+    // The floor is not trusted blindly.
+    let mut detector =
+        create_mock_detector(client, pool).with_trusted_l1_batch_floor(Some(L1BatchNumber(8)));
+    let err = detector.check_consistency().await.unwrap_err();
+    assert_matches!(
+        err,
+        Error::HashMatch(HashMatchError::TrustedFloorMismatch(L1BatchNumber(8)))
+    );
+}
+
+#[tokio::test]
+async fn reorg_localization_starts_from_executed_l1_batch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        store_miniblock(&mut storage, number, local_hash).await;
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+    client.last_executed_l1_batch_response = Some(L1BatchNumber(5));
+
+    let search_strategy = FixedOrderProbes::default();
+    let search_bounds = search_strategy.observed_bounds.clone();
+    let mut detector = create_mock_detector(client.clone(), pool.clone())
+        .with_probe_strategy(search_strategy)
+        .with_trusted_l1_batch_floor(Some(L1BatchNumber(3)))
+        .with_executed_l1_batch_floor(true);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(6),
+            ..
+        }
+    );
+    let first_search_bounds = search_bounds.lock().unwrap()[0];
+    assert_eq!(first_search_bounds, (L1BatchNumber(5), L1BatchNumber(10)));
+
+    // The executed L1 batch is not trusted blindly.
+    client.last_executed_l1_batch_response = Some(L1BatchNumber(8));
+    let mut detector = create_mock_detector(client, pool).with_executed_l1_batch_floor(true);
+    let err = detector.check_consistency().await.unwrap_err();
+    assert_matches!(
+        err,
+        Error::HashMatch(HashMatchError::ExecutedL1BatchMismatch(L1BatchNumber(8)))
+    );
+}
+
+#[test_casing(2, [(2, 8), (6, 0)])]
+#[tokio::test]
+async fn fast_reorg_localization((window, expected_lower_bound): (u32, u32)) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        store_miniblock(&mut storage, number, local_hash).await;
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 8 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    // Emulate non-monotonic divergence; it breaks the fast localization assumption.
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(4), H256::repeat_byte(0xff));
+    drop(storage);
+
+    let search_strategy = FixedOrderProbes::default();
+    let search_bounds = search_strategy.observed_bounds.clone();
+    let mut detector = create_mock_detector(client, pool)
+        .with_probe_strategy(search_strategy)
+        .with_fast_localization(Some(window));
+    detector.check_consistency().await.unwrap();
+    let first_search_bounds = search_bounds.lock().unwrap()[0];
+    assert_eq!(
+        first_search_bounds,
+        (L1BatchNumber(expected_lower_bound), L1BatchNumber(10))
+    );
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RpcErrorKind {
+    Transient,
+    Fatal,
+}
+
+impl From<RpcErrorKind> for RpcError {
+    fn from(kind: RpcErrorKind) -> Self {
+        match kind {
+            RpcErrorKind::Transient => Self::RequestTimeout,
+            RpcErrorKind::Fatal => Self::HttpNotImplemented,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct MockMainNodeClient {
+    latest_miniblock_response: Option<MiniblockNumber>,
+    latest_l1_batch_response: Option<L1BatchNumber>,
+    miniblock_hash_responses: HashMap<MiniblockNumber, H256>,
+    miniblock_parent_hash_responses: HashMap<MiniblockNumber, H256>,
+    l1_batch_root_hash_responses: HashMap<L1BatchNumber, H256>,
+    miniblock_range_responses: HashMap<L1BatchNumber, (MiniblockNumber, MiniblockNumber)>,
+    virtual_blocks_responses: HashMap<MiniblockNumber, u32>,
+    protocol_version_response: Option<u16>,
+    fork_id_response: Option<H256>,
+    logs_root_hash_responses: HashMap<H256, H256>,
+    commitment_responses: HashMap<L1BatchNumber, H256>,
+    pass_through_data_hash_responses: HashMap<L1BatchNumber, H256>,
+    miniblock_transaction_responses: HashMap<MiniblockNumber, Vec<H256>>,
+    proven_l1_batch_responses: HashMap<L1BatchNumber, bool>,
+    l1_batch_details_responses: HashMap<L1BatchNumber, L1BatchDetails>,
+    pending_miniblock_response: Option<(MiniblockNumber, H256)>,
+    last_executed_l1_batch_response: Option<L1BatchNumber>,
+    /// Root hashes returned by `l1_batch_root_hashes` instead of `l1_batch_root_hash_responses`, emulating
+    /// the main node returning inconsistent data.
+    stale_l1_batch_root_hash_responses: HashMap<L1BatchNumber, H256>,
+    /// Names of hash / details methods called on the client, in the call order.
+    requested_methods: Arc<Mutex<Vec<&'static str>>>,
+    /// Number of subsequent `miniblock_hash` requests that will hang indefinitely.
+    hanging_miniblock_hash_requests: Arc<Mutex<usize>>,
+    /// If set, all L1 batch root hash requests will hang indefinitely.
+    hang_l1_batch_root_hash_requests: bool,
+    /// Latency of each L1 batch root hash request.
+    l1_batch_root_hash_latency: Duration,
+    error_kind: Arc<Mutex<Option<RpcErrorKind>>>,
+}
+
+#[async_trait]
+impl MainNodeClient for MockMainNodeClient {
+    async fn sealed_miniblock_number(&self) -> EnrichedClientResult<MiniblockNumber> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(
+                error_kind.into(),
+                "sealed_miniblock_number",
+            ));
+        }
+        Ok(self
+            .latest_miniblock_response
+            .expect("unexpected `sealed_miniblock_number` request"))
+    }
+
+    async fn sealed_l1_batch_number(&self) -> EnrichedClientResult<L1BatchNumber> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(
+                error_kind.into(),
+                "sealed_l1_batch_number",
+            ));
+        }
+        Ok(self
+            .latest_l1_batch_response
+            .expect("unexpected `sealed_l1_batch_number` request"))
+    }
+
+    async fn miniblock_hash(&self, number: MiniblockNumber) -> EnrichedClientResult<Option<H256>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "miniblock_hash")
+                    .with_arg("number", &number),
+            );
+        }
+        self.requested_methods
+            .lock()
+            .unwrap()
+            .push("miniblock_hash");
+        let should_hang = {
+            let mut hanging_requests = self.hanging_miniblock_hash_requests.lock().unwrap();
+            let should_hang = *hanging_requests > 0;
+            *hanging_requests = hanging_requests.saturating_sub(1);
+            should_hang
+        };
+        if should_hang {
+            future::pending::<()>().await;
+        }
+
+        if let Some(response) = self.miniblock_hash_responses.get(&number) {
+            Ok(Some(*response))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn miniblock_hashes(
+        &self,
+        range: RangeInclusive<MiniblockNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "miniblock_hashes")
+                    .with_arg("range", &range),
+            );
+        }
+        self.requested_methods
+            .lock()
+            .unwrap()
+            .push("miniblock_hashes");
+
+        let hashes = (range.start().0..=range.end().0)
+            .map(|number| {
+                self.miniblock_hash_responses
+                    .get(&MiniblockNumber(number))
+                    .copied()
+            })
+            .collect();
+        Ok(hashes)
+    }
+
+    async fn miniblock_parent_hash(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "miniblock_parent_hash")
+                    .with_arg("number", &number),
+            );
+        }
+        Ok(self.miniblock_parent_hash_responses.get(&number).copied())
+    }
+
+    async fn l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "l1_batch_root_hash")
+                    .with_arg("number", &number),
+            );
+        }
+        self.requested_methods
+            .lock()
+            .unwrap()
+            .push("l1_batch_root_hash");
+        if self.hang_l1_batch_root_hash_requests {
+            future::pending::<()>().await;
+        }
+        if !self.l1_batch_root_hash_latency.is_zero() {
+            tokio::time::sleep(self.l1_batch_root_hash_latency).await;
+        }
+
+        if let Some(response) = self.l1_batch_root_hash_responses.get(&number) {
+            Ok(Some(*response))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn l1_batch_root_hashes(
+        &self,
+        range: RangeInclusive<L1BatchNumber>,
+    ) -> EnrichedClientResult<Vec<Option<H256>>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "l1_batch_root_hashes")
+                    .with_arg("range", &range),
+            );
+        }
+        self.requested_methods
+            .lock()
+            .unwrap()
+            .push("l1_batch_root_hashes");
+        if self.hang_l1_batch_root_hash_requests {
+            future::pending::<()>().await;
+        }
+        if !self.l1_batch_root_hash_latency.is_zero() {
+            tokio::time::sleep(self.l1_batch_root_hash_latency).await;
+        }
+
+        let hashes = (range.start().0..=range.end().0)
+            .map(|number| {
+                let number = L1BatchNumber(number);
+                self.stale_l1_batch_root_hash_responses
+                    .get(&number)
+                    .or_else(|| self.l1_batch_root_hash_responses.get(&number))
+                    .copied()
+            })
+            .collect();
+        Ok(hashes)
+    }
+
+    async fn l1_batch_miniblock_range(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<(MiniblockNumber, MiniblockNumber)>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "l1_batch_miniblock_range")
+                    .with_arg("number", &number),
+            );
+        }
+        Ok(self.miniblock_range_responses.get(&number).copied())
+    }
+
+    async fn protocol_version(&self) -> EnrichedClientResult<u16> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(
+                error_kind.into(),
+                "protocol_version",
+            ));
+        }
+        Ok(self
+            .protocol_version_response
+            .unwrap_or(ProtocolVersionId::latest() as u16))
+    }
+
+    async fn fork_id(&self) -> EnrichedClientResult<Option<H256>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(error_kind.into(), "fork_id"));
+        }
+        Ok(self.fork_id_response)
+    }
+
+    async fn miniblock_virtual_blocks(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<u32>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "miniblock_virtual_blocks")
+                    .with_arg("number", &number),
+            );
+        }
+        Ok(self.virtual_blocks_responses.get(&number).copied())
+    }
+
+    async fn l1_batch_details(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<L1BatchDetails>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "l1_batch_details")
+                    .with_arg("number", &number),
+            );
+        }
+        self.requested_methods
+            .lock()
+            .unwrap()
+            .push("l1_batch_details");
+        Ok(self.l1_batch_details_responses.get(&number).cloned())
+    }
+
+    async fn l1_batch_proven(&self, number: L1BatchNumber) -> EnrichedClientResult<Option<bool>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "l1_batch_proven")
+                    .with_arg("number", &number),
+            );
+        }
+        Ok(self.proven_l1_batch_responses.get(&number).copied())
+    }
+
+    async fn l2_to_l1_logs_root_hash(&self, tx_hash: H256) -> EnrichedClientResult<Option<H256>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "l2_to_l1_logs_root_hash")
+                    .with_arg("tx_hash", &tx_hash),
+            );
+        }
+        Ok(self.logs_root_hash_responses.get(&tx_hash).copied())
+    }
+
+    async fn l1_batch_commitment(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(
+                EnrichedClientError::new(error_kind.into(), "l1_batch_commitment")
+                    .with_arg("number", &number),
+            );
+        }
+        Ok(self.commitment_responses.get(&number).copied())
+    }
+
+    async fn miniblock_transaction_hashes(
+        &self,
+        number: MiniblockNumber,
+    ) -> EnrichedClientResult<Option<Vec<H256>>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(
+                error_kind.into(),
+                "miniblock_transaction_hashes",
+            )
+            .with_arg("number", &number));
+        }
+        self.requested_methods
+            .lock()
+            .unwrap()
+            .push("miniblock_transaction_hashes");
+        Ok(self.miniblock_transaction_responses.get(&number).cloned())
+    }
+
+    async fn pending_miniblock(&self) -> EnrichedClientResult<Option<(MiniblockNumber, H256)>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(
+                error_kind.into(),
+                "pending_miniblock",
+            ));
+        }
+        Ok(self.pending_miniblock_response)
+    }
+
+    async fn last_executed_l1_batch(&self) -> EnrichedClientResult<Option<L1BatchNumber>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(
+                error_kind.into(),
+                "last_executed_l1_batch",
+            ));
+        }
+        Ok(self.last_executed_l1_batch_response)
+    }
+
+    async fn l1_batch_pass_through_data_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> EnrichedClientResult<Option<H256>> {
+        if let &Some(error_kind) = &*self.error_kind.lock().unwrap() {
+            return Err(EnrichedClientError::new(
+                error_kind.into(),
+                "l1_batch_pass_through_data_hash",
+            )
+            .with_arg("number", &number));
+        }
+        Ok(self.pass_through_data_hash_responses.get(&number).copied())
+    }
+}
+
+#[async_trait]
+impl HandleReorgDetectorEvent for mpsc::UnboundedSender<(MiniblockNumber, L1BatchNumber)> {
+    async fn initialize(&mut self) {
+        // Do nothing
+    }
+
+    async fn update_correct_block(
+        &mut self,
+        last_correct_miniblock: MiniblockNumber,
+        last_correct_l1_batch: L1BatchNumber,
+    ) {
+        self.send((last_correct_miniblock, last_correct_l1_batch))
+            .ok();
+    }
+
+    async fn report_divergence(&mut self, _diverged_l1_batch: L1BatchNumber) {
+        // Do nothing
+    }
+
+    async fn report_localized_reorg(
+        &mut self,
+        _last_correct_l1_batch: L1BatchNumber,
+        _last_correct_miniblock: Option<MiniblockNumber>,
+    ) {
+        // Do nothing
+    }
+
+    async fn report_suspicion(&mut self, _suspicion: ReorgSuspicion) {
+        // Do nothing
+    }
+
+    async fn report_transient_failure(
+        &mut self,
+        _consecutive_failures: usize,
+        _last_correct_block: Option<(MiniblockNumber, L1BatchNumber)>,
+    ) {
+        // Do nothing
+    }
+
+    async fn report_failure(&mut self, _error: &str) {
+        // Do nothing
+    }
+
+    async fn start_shutting_down(&mut self) {
+        // Do nothing
+    }
+}
+
+#[async_trait]
+impl HandleReorgDetectorEvent for mpsc::UnboundedSender<ReorgSuspicion> {
+    async fn initialize(&mut self) {
+        // Do nothing
+    }
+
+    async fn update_correct_block(
+        &mut self,
+        _last_correct_miniblock: MiniblockNumber,
+        _last_correct_l1_batch: L1BatchNumber,
+    ) {
+        // Do nothing
+    }
+
+    async fn report_divergence(&mut self, _diverged_l1_batch: L1BatchNumber) {
+        // Do nothing
+    }
+
+    async fn report_localized_reorg(
+        &mut self,
+        _last_correct_l1_batch: L1BatchNumber,
+        _last_correct_miniblock: Option<MiniblockNumber>,
+    ) {
+        // Do nothing
+    }
+
+    async fn report_suspicion(&mut self, suspicion: ReorgSuspicion) {
+        self.send(suspicion).ok();
+    }
+
+    async fn report_transient_failure(
+        &mut self,
+        _consecutive_failures: usize,
+        _last_correct_block: Option<(MiniblockNumber, L1BatchNumber)>,
+    ) {
+        // Do nothing
+    }
+
+    async fn report_failure(&mut self, _error: &str) {
+        // Do nothing
+    }
+
+    async fn start_shutting_down(&mut self) {
+        // Do nothing
+    }
+}
+
+/// Checks that the health is ready and reports the specified last correct blocks, together with the check timestamp.
+fn is_ready_with_correct_blocks(health: Health, miniblock: u32, l1_batch: u32) -> bool {
+    let health = serde_json::to_value(health).unwrap();
+    let details = &health["details"];
+    if health["status"] != "ready"
+        || details["last_correct_miniblock"] != miniblock
+        || details["last_correct_l1_batch"] != l1_batch
+    {
+        return false;
+    }
+    let checked_at = details["checked_at"]
+        .as_str()
+        .expect("no `checked_at` in health details");
+    chrono::DateTime::parse_from_rfc3339(checked_at).unwrap();
+    true
+}
+
+fn create_mock_detector(client: MockMainNodeClient, pool: ConnectionPool) -> ReorgDetector {
+    ReorgDetector::from_parts(Box::new(client), pool).with_sleep_interval(Duration::from_millis(10))
+}
+
+#[test_casing(4, Product(([false, true], [false, true])))]
+#[tokio::test]
+async fn normal_reorg_function(snapshot_recovery: bool, with_transient_errors: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let mut client = MockMainNodeClient::default();
+    if snapshot_recovery {
+        storage
+            .protocol_versions_dal()
+            .save_protocol_version_with_tx(ProtocolVersion::default())
+            .await;
+    } else {
+        let genesis_root_hash =
+            ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+                .await
+                .unwrap();
+        client.miniblock_hash_responses.insert(
+            MiniblockNumber(0),
+            MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+        );
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(0), genesis_root_hash);
+    }
+
+    let l1_batch_numbers = if snapshot_recovery {
+        11_u32..=20
+    } else {
+        1_u32..=10
+    };
+    let last_l1_batch_number = L1BatchNumber(*l1_batch_numbers.end());
+    let last_miniblock_number = MiniblockNumber(*l1_batch_numbers.end());
+    let miniblock_and_l1_batch_hashes = l1_batch_numbers.map(|number| {
+        let miniblock_hash = H256::from_low_u64_be(number.into());
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), miniblock_hash);
+        let l1_batch_hash = H256::repeat_byte(number as u8);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), l1_batch_hash);
+        (number, miniblock_hash, l1_batch_hash)
+    });
+    let miniblock_and_l1_batch_hashes: Vec<_> = miniblock_and_l1_batch_hashes.collect();
+
+    if with_transient_errors {
+        *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+        // "Fix" the client after a certain delay.
+        let error_kind = Arc::clone(&client.error_kind);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            *error_kind.lock().unwrap() = None;
+        });
+    }
+
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let (block_update_sender, mut block_update_receiver) =
+        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(block_update_sender),
+        ..create_mock_detector(client, pool.clone())
+    };
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    for (number, miniblock_hash, l1_batch_hash) in miniblock_and_l1_batch_hashes {
+        store_miniblock(&mut storage, number, miniblock_hash).await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        seal_l1_batch(&mut storage, number, l1_batch_hash).await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    while let Some((miniblock, l1_batch)) = block_update_receiver.recv().await {
+        assert!(miniblock <= last_miniblock_number);
+        assert!(l1_batch <= last_l1_batch_number);
+        if miniblock == last_miniblock_number && l1_batch == last_l1_batch_number {
+            break;
+        }
+    }
+
+    // Check detector shutdown
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn paused_detector_skips_consistency_checks() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let (pause_sender, pause_receiver) = watch::channel(true);
+    let (block_update_sender, mut block_update_receiver) =
+        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(block_update_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_pause_signal(pause_receiver);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(block_update_receiver.try_recv().is_err());
+
+    pause_sender.send_replace(false);
+    let (miniblock, l1_batch) = block_update_receiver.recv().await.unwrap();
+    assert_eq!(miniblock, MiniblockNumber(0));
+    assert_eq!(l1_batch, L1BatchNumber(0));
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn detector_fails_after_max_transient_retries() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    drop(storage);
+
+    let client = MockMainNodeClient::default();
+    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+    let detector = ReorgDetector::from_parts(Box::new(client), pool)
+        .with_sleep_interval(Duration::from_millis(10))
+        .with_max_transient_retries(2);
+    let health_check = detector.health_check();
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let err = detector.run(stop_receiver).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("exceeded 2 consecutive retries"), "{err}");
+
+    // The health updater is dropped together with the detector.
+    let health = health_check.check_health().await;
+    assert_matches!(health.status(), HealthStatus::ShutDown);
+
+    let (health_check, mut health_updater) = ReactiveHealthCheck::new("reorg_detector");
+    health_updater
+        .report_failure("main node is unreachable")
+        .await;
+    let expected_health = Health::from(HealthStatus::NotReady)
+        .with_details(serde_json::json!({ "error": "main node is unreachable" }));
+    assert_eq!(health_check.check_health().await, expected_health);
+}
+
+#[test_casing(2, [RpcErrorKind::Transient, RpcErrorKind::Fatal])]
+#[tokio::test]
+async fn failure_is_reported_for_all_detector_errors(error_kind: RpcErrorKind) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    drop(storage);
+
+    let client = MockMainNodeClient::default();
+    *client.error_kind.lock().unwrap() = Some(error_kind);
+    let (event_sender, mut event_receiver) = broadcast::channel(16);
+    let detector = ReorgDetector {
+        max_transient_retries: Some(1),
+        ..create_mock_detector(client, pool)
+    }
+    .with_event_broadcast(event_sender);
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let err = detector.run(stop_receiver).await.unwrap_err();
+
+    let mut reported_errors = vec![];
+    while let Ok(event) = event_receiver.try_recv() {
+        if let ReorgEvent::Failed { error } = event {
+            reported_errors.push(error);
+        }
+    }
+    assert_eq!(reported_errors, [format!("{err:#}")]);
+}
+
+#[tokio::test]
+async fn reorg_is_detected_with_scripted_client() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let client = testonly::MockMainNodeClient::default();
+    client.set_miniblock_hash(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client.set_l1_batch_root_hash(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=3 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        // The main node diverges starting from L1 batch #2.
+        let remote_byte = if number >= 2 { 0xff } else { number as u8 };
+        client.set_miniblock_hash(MiniblockNumber(number), H256::repeat_byte(remote_byte));
+        client.set_l1_batch_root_hash(L1BatchNumber(number), H256::repeat_byte(remote_byte));
+    }
+    drop(storage);
+    client.inject_error("sealed_l1_batch_number", testonly::InjectedError::Transient);
+
+    let detector = ReorgDetector::from_parts(Box::new(client.clone()), pool)
+        .with_sleep_interval(Duration::from_millis(10));
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let last_correct_l1_batch = detector.run(stop_receiver).await.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(1)));
+
+    client.set_persistent_error(Some(testonly::InjectedError::Fatal));
+    let err = client.sealed_l1_batch_number().await.unwrap_err();
+    assert!(!HashMatchError::Rpc(err).is_transient());
+}
+
+/// Clock recording requested sleeps and returning immediately.
+#[derive(Debug, Default)]
+struct RecordingClock(Arc<Mutex<Vec<Duration>>>);
+
+#[async_trait]
+impl Clock for RecordingClock {
+    async fn sleep(&self, duration: Duration) {
+        self.0.lock().unwrap().push(duration);
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test]
+async fn retry_backoff_is_exponential_and_capped() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    drop(storage);
+
+    let client = MockMainNodeClient::default();
+    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+    let clock = RecordingClock::default();
+    let sleeps = clock.0.clone();
+    // Backoffs are large enough for the test to hang if the real timer were used.
+    let detector = ReorgDetector {
+        min_retry_backoff: Some(Duration::from_secs(60)),
+        max_retry_backoff: Duration::from_secs(240),
+        ..create_mock_detector(client, pool)
+    }
+    .with_max_transient_retries(5)
+    .with_clock(clock);
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    detector.run(stop_receiver).await.unwrap_err();
+
+    let sleeps = sleeps.lock().unwrap().clone();
+    let expected_backoffs = [60, 120, 240, 240, 240].map(Duration::from_secs);
+    assert_eq!(sleeps.len(), expected_backoffs.len(), "{sleeps:?}");
+    for (sleep, backoff) in sleeps.into_iter().zip(expected_backoffs) {
+        // Backoffs are jittered.
+        assert!(
+            sleep >= backoff / 2 && sleep <= backoff,
+            "{sleep:?} vs {backoff:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn consecutive_transient_failures_are_reported_in_health() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+    let error_kind = Arc::clone(&client.error_kind);
+
+    let sink = CapturingMetricsSink::default();
+    let metric_calls = sink.0.clone();
+    let detector = create_mock_detector(client, pool).with_metrics_sink(sink);
+    let health_check = detector.health_check();
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    loop {
+        let health = serde_json::to_value(health_check.check_health().await).unwrap();
+        let failure_count = health["details"]["consecutive_transient_failures"].as_u64();
+        if failure_count.map_or(false, |count| count >= 2) {
+            assert_eq!(health["status"], "affected");
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // "Fix" the client; the failure count should be reset after a successful check.
+    *error_kind.lock().unwrap() = None;
+    while !is_ready_with_correct_blocks(health_check.check_health().await, 0, 0) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+
+    let staleness_gauge_values: Vec<_> = metric_calls
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|call| match call {
+            MetricCall::Gauge(DetectorGauge::SecondsSinceLastSuccessfulCheck, value) => {
+                Some(*value)
+            }
+            _ => None,
+        })
+        .collect();
+    // The gauge should be updated both on transient failures and on successful checks.
+    assert!(
+        staleness_gauge_values.len() > 2,
+        "{staleness_gauge_values:?}"
+    );
+    assert_eq!(staleness_gauge_values.last(), Some(&0));
+}
+
+#[tokio::test]
+async fn time_since_last_successful_check_is_reported() {
+    let pool = ConnectionPool::test_pool().await;
+    let sink = CapturingMetricsSink::default();
+    let metric_calls = sink.0.clone();
+    let detector = ReorgDetector {
+        last_successful_check_at: Instant::now() - Duration::from_secs(600),
+        ..create_mock_detector(MockMainNodeClient::default(), pool)
+    }
+    .with_metrics_sink(sink);
+
+    detector.record_time_since_last_successful_check();
+    let metric_calls = metric_calls.lock().unwrap();
+    assert_matches!(
+        metric_calls.as_slice(),
+        [MetricCall::Gauge(
+            DetectorGauge::SecondsSinceLastSuccessfulCheck,
+            600..=601
+        )]
+    );
+}
+
+#[tokio::test]
+async fn detector_reports_to_external_health_check() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    // Emulate registering the health check before the detector is created.
+    let (health_check, health_updater) = ReactiveHealthCheck::new("external_reorg_detector");
+    let registered_health_check = health_check.clone();
+    let http_client = HttpClientBuilder::default()
+        .build("http://127.0.0.1:3050")
+        .unwrap();
+    let detector = ReorgDetector {
+        client: Box::new(client),
+        sleep_interval: Duration::from_millis(10),
+        ..ReorgDetector::with_health_check(http_client, pool, (health_check, health_updater))
+    };
+    assert_eq!(detector.health_check().name(), "external_reorg_detector");
+
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    while !is_ready_with_correct_blocks(registered_health_check.check_health().await, 0, 0) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+    let health = registered_health_check.check_health().await;
+    assert_matches!(health.status(), HealthStatus::ShutDown);
+}
+
+#[tokio::test]
+async fn virtual_blocks_mismatch_is_reported_as_suspicion() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::zero()).await;
+    seal_l1_batch(&mut storage, 1, H256::zero()).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::zero());
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::zero());
+    // The local miniblock has a single virtual block.
+    client
+        .virtual_blocks_responses
+        .insert(MiniblockNumber(1), 2);
+
+    let (suspicion_sender, mut suspicion_receiver) = mpsc::unbounded_channel::<ReorgSuspicion>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(suspicion_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_virtual_blocks_comparison(true);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    let suspicion = suspicion_receiver.recv().await.unwrap();
+    assert_eq!(
+        suspicion,
+        ReorgSuspicion::VirtualBlocksMismatch {
+            miniblock: MiniblockNumber(1),
+            local: 1,
+            remote: 2,
+        }
+    );
+
+    // A suspicion must not be treated as a reorg.
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn pending_miniblock_mismatch_is_reported_as_suspicion(compare_pending_miniblock: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::repeat_byte(1)).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    // Miniblock #2 is sealed locally, but is still pending on the main node.
+    store_miniblock(&mut storage, 2, H256::repeat_byte(2)).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::repeat_byte(1));
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client.latest_miniblock_response = Some(MiniblockNumber(1));
+    client.latest_l1_batch_response = Some(L1BatchNumber(1));
+    client.pending_miniblock_response = Some((MiniblockNumber(2), H256::repeat_byte(0xff)));
+
+    let (suspicion_sender, mut suspicion_receiver) = mpsc::unbounded_channel::<ReorgSuspicion>();
+    let mut detector = ReorgDetector {
+        event_handler: Box::new(suspicion_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_pending_miniblock_comparison(compare_pending_miniblock);
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let outcome = detector.run_once(&mut stop_receiver).await.unwrap();
+    // A pending miniblock mismatch must not be treated as a reorg.
+    assert_eq!(
+        outcome,
+        Some(CheckOutcome::Consistent {
+            l1_batch: L1BatchNumber(1),
+            miniblock: MiniblockNumber(1),
+        })
+    );
+
+    if compare_pending_miniblock {
+        let suspicion = suspicion_receiver.try_recv().unwrap();
+        assert_eq!(
+            suspicion,
+            ReorgSuspicion::PendingMiniblockMismatch {
+                miniblock: MiniblockNumber(2),
+                local_hash: H256::repeat_byte(2),
+                remote_hash: H256::repeat_byte(0xff),
+            }
+        );
+    } else {
+        suspicion_receiver.try_recv().unwrap_err();
+    }
+}
+
+#[tokio::test]
+async fn protocol_version_mismatch_is_reported_as_suspicion() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let local_version = ProtocolVersionId::latest() as u16;
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client.protocol_version_response = Some(local_version + 1);
+
+    let (suspicion_sender, mut suspicion_receiver) = mpsc::unbounded_channel::<ReorgSuspicion>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(suspicion_sender),
+        ..create_mock_detector(client, pool)
+    };
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    let suspicion = suspicion_receiver.recv().await.unwrap();
+    assert_eq!(
+        suspicion,
+        ReorgSuspicion::ProtocolVersionMismatch {
+            local: local_version,
+            remote: local_version + 1,
+        }
+    );
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn detector_stops_on_fatal_rpc_error() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+
+    let client = MockMainNodeClient::default();
+    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Fatal);
+
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let detector = create_mock_detector(client, pool.clone());
+    // Check that the detector stops when a fatal RPC error is encountered.
+    detector.run(stop_receiver).await.unwrap_err();
+}
+
+#[tokio::test]
+async fn custom_error_classifier_is_used() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Fatal);
+    let error_kind = Arc::clone(&client.error_kind);
+
+    // Treat all RPC errors (including fatal ones) as transient.
+    let detector = create_mock_detector(client, pool).with_error_classifier(|err| {
+        matches!(err, Error::HashMatch(HashMatchError::Rpc(_))) || err.is_transient()
+    });
+    let health_check = detector.health_check();
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    loop {
+        let health = serde_json::to_value(health_check.check_health().await).unwrap();
+        let failure_count = health["details"]["consecutive_transient_failures"].as_u64();
+        if failure_count.map_or(false, |count| count >= 2) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(!detector_task.is_finished());
+
+    *error_kind.lock().unwrap() = None;
+    while health_check.check_health().await.status() != HealthStatus::Ready {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn reorg_is_detected_on_batch_hash_mismatch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let miniblock_hash = H256::from_low_u64_be(23);
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), miniblock_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(2), miniblock_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(2), H256::repeat_byte(2));
+
+    let detector = create_mock_detector(client, pool.clone());
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    store_miniblock(&mut storage, 1, miniblock_hash).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    store_miniblock(&mut storage, 2, miniblock_hash).await;
+    seal_l1_batch(&mut storage, 2, H256::repeat_byte(0xff)).await;
+    // ^ Hash of L1 batch #2 differs from that on the main node.
+
+    let task_result = detector_task.await.unwrap();
+    let last_correct_l1_batch = task_result.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(1)));
+}
+
+#[tokio::test]
+async fn single_shot_consistency_checks() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=2 {
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+    }
+    store_miniblock(&mut storage, 1, H256::repeat_byte(1)).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector = create_mock_detector(client, pool.clone());
+    let outcome = detector.run_once(&mut stop_receiver).await.unwrap();
+    assert_eq!(
+        outcome,
+        Some(CheckOutcome::Consistent {
+            l1_batch: L1BatchNumber(1),
+            miniblock: MiniblockNumber(1),
+        })
+    );
+
+    store_miniblock(&mut storage, 2, H256::repeat_byte(2)).await;
+    seal_l1_batch(&mut storage, 2, H256::repeat_byte(0xff)).await;
+    // ^ Hash of L1 batch #2 differs from that on the main node.
+    let outcome = detector.run_once(&mut stop_receiver).await.unwrap();
+    assert_matches!(
+        outcome,
+        Some(CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(1),
+            ..
+        })
+    );
+}
+
+#[tokio::test]
+async fn sealed_block_checks_use_local_snapshot() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::repeat_byte(1));
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    store_miniblock(&mut storage, 1, H256::repeat_byte(1)).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+
+    let detector = create_mock_detector(client, pool.clone());
+    let mut snapshot_storage = pool.access_storage().await.unwrap();
+    let snapshot = detector
+        .read_local_snapshot(&mut snapshot_storage, L1BatchNumber(1), MiniblockNumber(1))
+        .await
+        .unwrap();
+    drop(snapshot_storage);
+
+    // Blocks are rolled back after the snapshot is taken; the checks should still compare the snapshot hashes
+    // rather than reading the local DB again.
+    storage
+        .blocks_dal()
+        .delete_l1_batches(L1BatchNumber(0))
+        .await
+        .unwrap();
+    storage
+        .blocks_dal()
+        .delete_miniblocks(MiniblockNumber(0))
+        .await
+        .unwrap();
+
+    let (checked_l1_batch, divergence) = detector
+        .check_sealed_l1_batch_root_hash(&snapshot)
+        .await
+        .unwrap();
+    assert_eq!(checked_l1_batch, L1BatchNumber(1));
+    assert!(divergence.is_none(), "{divergence:?}");
+    let (checked_miniblock, divergence) = detector
+        .check_sealed_miniblock_hash(&snapshot)
+        .await
+        .unwrap();
+    assert_eq!(checked_miniblock, MiniblockNumber(1));
+    assert!(divergence.is_none(), "{divergence:?}");
+}
+
+#[tokio::test]
+async fn reorg_is_detected_on_miniblock_hash_mismatch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let mut client = MockMainNodeClient::default();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let miniblock_hash = H256::from_low_u64_be(23);
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), miniblock_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(2), miniblock_hash);
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(3), miniblock_hash);
+
+    let detector = create_mock_detector(client, pool.clone());
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    store_miniblock(&mut storage, 1, miniblock_hash).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    store_miniblock(&mut storage, 2, miniblock_hash).await;
+    store_miniblock(&mut storage, 3, H256::repeat_byte(42)).await;
+    // ^ Hash of the miniblock #3 differs from that on the main node.
+
+    let task_result = detector_task.await.unwrap();
+    let last_correct_l1_batch = task_result.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(1)));
+    // ^ All locally stored L1 batches should be correct.
+}
+
+#[tokio::test]
+async fn diverged_miniblock_is_localized() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let mut client = MockMainNodeClient::default();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let miniblock_hash = H256::from_low_u64_be(23);
+    store_miniblock(&mut storage, 1, miniblock_hash).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), miniblock_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    for number in 2..=5 {
+        store_miniblock(&mut storage, number, miniblock_hash).await;
+        let remote_hash = if number <= 3 {
+            miniblock_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let requested_methods = client.requested_methods.clone();
+    let detector = create_mock_detector(client, pool);
+    let last_correct_miniblock = detector
+        .detect_miniblock_reorg(MiniblockNumber(1), MiniblockNumber(5))
+        .await
+        .unwrap();
+    assert_eq!(last_correct_miniblock, MiniblockNumber(3));
+    // Remote hashes should be fetched for the entire search range at once.
+    let requested_methods = std::mem::take(&mut *requested_methods.lock().unwrap());
+    assert_eq!(requested_methods, ["miniblock_hashes"]);
+
+    let (event_sender, mut event_receiver) = broadcast::channel(16);
+    let detector = detector.with_event_broadcast(event_sender);
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let last_correct_l1_batch = detector.run(stop_receiver).await.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(1)));
+
+    let mut events = vec![];
+    while let Ok(event) = event_receiver.try_recv() {
+        events.push(event);
+    }
+    assert!(
+        events.contains(&ReorgEvent::ReorgLocalized {
+            last_correct_l1_batch: L1BatchNumber(1),
+            last_correct_miniblock: Some(MiniblockNumber(3)),
+        }),
+        "{events:?}"
+    );
+}
+
+#[tokio::test]
+async fn miniblock_hash_mismatch_at_tip_is_tolerated_as_tip_churn() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let miniblock_hash = H256::from_low_u64_be(23);
+    store_miniblock(&mut storage, 1, miniblock_hash).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    store_miniblock(&mut storage, 2, H256::repeat_byte(42)).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), miniblock_hash);
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(2), miniblock_hash);
+    // ^ Hash of the miniblock #2 in the unsealed L1 batch differs from that on the main node.
+
+    let (suspicion_sender, mut suspicion_receiver) = mpsc::unbounded_channel::<ReorgSuspicion>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(suspicion_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_tip_churn_tolerance(true);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    let suspicion = suspicion_receiver.recv().await.unwrap();
+    assert_eq!(
+        suspicion,
+        ReorgSuspicion::TipChurn {
+            l1_batch: L1BatchNumber(1)
+        }
+    );
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn reorg_is_reported_after_confirmation_checks() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=3 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+    }
+    drop(storage);
+    let mut diverged_client = client.clone();
+    diverged_client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(3), H256::repeat_byte(0xff));
+
+    let (suspicion_sender, mut suspicion_receiver) = mpsc::unbounded_channel::<ReorgSuspicion>();
+    let mut detector = ReorgDetector {
+        event_handler: Box::new(suspicion_sender),
+        ..create_mock_detector(diverged_client.clone(), pool)
+    }
+    .with_confirmation_checks(2);
+    let expected_suspicion = ReorgSuspicion::UnconfirmedReorg {
+        diverged_l1_batch: L1BatchNumber(3),
+        observations: 1,
+        required_observations: 2,
+    };
+
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(outcome, CheckOutcome::Skipped);
+    assert_eq!(suspicion_receiver.try_recv().unwrap(), expected_suspicion);
+
+    // A passing check resets observations.
+    detector.client = Box::new(client);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(outcome, CheckOutcome::Consistent { .. });
+
+    detector.client = Box::new(diverged_client);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(outcome, CheckOutcome::Skipped);
+    assert_eq!(suspicion_receiver.try_recv().unwrap(), expected_suspicion);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(2),
+            ..
+        }
+    );
+    assert!(suspicion_receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn reorg_outcome_contains_diverged_miniblock_hashes() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let miniblock_hash = H256::from_low_u64_be(23);
+    for number in 1..=3 {
+        store_miniblock(&mut storage, number, miniblock_hash).await;
+    }
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    for number in 1..=2 {
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), miniblock_hash);
+    }
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(3), H256::repeat_byte(42));
+
+    let mut detector = create_mock_detector(client, pool);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(1),
+            divergence: Some(DivergenceKind::MiniblockHash {
+                miniblock: MiniblockNumber(3),
+                local: miniblock_hash,
+                remote: H256::repeat_byte(42),
+            }),
+        }
+    );
+}
+
+#[tokio::test]
+async fn sampling_catches_diverged_miniblock_within_checked_batch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let miniblock_hash = H256::from_low_u64_be(23);
+    for number in 1..=3 {
+        store_miniblock(&mut storage, number, miniblock_hash).await;
+    }
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    for number in 1..=3 {
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), miniblock_hash);
+    }
+    // Only a non-head miniblock in the checked L1 batch has diverged.
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(2), H256::repeat_byte(42));
+
+    let mut detector = create_mock_detector(client.clone(), pool.clone());
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(outcome, CheckOutcome::Consistent { .. });
+
+    let mut detector = create_mock_detector(client, pool).with_miniblock_sampling(3);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(0),
+            ..
+        }
+    );
+}
+
+#[tokio::test]
+async fn restart_without_rollback_after_reorg_is_detected() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    for number in 1..=2 {
+        store_miniblock(&mut storage, number, H256::zero()).await;
+        seal_l1_batch(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+    }
+
+    let create_client = || {
+        let mut client = MockMainNodeClient::default();
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(0), genesis_root_hash);
+        for number in 1..=2 {
+            client
+                .miniblock_hash_responses
+                .insert(MiniblockNumber(number), H256::zero());
+        }
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(1), H256::from_low_u64_be(1));
+        // ^ L1 batch #2 differs from that on the main node.
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(2), H256::repeat_byte(0xff));
+        client
+    };
+
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector =
+        create_mock_detector(create_client(), pool.clone()).with_reorg_persistence(true);
+    let last_correct_l1_batch = detector.run_inner(&mut stop_receiver).await.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(1)));
+
+    // Emulate restarting the node without performing a rollback.
+    let mut detector =
+        create_mock_detector(create_client(), pool.clone()).with_reorg_persistence(true);
+    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
+    assert_matches!(err, HashMatchError::UnresolvedPriorReorg(L1BatchNumber(2)));
+
+    // Perform the rollback; the persisted reorg info should be reset.
+    storage
+        .blocks_dal()
+        .delete_l1_batches(L1BatchNumber(1))
+        .await
+        .unwrap();
+    storage
+        .blocks_dal()
+        .delete_miniblocks(MiniblockNumber(1))
+        .await
+        .unwrap();
+    detector.check_prior_reorg().await.unwrap();
+    let diverged_l1_batch = storage
+        .reorg_detector_dal()
+        .get_diverged_l1_batch()
+        .await
+        .unwrap();
+    assert_eq!(diverged_l1_batch, None);
+}
+
+#[test]
+fn diverged_l1_batch_number_at_boundary() {
+    let diverged = ReorgDetector::diverged_l1_batch_number(L1BatchNumber(5), false).unwrap();
+    assert_eq!(diverged, L1BatchNumber(5));
+    let diverged = ReorgDetector::diverged_l1_batch_number(L1BatchNumber(5), true).unwrap();
+    assert_eq!(diverged, L1BatchNumber(6));
+
+    let diverged = ReorgDetector::diverged_l1_batch_number(L1BatchNumber(u32::MAX), false).unwrap();
+    assert_eq!(diverged, L1BatchNumber(u32::MAX));
+    let err = ReorgDetector::diverged_l1_batch_number(L1BatchNumber(u32::MAX), true).unwrap_err();
+    assert_matches!(err, HashMatchError::Internal(_));
+    assert!(!err.is_transient());
+}
+
+#[test]
+fn null_remote_miniblock_hash_is_treated_as_missing() {
+    let block = serde_json::json!({
+        "hash": null,
+        "parentHash": H256::repeat_byte(1),
+        "number": "0x2",
+        "transactions": [],
+    });
+    let response: Option<RemoteBlockHash> = serde_json::from_value(block).unwrap();
+    assert!(response.is_some());
+    assert_eq!(
+        RemoteBlockHash::into_hash(response, MiniblockNumber(2)),
+        None
+    );
+
+    let block = serde_json::json!({ "hash": H256::repeat_byte(2), "number": "0x2" });
+    let response: Option<RemoteBlockHash> = serde_json::from_value(block).unwrap();
+    assert_eq!(
+        RemoteBlockHash::into_hash(response, MiniblockNumber(2)),
+        Some(H256::repeat_byte(2))
+    );
+    let response: Option<RemoteBlockHash> =
+        serde_json::from_value(serde_json::Value::Null).unwrap();
+    assert_eq!(
+        RemoteBlockHash::into_hash(response, MiniblockNumber(2)),
+        None
+    );
+}
+
+#[test]
+fn parsing_remote_block_header_with_null_hash() {
+    let raw_response = r#"{
+        "hash": null,
+        "parentHash": "0x0101010101010101010101010101010101010101010101010101010101010101",
+        "number": "0x2",
+        "timestamp": "0x65",
+        "transactions": []
+    }"#;
+    let response: Option<RemoteBlockHeader> = serde_json::from_str(raw_response).unwrap();
+    let header = response.unwrap();
+    assert_eq!(header.parent_hash, H256::repeat_byte(1));
+    assert_eq!(
+        RemoteBlockHeader::into_pending_miniblock(Some(header)).unwrap(),
+        None
+    );
+
+    let raw_response = r#"{
+        "hash": "0x0202020202020202020202020202020202020202020202020202020202020202",
+        "parentHash": "0x0101010101010101010101010101010101010101010101010101010101010101",
+        "number": "0x2"
+    }"#;
+    let response: Option<RemoteBlockHeader> = serde_json::from_str(raw_response).unwrap();
+    assert_eq!(
+        RemoteBlockHeader::into_pending_miniblock(response).unwrap(),
+        Some((MiniblockNumber(2), H256::repeat_byte(2)))
+    );
+
+    let response: Option<RemoteBlockHeader> = serde_json::from_str("null").unwrap();
+    assert_eq!(
+        RemoteBlockHeader::into_pending_miniblock(response).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn displaying_truncated_hashes() {
+    let hash = H256::from_slice(&(0..32).collect::<Vec<u8>>());
+    let full = DisplayHash {
+        hash,
+        truncation: None,
+    };
+    assert_eq!(full.to_string(), format!("{hash:?}"));
+
+    let truncated = DisplayHash {
+        hash,
+        truncation: Some(4),
+    };
+    assert_eq!(truncated.to_string(), "0x00010203..1c1d1e1f");
+
+    let not_truncated = DisplayHash {
+        hash,
+        truncation: Some(16),
+    };
+    assert_eq!(not_truncated.to_string(), format!("{hash:?}"));
+}
+
+#[tokio::test]
+async fn hash_display_truncation_does_not_affect_comparison() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let local_hash = H256::zero();
+    store_miniblock(&mut storage, 1, local_hash).await;
+    seal_l1_batch(&mut storage, 1, local_hash).await;
+    drop(storage);
+
+    // The remote hash only differs from the local one in bytes hidden by truncation.
+    let mut remote_hash = local_hash;
+    remote_hash.0[16] = 1;
+    let detector = ReorgDetector {
+        hash_display_truncation: Some(8),
+        ..create_mock_detector(MockMainNodeClient::default(), pool)
+    };
+    assert_eq!(
+        detector.display_hash(local_hash).to_string(),
+        detector.display_hash(remote_hash).to_string()
+    );
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), remote_hash);
+    let detector = ReorgDetector {
+        client: Box::new(client),
+        ..detector
+    };
+    let output = detector.root_hashes_match(L1BatchNumber(1)).await.unwrap();
+    assert_matches!(output, MatchOutput::Mismatch(_));
+}
+
+#[tokio::test]
+async fn syncing_tip_is_skipped_in_catch_up_only_mode() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=3 {
+        let miniblock_hash = H256::from_low_u64_be(number.into());
+        let l1_batch_hash = H256::repeat_byte(number as u8);
+        store_miniblock(&mut storage, number, miniblock_hash).await;
+        seal_l1_batch(&mut storage, number, l1_batch_hash).await;
+
+        if number < 3 {
+            storage
+                .eth_sender_dal()
+                .insert_bogus_confirmed_eth_tx(
+                    L1BatchNumber(number),
+                    AggregatedActionType::Commit,
+                    H256::repeat_byte(0xcc),
+                    chrono::Utc::now(),
+                )
+                .await
+                .unwrap();
+            client
+                .miniblock_hash_responses
+                .insert(MiniblockNumber(number), miniblock_hash);
+            client
+                .l1_batch_root_hash_responses
+                .insert(L1BatchNumber(number), l1_batch_hash);
+        } else {
+            // The syncing tip diverges from the main node.
+            client
+                .miniblock_hash_responses
+                .insert(MiniblockNumber(number), H256::zero());
+            client
+                .l1_batch_root_hash_responses
+                .insert(L1BatchNumber(number), H256::zero());
+        }
+    }
+    drop(storage);
+
+    let (block_update_sender, mut block_update_receiver) =
+        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(block_update_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_catch_up_only(true);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    let (miniblock, l1_batch) = block_update_receiver.recv().await.unwrap();
+    assert_eq!(miniblock, MiniblockNumber(2));
+    assert_eq!(l1_batch, L1BatchNumber(2));
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StorageUpdateStrategy {
+    /// Prefill the local storage with all block data.
+    Prefill,
+    /// Sequentially add a new L1 batch after the previous one was checked.
+    Sequential,
+}
+
+impl StorageUpdateStrategy {
+    const ALL: [Self; 2] = [Self::Prefill, Self::Sequential];
+}
+
+#[test_casing(16, Product(([false, true], [2, 3, 5, 8], StorageUpdateStrategy::ALL)))]
+#[tokio::test]
+async fn reorg_is_detected_on_historic_batch_hash_mismatch(
+    snapshot_recovery: bool,
+    last_correct_batch: u32,
+    storage_update_strategy: StorageUpdateStrategy,
+) {
+    assert!(last_correct_batch < 10);
+    let (l1_batch_numbers, last_correct_batch) = if snapshot_recovery {
+        (11_u32..=20, last_correct_batch + 10)
+    } else {
+        (1_u32..=10, last_correct_batch)
+    };
+
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .protocol_versions_dal()
+        .save_protocol_version_with_tx(ProtocolVersion::default())
+        .await;
+    let earliest_l1_batch_number = l1_batch_numbers.start() - 1;
+    store_miniblock(&mut storage, earliest_l1_batch_number, H256::zero()).await;
+    seal_l1_batch(&mut storage, earliest_l1_batch_number, H256::zero()).await;
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(earliest_l1_batch_number), H256::zero());
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(earliest_l1_batch_number), H256::zero());
+
+    let miniblock_and_l1_batch_hashes = l1_batch_numbers.clone().map(|number| {
+        let mut miniblock_hash = H256::from_low_u64_be(number.into());
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), miniblock_hash);
+        let mut l1_batch_hash = H256::repeat_byte(number as u8);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), l1_batch_hash);
+
+        if number > last_correct_batch {
+            miniblock_hash = H256::zero();
+            l1_batch_hash = H256::zero();
+        }
+        (number, miniblock_hash, l1_batch_hash)
+    });
+    let mut miniblock_and_l1_batch_hashes: Vec<_> = miniblock_and_l1_batch_hashes.collect();
+
+    if matches!(storage_update_strategy, StorageUpdateStrategy::Prefill) {
+        for &(number, miniblock_hash, l1_batch_hash) in &miniblock_and_l1_batch_hashes {
+            store_miniblock(&mut storage, number, miniblock_hash).await;
+            seal_l1_batch(&mut storage, number, l1_batch_hash).await;
+        }
+    }
+
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let (block_update_sender, mut block_update_receiver) =
+        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(block_update_sender),
+        ..create_mock_detector(client, pool.clone())
+    };
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    if matches!(storage_update_strategy, StorageUpdateStrategy::Sequential) {
+        let mut last_number = earliest_l1_batch_number;
+        while let Some((miniblock, l1_batch)) = block_update_receiver.recv().await {
+            if miniblock == MiniblockNumber(last_number) && l1_batch == L1BatchNumber(last_number) {
+                let (number, miniblock_hash, l1_batch_hash) =
+                    miniblock_and_l1_batch_hashes.remove(0);
+                assert_eq!(number, last_number + 1);
+                store_miniblock(&mut storage, number, miniblock_hash).await;
+                seal_l1_batch(&mut storage, number, l1_batch_hash).await;
+                last_number = number;
+            }
+        }
+    }
+
+    let task_result = detector_task.await.unwrap();
+    let last_correct_l1_batch = task_result.unwrap();
+    assert_eq!(
+        last_correct_l1_batch,
+        Some(L1BatchNumber(last_correct_batch))
+    );
+}
+
+#[tokio::test]
+async fn stopping_reorg_detector_while_waiting_for_l1_batch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    assert!(storage.blocks_dal().is_genesis_needed().await.unwrap());
+    drop(storage);
+
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector = create_mock_detector(MockMainNodeClient::default(), pool);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    stop_sender.send_replace(true);
+
+    let task_result = detector_task.await.unwrap();
+    let last_correct_l1_batch = task_result.unwrap();
+    assert_eq!(last_correct_l1_batch, None);
+}
+
+#[tokio::test]
+async fn detector_errors_on_earliest_batch_hash_mismatch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    assert_ne!(genesis_root_hash, H256::zero());
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), H256::zero());
+
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector = create_mock_detector(client, pool.clone());
+
+    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
+    assert_matches!(err, HashMatchError::EarliestHashMismatch(L1BatchNumber(0)));
+}
+
+#[tokio::test]
+async fn detector_errors_on_fork_id_mismatch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+
+    let mut client = MockMainNodeClient {
+        fork_id_response: Some(H256::repeat_byte(2)),
+        ..MockMainNodeClient::default()
+    };
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector =
+        create_mock_detector(client, pool.clone()).with_expected_fork_id(H256::repeat_byte(1));
+
+    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
+    assert_matches!(
+        err,
+        HashMatchError::ForkMismatch { expected, actual }
+            if expected == H256::repeat_byte(1) && actual == Some(H256::repeat_byte(2))
+    );
+}
+
+#[derive(Debug)]
+struct CustomMessageFormatter;
+
+impl MessageFormatter for CustomMessageFormatter {
+    fn format_error(&self, err: &HashMatchError) -> String {
+        match err {
+            HashMatchError::EarliestHashMismatch(number) => {
+                format!("Erster L1-Batch #{number} stimmt nicht mit dem Hauptknoten überein")
+            }
+            _ => err.to_string(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn earliest_batch_hash_mismatch_with_custom_message_formatter() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), H256::zero());
+
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let detector =
+        create_mock_detector(client, pool.clone()).with_message_formatter(CustomMessageFormatter);
+
+    let err = detector.run(stop_receiver).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Erster L1-Batch #0 stimmt nicht mit dem Hauptknoten überein"
+    );
+    let err = err.downcast_ref::<HashMatchError>().unwrap();
+    assert_matches!(err, HashMatchError::EarliestHashMismatch(L1BatchNumber(0)));
+}
+
+#[tokio::test]
+async fn detector_errors_on_earliest_batch_hash_mismatch_with_snapshot_recovery() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(3), H256::zero());
+
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector = create_mock_detector(client, pool.clone());
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .protocol_versions_dal()
+            .save_protocol_version_with_tx(ProtocolVersion::default())
+            .await;
+        store_miniblock(&mut storage, 3, H256::from_low_u64_be(3)).await;
+        seal_l1_batch(&mut storage, 3, H256::from_low_u64_be(3)).await;
+    });
+
+    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
+    assert_matches!(err, HashMatchError::EarliestHashMismatch(L1BatchNumber(3)));
+}
+
+#[tokio::test]
+async fn check_is_skipped_with_miniblock_but_no_l1_batch() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .protocol_versions_dal()
+        .save_protocol_version_with_tx(ProtocolVersion::default())
+        .await;
+    store_miniblock(&mut storage, 3, H256::from_low_u64_be(3)).await;
+    drop(storage);
+
+    let mut detector = create_mock_detector(MockMainNodeClient::default(), pool);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(outcome, CheckOutcome::Skipped);
+    let outcome = detector.check_consistency_uncached().await.unwrap();
+    assert_eq!(outcome, CheckOutcome::Skipped);
+}
+
+#[tokio::test]
+async fn check_is_skipped_with_l1_batch_but_no_miniblocks() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .protocol_versions_dal()
+        .save_protocol_version_with_tx(ProtocolVersion::default())
+        .await;
+    // Emulate snapshot recovery in progress: the L1 batch is restored, but its miniblocks are not yet.
+    seal_l1_batch(&mut storage, 3, H256::from_low_u64_be(3)).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(3), H256::from_low_u64_be(3));
+    let mut detector = create_mock_detector(client.clone(), pool.clone());
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(outcome, CheckOutcome::Skipped);
+
+    // The detector should wait rather than error.
+    let detector = create_mock_detector(client, pool);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!detector_task.is_finished());
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn detector_errors_on_local_batch_gap() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::zero()).await;
+    seal_l1_batch(&mut storage, 1, H256::zero()).await;
+    // Plant a gap: L1 batch #2 has no metadata, while L1 batch #3 has.
+    store_miniblock(&mut storage, 2, H256::zero()).await;
+    storage
+        .blocks_dal()
+        .insert_mock_l1_batch(&create_l1_batch(2))
+        .await
+        .unwrap();
+    storage
+        .blocks_dal()
+        .mark_miniblocks_as_executed_in_l1_batch(L1BatchNumber(2))
+        .await
+        .unwrap();
+    store_miniblock(&mut storage, 3, H256::zero()).await;
+    seal_l1_batch(&mut storage, 3, H256::zero()).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=3 {
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::zero());
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::zero());
+    }
+
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector = create_mock_detector(client, pool);
+
+    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
+    assert_matches!(
+        err,
+        HashMatchError::LocalBatchGap(L1BatchNumber(1), L1BatchNumber(3))
+    );
+}
+
+#[test_casing(3, [None, Some(0), Some(3)])]
+#[tokio::test]
+async fn forensic_scan_has_no_side_effects(last_correct_l1_batch: Option<u32>) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    for number in 1..=5 {
+        store_miniblock(&mut storage, number, H256::zero()).await;
+        seal_l1_batch(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+    }
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=6 {
+        let hash = match last_correct_l1_batch {
+            Some(last_correct) if number > last_correct => H256::repeat_byte(0xff),
+            _ => H256::from_low_u64_be(number.into()),
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), hash);
+    }
+    client.latest_l1_batch_response = Some(L1BatchNumber(6));
+
+    let detector = create_mock_detector(client, pool.clone());
+    let report = detector.forensic_scan().await.unwrap();
+    let expected_report = DivergenceReport {
+        earliest_l1_batch: L1BatchNumber(0),
+        checked_l1_batch: L1BatchNumber(5),
+        last_correct_l1_batch: Some(L1BatchNumber(last_correct_l1_batch.unwrap_or(5))),
+        first_diverged_l1_batch: last_correct_l1_batch.map(|number| L1BatchNumber(number + 1)),
+        diverged_hash: last_correct_l1_batch.map(|_| DivergedHash::RootHash),
+    };
+    assert_eq!(report, expected_report);
+    let divergence = detector.find_divergence().await.unwrap();
+    assert_eq!(divergence, last_correct_l1_batch.map(L1BatchNumber));
+
+    // Health must not be touched by the scan, and the local DB must be left intact.
+    let health = detector.health_check().check_health().await;
+    assert_matches!(health.status(), HealthStatus::NotReady);
+    let mut storage = pool.access_storage().await.unwrap();
+    let sealed_l1_batch = storage
+        .blocks_dal()
+        .get_last_l1_batch_number_with_metadata()
+        .await
+        .unwrap();
+    assert_eq!(sealed_l1_batch, Some(L1BatchNumber(5)));
+}
+
+#[tokio::test]
+async fn inconsistent_remote_hashes_are_classified_as_caching_artifact() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let miniblock_hash = H256::from_low_u64_be(1);
+    store_miniblock(&mut storage, 1, miniblock_hash).await;
+    seal_l1_batch(&mut storage, 1, H256::zero()).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::zero());
+    // The main node returns a stale hash for miniblock #1, which is inconsistent
+    // with the parent hash of miniblock #2 it reports.
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::repeat_byte(0xff));
+    client
+        .miniblock_parent_hash_responses
+        .insert(MiniblockNumber(2), miniblock_hash);
+
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector = create_mock_detector(client, pool);
+    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
+    assert_matches!(
+        err,
+        HashMatchError::RemoteCachingArtifact(MiniblockNumber(1))
+    );
+    assert!(err.is_transient());
+}
+
+#[tokio::test]
+async fn all_diverged_fields_are_reported() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::zero()).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    let mut remote_details = storage
+        .blocks_web3_dal()
+        .get_l1_batch_details(L1BatchNumber(1))
+        .await
+        .unwrap()
+        .unwrap();
+    drop(storage);
+    remote_details.base.timestamp += 1;
+    remote_details.base.l2_tx_count += 1;
+    remote_details.base.l1_gas_price += 1;
+    remote_details.base.execute_tx_hash = Some(H256::repeat_byte(0xee));
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::zero());
+    client
+        .l1_batch_details_responses
+        .insert(L1BatchNumber(1), remote_details);
+
+    let (suspicion_sender, mut suspicion_receiver) = mpsc::unbounded_channel::<ReorgSuspicion>();
+    let mut detector = ReorgDetector {
+        event_handler: Box::new(suspicion_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_field_comparison(FieldSet::all() - FieldSet::PROVE_TX_HASH);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(outcome, CheckOutcome::Consistent { .. });
+
+    let suspicion = suspicion_receiver.try_recv().unwrap();
+    assert_eq!(
+        suspicion,
+        ReorgSuspicion::FieldDivergence(FieldDivergence {
+            l1_batch: L1BatchNumber(1),
+            fields: FieldSet::TIMESTAMP
+                | FieldSet::TX_COUNTS
+                | FieldSet::GAS_PRICES
+                | FieldSet::EXECUTE_TX_HASH,
+        })
+    );
+}
+
+#[tokio::test]
+async fn comparisons_are_performed_at_configured_cadence() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::zero()).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    let remote_details = storage
+        .blocks_web3_dal()
+        .get_l1_batch_details(L1BatchNumber(1))
+        .await
+        .unwrap()
+        .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::zero());
+    client
+        .l1_batch_details_responses
+        .insert(L1BatchNumber(1), remote_details);
+    let requested_methods = client.requested_methods.clone();
+
+    let cadence = ComparisonCadence {
+        l1_batch_root_hash: 2,
+        miniblock_hash: 3,
+        extended_fields: 4,
+    };
+    let mut detector = create_mock_detector(client, pool)
+        .with_field_comparison(FieldSet::all())
+        .with_comparison_cadence(cadence);
+    for iteration in 0..8 {
+        let outcome = detector.check_consistency_uncached().await.unwrap();
+        assert_eq!(
+            outcome,
+            CheckOutcome::Consistent {
+                l1_batch: L1BatchNumber(1),
+                miniblock: MiniblockNumber(1),
+            }
+        );
+
+        let requested_methods = std::mem::take(&mut *requested_methods.lock().unwrap());
+        let count = |method| {
+            requested_methods
+                .iter()
+                .filter(|&&name| name == method)
+                .count()
+        };
+        let expected_root_hash_requests = usize::from(iteration % 2 == 0);
+        assert_eq!(
+            count("l1_batch_root_hash"),
+            expected_root_hash_requests,
+            "{requested_methods:?}"
+        );
+        let expected_miniblock_requests = usize::from(iteration % 3 == 0);
+        assert_eq!(
+            count("miniblock_hash"),
+            expected_miniblock_requests,
+            "{requested_methods:?}"
+        );
+        let expected_details_requests = usize::from(iteration % 4 == 0);
+        assert_eq!(
+            count("l1_batch_details"),
+            expected_details_requests,
+            "{requested_methods:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn rpc_trace_captures_latest_responses() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::repeat_byte(1)).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    store_miniblock(&mut storage, 2, H256::repeat_byte(2)).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient {
+        latest_miniblock_response: Some(MiniblockNumber(1)),
+        ..MockMainNodeClient::default()
+    };
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::repeat_byte(1));
+
+    let mut detector = create_mock_detector(client.clone(), pool.clone());
+    detector.check_consistency().await.unwrap();
+    assert!(detector.recent_rpc_trace().is_empty());
+
+    let mut detector = create_mock_detector(client, pool).with_rpc_trace(3);
+    detector.check_consistency().await.unwrap();
+    let responses: Vec<_> = detector
+        .recent_rpc_trace()
+        .into_iter()
+        .map(|entry| entry.response)
+        .collect();
+    // The main node doesn't have miniblock #2, so the detector should fetch its latest miniblock.
+    assert_eq!(
+        responses,
+        [
+            RpcTraceResponse::MiniblockHash {
+                number: MiniblockNumber(2),
+                hash: None,
+            },
+            RpcTraceResponse::SealedMiniblockNumber(MiniblockNumber(1)),
+            RpcTraceResponse::MiniblockHash {
+                number: MiniblockNumber(1),
+                hash: Some(H256::repeat_byte(1)),
+            },
+        ]
+    );
+
+    detector.check_consistency_uncached().await.unwrap();
+    let trace = detector.recent_rpc_trace();
+    assert_eq!(trace.len(), 3);
+    assert_eq!(
+        trace[2].response,
+        RpcTraceResponse::MiniblockHash {
+            number: MiniblockNumber(1),
+            hash: Some(H256::repeat_byte(1)),
+        }
+    );
+    assert!(trace
+        .windows(2)
+        .all(|window| window[0].timestamp <= window[1].timestamp));
+}
+
+#[tokio::test]
+async fn incoherent_main_node_namespaces_are_detected() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient {
+        latest_l1_batch_response: Some(L1BatchNumber(1)),
+        // The `eth` namespace lags behind the `zks` namespace.
+        latest_miniblock_response: Some(MiniblockNumber(1)),
+        ..MockMainNodeClient::default()
+    };
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .miniblock_range_responses
+        .insert(L1BatchNumber(1), (MiniblockNumber(1), MiniblockNumber(2)));
+
+    let mut detector =
+        create_mock_detector(client.clone(), pool.clone()).with_namespace_coherence_check(true);
+    let err = detector.check_consistency().await.unwrap_err();
+    assert_matches!(
+        &err,
+        Error::HashMatch(HashMatchError::IncoherentNamespaces {
+            l1_batch: L1BatchNumber(1),
+            miniblock: MiniblockNumber(1),
+        })
+    );
+    assert!(err.is_transient());
+
+    client.latest_miniblock_response = Some(MiniblockNumber(2));
+    let mut detector = create_mock_detector(client, pool).with_namespace_coherence_check(true);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(
+        outcome,
+        CheckOutcome::Consistent {
+            l1_batch: L1BatchNumber(0),
+            miniblock: MiniblockNumber(0),
+        }
+    );
+}
+
+#[tokio::test]
+async fn forensic_scan_respects_max_verify_age() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    // The main node has pruned all L1 batches before #7.
+    let mut client = MockMainNodeClient {
+        latest_l1_batch_response: Some(L1BatchNumber(10)),
+        ..MockMainNodeClient::default()
+    };
+    for number in 1..=10 {
+        let hash = H256::from_low_u64_be(number.into());
+        seal_l1_batch(&mut storage, number, hash).await;
+        if number >= 7 {
+            client
+                .l1_batch_root_hash_responses
+                .insert(L1BatchNumber(number), hash);
+        }
+    }
+    drop(storage);
+
+    let detector = create_mock_detector(client.clone(), pool.clone());
+    let err = detector.forensic_scan().await.unwrap_err();
+    let err = err.downcast::<HashMatchError>().unwrap();
+    assert_matches!(
+        err,
+        HashMatchError::EarliestL1BatchTruncated(L1BatchNumber(0))
+    );
+
+    let detector = create_mock_detector(client, pool).with_max_verify_age(3);
+    let report = detector.forensic_scan().await.unwrap();
+    assert_eq!(
+        report,
+        DivergenceReport {
+            earliest_l1_batch: L1BatchNumber(7),
+            checked_l1_batch: L1BatchNumber(10),
+            last_correct_l1_batch: Some(L1BatchNumber(10)),
+            first_diverged_l1_batch: None,
+            diverged_hash: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn watchdog_aborts_hanging_check() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    *client.hanging_miniblock_hash_requests.lock().unwrap() = 1;
+
+    let sink = CapturingMetricsSink::default();
+    let metric_calls = sink.0.clone();
+    let (block_update_sender, mut block_update_receiver) =
+        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(block_update_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_watchdog(Duration::from_millis(50))
+    .with_metrics_sink(sink);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    // The first check hangs and should be aborted; the following one should succeed.
+    let (miniblock, l1_batch) = block_update_receiver.recv().await.unwrap();
+    assert_eq!(
+        (miniblock, l1_batch),
+        (MiniblockNumber(0), L1BatchNumber(0))
+    );
+    let watchdog_timeouts = metric_calls
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|&&call| call == MetricCall::Counter(DetectorCounter::WatchdogTimeouts))
+        .count();
+    assert_eq!(watchdog_timeouts, 1);
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
+}
+
+#[tokio::test]
+async fn reorg_localization_longer_than_watchdog_timeout_completes() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient {
+        l1_batch_root_hash_latency: Duration::from_millis(30),
+        ..MockMainNodeClient::default()
+    };
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=100 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        store_miniblock(&mut storage, number, local_hash).await;
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), local_hash);
+        let remote_hash = if number < 60 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let sink = CapturingMetricsSink::default();
+    let metric_calls = sink.0.clone();
+    // Localization requires more RPC round trips than fit into a single watchdog period.
+    let detector = create_mock_detector(client, pool)
+        .with_watchdog(Duration::from_millis(100))
+        .with_metrics_sink(sink);
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let last_correct_l1_batch =
+        tokio::time::timeout(Duration::from_secs(10), detector.run(stop_receiver))
+            .await
+            .expect("reorg localization hasn't completed")
+            .unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(59)));
+
+    let watchdog_timeouts = metric_calls
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|&&call| call == MetricCall::Counter(DetectorCounter::WatchdogTimeouts))
+        .count();
+    assert!(watchdog_timeouts > 0);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetricCall {
+    Gauge(DetectorGauge, u64),
+    Histogram(DetectorHistogram),
+    CountHistogram(DetectorCountHistogram, usize),
+    Counter(DetectorCounter),
+}
+
+#[derive(Debug, Default)]
+struct CapturingMetricsSink(Arc<Mutex<Vec<MetricCall>>>);
+
+impl MetricsSink for CapturingMetricsSink {
+    fn record_gauge(&self, gauge: DetectorGauge, value: u64) {
+        self.0.lock().unwrap().push(MetricCall::Gauge(gauge, value));
+    }
+
+    fn record_histogram(&self, histogram: DetectorHistogram, _value: Duration) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(MetricCall::Histogram(histogram));
+    }
+
+    fn record_count_histogram(&self, histogram: DetectorCountHistogram, value: usize) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(MetricCall::CountHistogram(histogram, value));
+    }
+
+    fn increment_counter(&self, counter: DetectorCounter) {
+        self.0.lock().unwrap().push(MetricCall::Counter(counter));
+    }
+}
+
+#[tokio::test]
+async fn metrics_are_emitted_via_sink() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=2 {
+        store_miniblock(&mut storage, number, H256::zero()).await;
+        seal_l1_batch(&mut storage, number, H256::zero()).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::zero());
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::zero());
+    }
+    drop(storage);
+
+    let sink = CapturingMetricsSink::default();
+    let calls = sink.0.clone();
+    let mut detector = create_mock_detector(client, pool).with_metrics_sink(sink);
+    detector.check_consistency().await.unwrap();
+
+    let calls = calls.lock().unwrap().clone();
+    assert_eq!(
+        calls,
+        [
+            MetricCall::Histogram(DetectorHistogram::RpcLatency {
+                method: "l1_batch_root_hash"
+            }),
+            MetricCall::Gauge(DetectorGauge::MaxVerifiedL1Batch, 2),
+            MetricCall::Histogram(DetectorHistogram::RpcLatency {
+                method: "miniblock_hash"
+            }),
+            MetricCall::Gauge(DetectorGauge::LastCorrectMiniblock, 2),
+            MetricCall::Gauge(DetectorGauge::LastCorrectL1Batch, 2),
+            MetricCall::Histogram(DetectorHistogram::CheckLatency),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reorg_is_reported_via_sink_and_callback() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=4 {
+        store_miniblock(&mut storage, number, H256::zero()).await;
+        seal_l1_batch(&mut storage, number, H256::zero()).await;
+        let remote_hash = if number <= 2 {
+            H256::zero()
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let sink = CapturingMetricsSink::default();
+    let calls = sink.0.clone();
+    let reported_reorgs = Arc::<Mutex<Vec<_>>>::default();
+    let reported_reorgs_for_callback = reported_reorgs.clone();
+    let mut detector = create_mock_detector(client, pool)
+        .with_metrics_sink(sink)
+        .with_reorg_callback(move |last_correct_l1_batch| {
+            reported_reorgs_for_callback
+                .lock()
+                .unwrap()
+                .push(last_correct_l1_batch);
+        });
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(2),
+            ..
+        }
+    );
+    assert_eq!(*reported_reorgs.lock().unwrap(), [L1BatchNumber(2)]);
+
+    let calls = calls.lock().unwrap();
+    assert!(
+        calls.contains(&MetricCall::Gauge(DetectorGauge::ReorgDepth, 2)),
+        "{calls:?}"
+    );
+    let probe_counts: Vec<_> = calls
+        .iter()
+        .filter_map(|call| match call {
+            MetricCall::CountHistogram(DetectorCountHistogram::LocalizationProbes, count) => {
+                Some(*count)
+            }
+            _ => None,
+        })
+        .collect();
+    // Binary search over (0, 4) probes L1 batches #2 and #3.
+    assert_eq!(probe_counts, [2]);
+}
+
+#[tokio::test]
+async fn missing_remote_l1_batches_are_counted() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    drop(storage);
+
+    let sink = CapturingMetricsSink::default();
+    let calls = sink.0.clone();
+    let detector =
+        create_mock_detector(MockMainNodeClient::default(), pool).with_metrics_sink(sink);
+    let output = detector.root_hashes_match(L1BatchNumber(1)).await.unwrap();
+    assert_matches!(output, MatchOutput::NoRemoteReference);
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            MetricCall::Histogram(DetectorHistogram::RpcLatency {
+                method: "l1_batch_root_hash"
+            }),
+            MetricCall::Counter(DetectorCounter::MissingRemoteL1Batches),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn uncached_check_bypasses_continuity_cache() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=3 {
+        store_miniblock(&mut storage, number, H256::zero()).await;
+        seal_l1_batch(&mut storage, number, H256::zero()).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::zero());
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::zero());
+    }
+
+    let mut detector = create_mock_detector(client, pool.clone());
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(
+        outcome,
+        CheckOutcome::Consistent {
+            l1_batch: L1BatchNumber(3),
+            miniblock: MiniblockNumber(3),
+        }
+    );
+
+    // Plant a gap below the already checked L1 batches: re-insert L1 batch #2 without metadata.
+    storage
+        .blocks_dal()
+        .delete_l1_batches(L1BatchNumber(1))
+        .await
+        .unwrap();
+    storage
+        .blocks_dal()
+        .delete_miniblocks(MiniblockNumber(1))
+        .await
+        .unwrap();
+    store_miniblock(&mut storage, 2, H256::zero()).await;
+    storage
+        .blocks_dal()
+        .insert_mock_l1_batch(&create_l1_batch(2))
+        .await
+        .unwrap();
+    storage
+        .blocks_dal()
+        .mark_miniblocks_as_executed_in_l1_batch(L1BatchNumber(2))
+        .await
+        .unwrap();
+    store_miniblock(&mut storage, 3, H256::zero()).await;
+    seal_l1_batch(&mut storage, 3, H256::zero()).await;
+    drop(storage);
+
+    // The cached check only verifies continuity for new L1 batches, so it doesn't notice the gap.
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(outcome, CheckOutcome::Consistent { .. });
+    let err = detector.check_consistency_uncached().await.unwrap_err();
+    assert_matches!(
+        err,
+        Error::HashMatch(HashMatchError::LocalBatchGap(
+            L1BatchNumber(1),
+            L1BatchNumber(3)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn reorg_is_detected_without_waiting_for_main_node_to_catch_up() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    // Fill in local storage with some data, so that it's ahead of the main node.
+    for number in 1..5 {
+        store_miniblock(&mut storage, number, H256::zero()).await;
+        seal_l1_batch(&mut storage, number, H256::zero()).await;
+    }
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..3 {
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::zero());
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::zero());
+    }
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(3), H256::zero());
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(3), H256::repeat_byte(0xff));
+    client.latest_l1_batch_response = Some(L1BatchNumber(3));
+    client.latest_miniblock_response = Some(MiniblockNumber(3));
+
+    let (_stop_sender, stop_receiver) = watch::channel(false);
+    let detector = create_mock_detector(client, pool);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    let task_result = detector_task.await.unwrap();
+    let last_correct_l1_batch = task_result.unwrap();
+    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(2)));
+}
+
+#[tokio::test]
+async fn max_verified_l1_batch_advances() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client.latest_l1_batch_response = Some(L1BatchNumber(4));
+    client.latest_miniblock_response = Some(MiniblockNumber(4));
+    for number in 1..=4 {
+        client.miniblock_hash_responses.insert(
+            MiniblockNumber(number),
+            H256::from_low_u64_be(number.into()),
+        );
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::from_low_u64_be(number.into()));
+    }
+    for number in 1..=2 {
+        store_miniblock(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+        seal_l1_batch(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+    }
+
+    let mut detector = create_mock_detector(client, pool.clone());
+    let max_verified_l1_batch = |detector: &ReorgDetector| {
+        detector
+            .max_verified_l1_batch_number
+            .load(Ordering::Relaxed)
+    };
+    detector.check_consistency().await.unwrap();
+    assert_eq!(max_verified_l1_batch(&detector), 2);
+
+    for number in 3..=4 {
+        store_miniblock(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+        seal_l1_batch(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+    }
+    detector.check_consistency().await.unwrap();
+    assert_eq!(max_verified_l1_batch(&detector), 4);
+
+    // Verifying an earlier L1 batch must not decrease the value.
+    detector.forensic_scan().await.unwrap();
+    assert_eq!(max_verified_l1_batch(&detector), 4);
+}
+
+#[derive(Debug)]
+struct MockAnchor(HashMap<L1BatchNumber, H256>);
+
+#[async_trait]
+impl L1BatchAnchor for MockAnchor {
+    async fn l1_batch_root_hash(&self, number: L1BatchNumber) -> anyhow::Result<Option<H256>> {
+        Ok(self.0.get(&number).copied())
+    }
+}
+
+#[tokio::test]
+async fn consensus_with_anchor_mismatch_is_fatal() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::repeat_byte(1)).await;
+    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::repeat_byte(1));
+    let anchor = MockAnchor(HashMap::from([(L1BatchNumber(1), H256::repeat_byte(0xff))]));
+
+    let mut detector = create_mock_detector(client, pool).with_anchor(anchor);
+    let err = detector.check_consistency().await.unwrap_err();
+    assert_matches!(
+        err,
+        Error::HashMatch(HashMatchError::ConsensusWithAnchorMismatch(L1BatchNumber(
+            1
+        )))
+    );
+    assert!(!err.is_transient());
+}
+
+#[tokio::test]
+async fn storage_reacquisition_during_localization_is_retried() {
+    let pool = ConnectionPool::constrained_test_pool(1).await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+
+    let sink = CapturingMetricsSink::default();
+    let metric_calls = sink.0.clone();
+    let detector = create_mock_detector(client, pool.clone()).with_metrics_sink(sink);
+    // The test holds the only DB connection for a while, so that reacquiring it during localization fails.
+    let release_storage = async move {
+        tokio::time::sleep(Duration::from_millis(1_200)).await;
+        drop(storage);
+    };
+    let (last_correct_l1_batch, ()) = future::join(
+        detector.detect_reorg(L1BatchNumber(0), L1BatchNumber(10)),
+        release_storage,
+    )
+    .await;
+    assert_eq!(last_correct_l1_batch.unwrap(), L1BatchNumber(6));
+
+    let reacquisition_failures = metric_calls
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|&call| *call == MetricCall::Counter(DetectorCounter::StorageReacquisitionFailures))
+        .count();
+    assert!(reacquisition_failures > 0);
+}
+
+#[tokio::test]
+async fn root_hashes_are_prefetched_during_localization() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let requested_methods = client.requested_methods.clone();
+    let detector = create_mock_detector(client, pool);
+    let last_correct_l1_batch = detector
+        .detect_reorg(L1BatchNumber(0), L1BatchNumber(10))
+        .await
+        .unwrap();
+    assert_eq!(last_correct_l1_batch, L1BatchNumber(6));
+    // All root hashes within the search bounds should be fetched using a single request. The root hash
+    // of the first diverged L1 batch is then re-requested to verify the localization result.
+    assert_eq!(
+        *requested_methods.lock().unwrap(),
+        ["l1_batch_root_hashes", "l1_batch_root_hash"]
+    );
+}
+
+#[tokio::test]
+async fn search_bounds_are_not_requested_during_localization() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=10 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        store_miniblock(&mut storage, number, local_hash).await;
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 6 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    let mut detector = create_mock_detector(client, pool).with_rpc_trace(100);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(6),
+            ..
+        }
+    );
+    let requested_l1_batches: Vec<_> = detector
+        .recent_rpc_trace()
+        .into_iter()
+        .filter_map(|entry| match entry.response {
+            RpcTraceResponse::L1BatchRootHash { number, .. } => Some(number.0),
+            _ => None,
+        })
+        .collect();
+    // The diverged L1 batch is known from the head check, so localization shouldn't re-request it.
+    let diverged_requests = requested_l1_batches
+        .iter()
+        .filter(|&&number| number == 10)
+        .count();
+    assert_eq!(diverged_requests, 1, "{requested_l1_batches:?}");
+}
+
+#[tokio::test]
+async fn root_hashes_of_finalized_l1_batches_are_cached() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+    let mut client = MockMainNodeClient::default();
+    for number in 1..=2 {
+        let root_hash = H256::repeat_byte(number as u8);
+        seal_l1_batch(&mut storage, number, root_hash).await;
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), root_hash);
+    }
+    // Only L1 batch #1 is finalized.
+    storage
+        .eth_sender_dal()
+        .insert_bogus_confirmed_eth_tx(
+            L1BatchNumber(1),
+            AggregatedActionType::Execute,
+            H256::repeat_byte(0xee),
+            chrono::Utc::now(),
+        )
+        .await
+        .unwrap();
+    drop(storage);
+
+    let requested_methods = client.requested_methods.clone();
+    let detector = ReorgDetector {
+        root_hash_cache: Some(Mutex::new(LruCache::new(NonZeroUsize::new(4).unwrap()))),
+        ..create_mock_detector(client, pool)
+    };
+    for _ in 0..2 {
+        for number in 1..=2 {
+            let output = detector
+                .root_hashes_match(L1BatchNumber(number))
+                .await
+                .unwrap();
+            assert_matches!(output, MatchOutput::Match);
+        }
+    }
+    assert_eq!(
+        *requested_methods.lock().unwrap(),
+        [
+            "l1_batch_root_hash",
+            "l1_batch_root_hash",
+            "l1_batch_root_hash"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn configuring_detector_via_builder() {
+    let pool = ConnectionPool::test_pool().await;
+    let http_client = HttpClientBuilder::default()
+        .build("http://127.0.0.1:3050")
+        .unwrap();
+
+    let detector = ReorgDetector::builder(http_client.clone(), pool.clone())
+        .sleep_interval(Duration::from_millis(10))
+        .health_check_name("custom_reorg_detector")
+        .build()
+        .unwrap();
+    assert_eq!(detector.sleep_interval, Duration::from_millis(10));
+    assert_eq!(detector.health_check().name(), "custom_reorg_detector");
+    assert!(detector.rpc_rate_limiter.is_none());
+    assert_eq!(detector.rpc_retries.max_retries, 0);
+
+    let detector = ReorgDetector::builder(http_client.clone(), pool.clone())
+        .rpc_rate_limit(NonZeroU32::new(10).unwrap())
+        .rpc_retries(3, Duration::from_millis(100))
+        .build()
+        .unwrap();
+    assert!(detector.rpc_rate_limiter.is_some());
+    assert_eq!(detector.rpc_retries.max_retries, 3);
+    assert_eq!(detector.rpc_retries.backoff, Duration::from_millis(100));
+
+    let detector = ReorgDetector::new(http_client.clone(), pool.clone());
+    assert_eq!(
+        detector.sleep_interval,
+        ReorgDetector::DEFAULT_SLEEP_INTERVAL
+    );
+    assert_eq!(detector.health_check().name(), "reorg_detector");
+
+    let err = ReorgDetector::builder(http_client, pool)
+        .sleep_interval(Duration::ZERO)
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("sleep interval"), "{err}");
+}
+
+#[tokio::test]
+async fn configuring_detector_via_setters() {
+    let pool = ConnectionPool::test_pool().await;
+    let detector = create_mock_detector(MockMainNodeClient::default(), pool)
+        .with_retry_backoff(Duration::from_millis(100), Duration::from_secs(1))
+        .with_cache_capacity(16)
+        .with_rpc_timeout(Duration::from_secs(5))
+        .with_rpc_rate_limit(NonZeroU32::new(10).unwrap())
+        .with_rpc_retries(3, Duration::from_millis(100));
+    assert_eq!(detector.min_retry_backoff, Some(Duration::from_millis(100)));
+    assert_eq!(detector.max_retry_backoff, Duration::from_secs(1));
+    assert!(detector.root_hash_cache.is_some());
+    assert_eq!(detector.rpc_timeout, Duration::from_secs(5));
+    assert!(detector.rpc_rate_limiter.is_some());
+    assert_eq!(detector.rpc_retries.max_retries, 3);
+    assert_eq!(detector.rpc_retries.backoff, Duration::from_millis(100));
+}
+
+#[tokio::test]
+#[should_panic(expected = "must not exceed max retry backoff")]
+async fn setting_invalid_retry_backoff() {
+    let pool = ConnectionPool::test_pool().await;
+    create_mock_detector(MockMainNodeClient::default(), pool)
+        .with_retry_backoff(Duration::from_secs(2), Duration::from_secs(1));
+}
+
+#[derive(Debug, Default)]
+struct RecordingEventHandler(Arc<Mutex<Vec<&'static str>>>);
+
+#[async_trait]
+impl HandleReorgDetectorEvent for RecordingEventHandler {
+    async fn initialize(&mut self) {
+        self.0.lock().unwrap().push("initialize");
+    }
+
+    async fn update_correct_block(&mut self, _: MiniblockNumber, _: L1BatchNumber) {
+        self.0.lock().unwrap().push("update_correct_block");
+    }
+
+    async fn report_divergence(&mut self, _: L1BatchNumber) {
+        self.0.lock().unwrap().push("report_divergence");
+    }
+
+    async fn report_localized_reorg(&mut self, _: L1BatchNumber, _: Option<MiniblockNumber>) {
+        self.0.lock().unwrap().push("report_localized_reorg");
+    }
+
+    async fn report_suspicion(&mut self, _: ReorgSuspicion) {
+        self.0.lock().unwrap().push("report_suspicion");
+    }
+
+    async fn report_transient_failure(
+        &mut self,
+        _: usize,
+        _: Option<(MiniblockNumber, L1BatchNumber)>,
+    ) {
+        self.0.lock().unwrap().push("report_transient_failure");
+    }
+
+    async fn report_failure(&mut self, _: &str) {
+        self.0.lock().unwrap().push("report_failure");
+    }
+
+    async fn start_shutting_down(&mut self) {
+        self.0.lock().unwrap().push("start_shutting_down");
+    }
+}
+
+#[tokio::test]
+async fn custom_event_handlers_are_invoked_alongside_health_updates() {
+    let pool = ConnectionPool::test_pool().await;
+    let http_client = HttpClientBuilder::default()
+        .build("http://127.0.0.1:3050")
+        .unwrap();
+    let first_events = Arc::<Mutex<Vec<_>>>::default();
+    let second_events = Arc::<Mutex<Vec<_>>>::default();
+    let mut detector = ReorgDetector::builder(http_client, pool)
+        .event_handler(Box::new(RecordingEventHandler(first_events.clone())))
+        .event_handler(Box::new(RecordingEventHandler(second_events.clone())))
+        .build()
+        .unwrap();
+    let health_check = detector.health_check();
+
+    detector.event_handler.initialize().await;
+    detector
+        .event_handler
+        .report_divergence(L1BatchNumber(1))
+        .await;
+    let health = health_check.check_health().await;
+    assert_matches!(health.status(), HealthStatus::Affected);
+    for events in [first_events, second_events] {
+        assert_eq!(*events.lock().unwrap(), ["initialize", "report_divergence"]);
+    }
+}
+
+#[tokio::test]
+async fn retry_backoff_is_exponential_with_jitter() {
+    let pool = ConnectionPool::test_pool().await;
+    let http_client = HttpClientBuilder::default()
+        .build("http://127.0.0.1:3050")
+        .unwrap();
+    let err = ReorgDetector::builder(http_client.clone(), pool.clone())
+        .retry_backoff(Duration::from_secs(2), Duration::from_secs(1))
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("retry backoff"), "{err}");
+
+    let mut detector = ReorgDetector::builder(http_client, pool)
+        .retry_backoff(Duration::from_millis(100), Duration::from_secs(1))
+        .build()
+        .unwrap();
+    let expected_backoffs_ms = [100, 200, 400, 800, 1_000, 1_000];
+    for expected_backoff_ms in expected_backoffs_ms {
+        let delay = detector.next_retry_delay();
+        let expected_backoff = Duration::from_millis(expected_backoff_ms);
+        assert!(
+            delay >= expected_backoff / 2 && delay <= expected_backoff,
+            "{delay:?}, expected backoff: {expected_backoff:?}"
+        );
+    }
+
+    // Emulate a successful check resetting the backoff.
+    detector.retry_backoff = None;
+    let delay = detector.next_retry_delay();
+    assert!(delay <= Duration::from_millis(100), "{delay:?}");
+}
+
+#[tokio::test]
+async fn setting_sleep_interval() {
+    let pool = ConnectionPool::test_pool().await;
+    let detector = create_mock_detector(MockMainNodeClient::default(), pool)
+        .with_sleep_interval(Duration::from_secs(30));
+    assert_eq!(detector.sleep_interval, Duration::from_secs(30));
+}
+
+#[tokio::test]
+#[should_panic(expected = "sleep interval must be positive")]
+async fn zero_sleep_interval_is_rejected() {
+    let pool = ConnectionPool::test_pool().await;
+    create_mock_detector(MockMainNodeClient::default(), pool).with_sleep_interval(Duration::ZERO);
+}
+
+#[tokio::test]
+async fn hanging_rpc_calls_time_out() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    *client.hanging_miniblock_hash_requests.lock().unwrap() = 1;
+
+    let mut detector = ReorgDetector {
+        rpc_timeout: Duration::from_millis(50),
+        ..create_mock_detector(client, pool)
+    };
+    let err = detector.check_consistency().await.unwrap_err();
+    assert!(err.is_transient(), "{err}");
+    // The following check should succeed since the main node no longer hangs.
+    detector.check_consistency().await.unwrap();
+}
+
+#[tokio::test]
+async fn transient_rpc_errors_are_retried_within_call() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    *client.hanging_miniblock_hash_requests.lock().unwrap() = 2;
+
+    let mut detector = ReorgDetector {
+        rpc_timeout: Duration::from_millis(50),
+        rpc_retries: RpcRetries {
+            max_retries: 2,
+            backoff: Duration::from_millis(1),
+        },
+        ..create_mock_detector(client, pool)
+    };
+    // Both timed out requests should be retried without failing the check.
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(outcome, CheckOutcome::Consistent { .. });
+}
+
+#[tokio::test]
+async fn rpc_retry_backoff_is_exponential_and_capped() {
+    let pool = ConnectionPool::test_pool().await;
+    let client = MockMainNodeClient::default();
+    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+    let clock = RecordingClock::default();
+    let sleeps = clock.0.clone();
+    let detector = ReorgDetector {
+        rpc_retries: RpcRetries {
+            max_retries: 4,
+            backoff: Duration::from_secs(1),
+        },
+        max_retry_backoff: Duration::from_secs(5),
+        ..create_mock_detector(client, pool)
+    }
+    .with_clock(clock);
+
+    let err = detector
+        .client()
+        .miniblock_hash(MiniblockNumber(0))
+        .await
+        .unwrap_err();
+    assert_matches!(err.as_ref(), RpcError::RequestTimeout);
+    let sleeps = sleeps.lock().unwrap().clone();
+    assert_eq!(
+        sleeps,
+        [1, 2, 4, 5].map(Duration::from_secs),
+        "RPC retry backoff should be driven by the clock"
+    );
+}
+
+#[tokio::test]
+async fn rpc_retries_are_aborted_on_stop_signal() {
+    let pool = ConnectionPool::test_pool().await;
+    let client = MockMainNodeClient::default();
+    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector = ReorgDetector {
+        rpc_retries: RpcRetries {
+            max_retries: 3,
+            backoff: Duration::from_secs(3_600),
+        },
+        stop_receiver: Some(stop_receiver),
+        ..create_mock_detector(client, pool)
+    };
+
+    let client = detector.client();
+    let call = client.miniblock_hash(MiniblockNumber(0));
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        stop_sender.send_replace(true);
+        future::pending::<()>().await;
+    };
+    let err = tokio::time::timeout(Duration::from_secs(10), async {
+        tokio::select! {
+            result = call => result,
+            () = stop => unreachable!(),
+        }
+    })
+    .await
+    .expect("RPC retries are not aborted")
+    .unwrap_err();
+    assert_matches!(err.as_ref(), RpcError::RequestTimeout);
+}
+
+#[tokio::test]
+async fn rpc_calls_are_rate_limited() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut client = MockMainNodeClient::default();
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(0), H256::repeat_byte(1));
+    let requests_per_second = NonZeroU32::new(20).unwrap();
+    let detector = ReorgDetector {
+        rpc_rate_limiter: Some(RateLimiter::direct(Quota::per_second(requests_per_second))),
+        ..create_mock_detector(client, pool)
+    };
+
+    // The first 20 requests fit into the initial burst, and each of the remaining 10 requests should wait for 50ms.
+    let started_at = Instant::now();
+    for _ in 0..10 {
+        let hash = detector
+            .client()
+            .miniblock_hash(MiniblockNumber(0))
+            .await
+            .unwrap();
+        assert_eq!(hash, Some(H256::repeat_byte(1)));
+    }
+    let hashes = detector
+        .client()
+        .l1_batch_root_hashes(L1BatchNumber(0)..=L1BatchNumber(19))
+        .await
+        .unwrap();
+    assert_eq!(hashes, [None; 20]);
+    let elapsed = started_at.elapsed();
+    assert!(elapsed >= Duration::from_millis(400), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn rpc_call_cost_can_exceed_rate_limit_burst() {
+    let pool = ConnectionPool::test_pool().await;
+    let client = MockMainNodeClient::default();
+    let requests_per_second = NonZeroU32::new(20).unwrap();
+    let detector = ReorgDetector {
+        rpc_rate_limiter: Some(RateLimiter::direct(Quota::per_second(requests_per_second))),
+        ..create_mock_detector(client, pool)
+    };
+
+    // The call costs 30 requests, i.e., more than the burst size of the rate limiter. The first 20 requests
+    // should fit into the initial burst, and each of the remaining 10 requests should wait for 50ms.
+    let started_at = Instant::now();
+    let hashes = tokio::time::timeout(
+        Duration::from_secs(10),
+        detector
+            .client()
+            .l1_batch_root_hashes(L1BatchNumber(0)..=L1BatchNumber(29)),
+    )
+    .await
+    .expect("rate-limited call hasn't completed")
+    .unwrap();
+    assert_eq!(hashes, [None; 30]);
+    let elapsed = started_at.elapsed();
+    assert!(elapsed >= Duration::from_millis(400), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn detector_with_custom_client() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    let requested_methods = client.requested_methods.clone();
+
+    let mut detector = ReorgDetector::from_parts(Box::new(client), pool);
+    assert_eq!(detector.health_check().name(), "reorg_detector");
+    assert_eq!(detector.last_correct_block(), None);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_eq!(
+        outcome,
+        CheckOutcome::Consistent {
+            l1_batch: L1BatchNumber(0),
+            miniblock: MiniblockNumber(0),
+        }
+    );
+    assert_eq!(
+        serde_json::to_value(outcome).unwrap(),
+        serde_json::json!({ "outcome": "consistent", "l1_batch": 0, "miniblock": 0 })
+    );
+    assert_eq!(
+        detector.last_correct_block(),
+        Some((MiniblockNumber(0), L1BatchNumber(0)))
+    );
+    // Check that the provided client was used.
+    assert!(!requested_methods.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn detector_events_are_broadcast() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    drop(storage);
+
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let (event_sender, mut event_receiver) = broadcast::channel(16);
+    let detector = ReorgDetector::from_parts(Box::new(client), pool)
+        .with_sleep_interval(Duration::from_millis(10))
+        .with_event_broadcast(event_sender);
+    let health_check = detector.health_check();
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    let event = event_receiver.recv().await.unwrap();
+    assert_eq!(event, ReorgEvent::Initialized);
+    let event = event_receiver.recv().await.unwrap();
+    assert_eq!(
+        event,
+        ReorgEvent::CorrectBlockUpdated {
+            last_correct_miniblock: MiniblockNumber(0),
+            last_correct_l1_batch: L1BatchNumber(0),
+        }
+    );
+    // The health check should be updated as well.
+    assert_eq!(
+        health_check.check_health().await.status(),
+        HealthStatus::Ready
+    );
+
+    stop_sender.send_replace(true);
+    detector_task.await.unwrap().unwrap();
+    let mut events = vec![];
+    while let Ok(event) = event_receiver.try_recv() {
+        events.push(event);
+    }
+    assert!(events.contains(&ReorgEvent::ShuttingDown), "{events:?}");
+}
+
+/// Stores a transaction together with the data produced by it (storage logs, events, L2-to-L1 logs,
+/// factory deps and a deployed token) in the specified miniblock.
+async fn store_miniblock_with_tx_data(
+    storage: &mut StorageProcessor<'_>,
+    number: u32,
+    hash: H256,
+    token_address: Address,
+) -> H256 {
+    let tx = create_l2_transaction(10, 100);
+    let tx_hash = tx.hash();
+    let tx_result = execute_l2_transaction(tx.clone());
+    storage
+        .transactions_dal()
+        .insert_transaction_l2(tx, TransactionExecutionMetrics::default())
+        .await;
+    store_miniblock(storage, number, hash).await;
+    let miniblock_number = MiniblockNumber(number);
+    storage
+        .transactions_dal()
+        .mark_txs_as_executed_in_miniblock(miniblock_number, &[tx_result], 1.into())
+        .await;
+
+    let deployment_log =
+        StorageLog::new_write_log(get_code_key(&token_address), H256::repeat_byte(1));
+    storage
+        .storage_logs_dal()
+        .insert_storage_logs(miniblock_number, &[(tx_hash, vec![deployment_log])])
+        .await
+        .unwrap();
+    let location = IncludedTxLocation {
+        tx_hash,
+        tx_index_in_miniblock: 0,
+        tx_initiator_address: Address::zero(),
+    };
+    let event = VmEvent {
+        location: (L1BatchNumber(number), 0),
+        address: token_address,
+        indexed_topics: vec![H256::repeat_byte(2)],
+        value: vec![],
+    };
+    storage
+        .events_dal()
+        .save_events(miniblock_number, &[(location, vec![&event])])
+        .await;
+    let l2_to_l1_log = UserL2ToL1Log(L2ToL1Log {
+        tx_number_in_block: 0,
+        ..L2ToL1Log::default()
+    });
+    storage
+        .events_dal()
+        .save_user_l2_to_l1_logs(miniblock_number, &[(location, vec![&l2_to_l1_log])])
+        .await;
+    let factory_deps = HashMap::from([(H256::repeat_byte(1), vec![0; 32])]);
+    storage
+        .factory_deps_dal()
+        .insert_factory_deps(miniblock_number, &factory_deps)
+        .await
+        .unwrap();
+    let token = TokenInfo {
+        l1_address: Address::repeat_byte(0xfe),
+        l2_address: token_address,
+        metadata: TokenMetadata {
+            name: "Test".to_owned(),
+            symbol: "TST".to_owned(),
+            decimals: 18,
+        },
+    };
+    storage.tokens_dal().add_tokens(&[token]).await.unwrap();
+    tx_hash
+}
+
+#[tokio::test]
+async fn reorg_is_reverted_in_auto_revert_mode() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    let token_address = Address::repeat_byte(0x42);
+    let mut diverged_tx_hash = None;
+    for number in 1..=4 {
+        let local_hash = H256::from_low_u64_be(number.into());
+        if number == 3 {
+            let tx_hash =
+                store_miniblock_with_tx_data(&mut storage, number, local_hash, token_address).await;
+            diverged_tx_hash = Some(tx_hash);
+        } else {
+            store_miniblock(&mut storage, number, local_hash).await;
+        }
+        seal_l1_batch(&mut storage, number, local_hash).await;
+        let remote_hash = if number <= 2 {
+            local_hash
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+
+    // The detector only rolls back Postgres data, so the state keeper cache and tree paths are not accessed.
+    let reverter = BlockReverter::new(
+        "/nonexistent/state_keeper_cache".to_owned(),
+        "/nonexistent/tree".to_owned(),
+        None,
+        pool.clone(),
+        L1ExecutedBatchesRevert::Allowed,
+    );
+    let (event_sender, mut event_receiver) = broadcast::channel(16);
+    let detector = ReorgDetector {
+        block_reverter: Some(reverter),
+        ..create_mock_detector(client, pool.clone())
+    }
+    .with_event_broadcast(event_sender);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
+
+    loop {
+        let event = event_receiver.recv().await.unwrap();
+        if matches!(
+            event,
+            ReorgEvent::ReorgLocalized {
+                last_correct_l1_batch: L1BatchNumber(2),
+                ..
+            }
+        ) {
+            break;
+        }
+    }
+    // The detector should continue running after the rollback.
+    loop {
+        let event = event_receiver.recv().await.unwrap();
+        if let ReorgEvent::CorrectBlockUpdated {
+            last_correct_miniblock,
+            last_correct_l1_batch,
+        } = event
+        {
+            assert_eq!(last_correct_miniblock, MiniblockNumber(2));
+            assert_eq!(last_correct_l1_batch, L1BatchNumber(2));
+            break;
+        }
+    }
+    stop_sender.send_replace(true);
+    let last_correct_l1_batch = detector_task.await.unwrap().unwrap();
+    assert_eq!(last_correct_l1_batch, None);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let sealed_l1_batch = storage
+        .blocks_dal()
+        .get_sealed_l1_batch_number()
+        .await
+        .unwrap();
+    assert_eq!(sealed_l1_batch, Some(L1BatchNumber(2)));
+    let sealed_miniblock = storage
+        .blocks_dal()
+        .get_sealed_miniblock_number()
+        .await
+        .unwrap();
+    assert_eq!(sealed_miniblock, Some(MiniblockNumber(2)));
+
+    // Check that all data produced after the last correct L1 batch is rolled back.
+    let diverged_tx_hash = diverged_tx_hash.unwrap();
+    let diverged_miniblock = MiniblockNumber(3);
+    let miniblock_txs = storage
+        .transactions_web3_dal()
+        .get_raw_miniblock_transactions(diverged_miniblock)
+        .await
+        .unwrap();
+    assert!(miniblock_txs.is_empty(), "{miniblock_txs:?}");
+    let storage_log_count = storage
+        .storage_logs_dal()
+        .count_miniblock_storage_logs(diverged_miniblock)
+        .await
+        .unwrap();
+    assert_eq!(storage_log_count, 0);
+    let events = storage
+        .events_web3_dal()
+        .get_all_logs(MiniblockNumber(2))
+        .await
+        .unwrap();
+    assert!(events.is_empty(), "{events:?}");
+    // Receipts include the transaction events and L2-to-L1 logs.
+    let receipts = storage
+        .transactions_web3_dal()
+        .get_transaction_receipts(&[diverged_tx_hash])
+        .await
+        .unwrap();
+    assert!(receipts.is_empty(), "{receipts:?}");
+    let factory_dep = storage
+        .factory_deps_dal()
+        .get_factory_dep(H256::repeat_byte(1))
+        .await
+        .unwrap();
+    assert_eq!(factory_dep, None);
+    let token_addresses = storage
+        .tokens_dal()
+        .get_all_l2_token_addresses()
+        .await
+        .unwrap();
+    assert!(!token_addresses.contains(&token_address));
+}
+
+#[tokio::test]
+async fn failover_client_rotates_endpoints_on_transient_errors() {
+    let mut primary = MockMainNodeClient::default();
+    primary
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::repeat_byte(1));
+    let mut backup = primary.clone();
+    backup.requested_methods = Arc::default();
+    backup.error_kind = Arc::default();
+    let primary_error_kind = primary.error_kind.clone();
+    let primary_methods = primary.requested_methods.clone();
+    let backup_error_kind = backup.error_kind.clone();
+    let backup_methods = backup.requested_methods.clone();
+    *primary_error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+
+    let client = FailoverClient::new(vec![primary, backup]);
+    for _ in 0..2 {
+        let hash = client.l1_batch_root_hash(L1BatchNumber(1)).await.unwrap();
+        assert_eq!(hash, Some(H256::repeat_byte(1)));
+    }
+    // The client should stick to the healthy backup endpoint.
+    assert_eq!(backup_methods.lock().unwrap().len(), 2);
+    assert!(primary_methods.lock().unwrap().is_empty());
+
+    *primary_error_kind.lock().unwrap() = None;
+    *backup_error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+    let hash = client.l1_batch_root_hash(L1BatchNumber(1)).await.unwrap();
+    assert_eq!(hash, Some(H256::repeat_byte(1)));
+    assert_eq!(primary_methods.lock().unwrap().len(), 1);
+
+    // Non-transient errors should not lead to failover.
+    *primary_error_kind.lock().unwrap() = Some(RpcErrorKind::Fatal);
+    *backup_error_kind.lock().unwrap() = None;
+    let err = client
+        .l1_batch_root_hash(L1BatchNumber(1))
+        .await
+        .unwrap_err();
+    assert!(!is_transient_err(&err), "{err}");
+    assert_eq!(backup_methods.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn detector_tags_db_connections() {
+    let pool = ConnectionPool::constrained_test_pool(1).await;
+    let mut storage = pool.access_storage().await.unwrap();
+    ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+        .await
+        .unwrap();
+
+    // Since the pool has a single connection, which is held by the test, the detector will fail acquiring
+    // a connection. The error message will contain the tags of the requested connection.
+    let mut detector = create_mock_detector(MockMainNodeClient::default(), pool.clone());
+    let err = detector.check_consistency().await.unwrap_err();
+    let err = format!("{err:?}");
+    // Matching strings in error messages is an anti-pattern, but we want to test DB observability here.
+    assert!(err.contains("requested by `reorg_detector`"), "{err}");
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn logs_tree_root_mismatch(compare_logs_tree_roots: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    for number in 1..=2 {
+        let tx = create_l2_transaction(10, 100);
+        let tx_hash = tx.hash();
+        let tx_result = execute_l2_transaction(tx.clone());
+        storage
+            .transactions_dal()
+            .insert_transaction_l2(tx, TransactionExecutionMetrics::default())
+            .await;
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        storage
+            .transactions_dal()
+            .mark_txs_as_executed_in_miniblock(
+                MiniblockNumber(number),
+                &[tx_result.clone()],
+                1.into(),
+            )
+            .await;
+
+        // Each L1 batch contains a single L2-to-L1 log emitted by the transaction.
+        let mut header = create_l1_batch(number);
+        header.l2_to_l1_logs.push(UserL2ToL1Log(L2ToL1Log {
+            tx_number_in_block: 0,
+            ..L2ToL1Log::default()
+        }));
+        storage
+            .blocks_dal()
+            .insert_mock_l1_batch(&header)
+            .await
+            .unwrap();
+        storage
+            .blocks_dal()
+            .mark_miniblocks_as_executed_in_l1_batch(L1BatchNumber(number))
+            .await
+            .unwrap();
+        storage
+            .blocks_dal()
+            .set_l1_batch_hash(L1BatchNumber(number), H256::repeat_byte(number as u8))
+            .await
+            .unwrap();
+        storage
+            .transactions_dal()
+            .mark_txs_as_executed_in_l1_batch(L1BatchNumber(number), &[tx_result])
+            .await;
+        let metadata = create_l1_batch_metadata(number);
+        storage
+            .blocks_dal()
+            .save_l1_batch_commitment_artifacts(
+                L1BatchNumber(number),
+                &l1_batch_metadata_to_commitment_artifacts(&metadata),
+            )
+            .await
+            .unwrap();
+
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+        // The main node has the same state root for L1 batch #2, but a different logs tree root.
+        let remote_logs_root = if number == 2 {
+            H256::repeat_byte(0xff)
+        } else {
+            metadata.l2_l1_merkle_root
+        };
+        client
+            .logs_root_hash_responses
+            .insert(tx_hash, remote_logs_root);
+    }
+    drop(storage);
+
+    let mut detector =
+        create_mock_detector(client, pool).with_logs_tree_root_comparison(compare_logs_tree_roots);
+    let outcome = detector.check_consistency().await.unwrap();
+    if compare_logs_tree_roots {
+        assert_eq!(
+            outcome,
+            CheckOutcome::ReorgDetected {
+                last_correct_l1_batch: L1BatchNumber(1),
+                divergence: None,
+            }
+        );
+    } else {
+        assert_eq!(
+            outcome,
+            CheckOutcome::Consistent {
+                l1_batch: L1BatchNumber(2),
+                miniblock: MiniblockNumber(2),
+            }
+        );
+    }
+}
+
+#[test]
+fn miniblock_transactions_diff() {
+    let [a, b, c] = [1, 2, 3].map(H256::repeat_byte);
+    let miniblock = MiniblockNumber(1);
+    assert!(MiniblockTransactionsDiff::new(miniblock, &[a, b], &[a, b]).is_empty());
+
+    let diff = MiniblockTransactionsDiff::new(miniblock, &[a, b], &[a, c]);
+    assert_eq!(diff.local_only, [b]);
+    assert_eq!(diff.remote_only, [c]);
+    assert!(!diff.reordered);
+
+    let diff = MiniblockTransactionsDiff::new(miniblock, &[a, b, c], &[b, a]);
+    assert_eq!(diff.local_only, [c]);
+    assert!(diff.remote_only.is_empty());
+    assert!(diff.reordered);
+}
+
+#[tokio::test]
+async fn diverged_transactions_are_diagnosed() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let mut local_tx_hashes = vec![];
+    for number in 1..=2 {
+        let tx = create_l2_transaction(10, 100);
+        local_tx_hashes.push(tx.hash());
+        let tx_result = execute_l2_transaction(tx.clone());
+        storage
+            .transactions_dal()
+            .insert_transaction_l2(tx, TransactionExecutionMetrics::default())
+            .await;
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        storage
+            .transactions_dal()
+            .mark_txs_as_executed_in_miniblock(MiniblockNumber(number), &[tx_result], 1.into())
+            .await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+
+        // L1 batch #2 has diverged since the main node has executed another transaction in it.
+        let (remote_hash, remote_tx_hash) = if number == 2 {
+            (H256::repeat_byte(0xff), H256::repeat_byte(0xee))
+        } else {
+            (H256::repeat_byte(number as u8), local_tx_hashes[0])
+        };
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), remote_hash);
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+        client
+            .miniblock_transaction_responses
+            .insert(MiniblockNumber(number), vec![remote_tx_hash]);
+    }
+    drop(storage);
+    client.latest_miniblock_response = Some(MiniblockNumber(2));
+    client.latest_l1_batch_response = Some(L1BatchNumber(2));
+
+    let requested_methods = client.requested_methods.clone();
+    let mut detector = create_mock_detector(client, pool).with_divergence_diagnostics(true);
+    let diffs = detector
+        .diagnose_divergence(L1BatchNumber(2))
+        .await
+        .unwrap();
+    assert_eq!(
+        diffs,
+        [MiniblockTransactionsDiff {
+            miniblock: MiniblockNumber(2),
+            local_only: vec![local_tx_hashes[1]],
+            remote_only: vec![H256::repeat_byte(0xee)],
+            reordered: false,
+        }]
+    );
+    let diffs = detector
+        .diagnose_divergence(L1BatchNumber(1))
+        .await
+        .unwrap();
+    assert!(diffs.is_empty(), "{diffs:?}");
+
+    requested_methods.lock().unwrap().clear();
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(1),
+            ..
+        }
+    );
+    let diagnosis_requests = requested_methods
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|&&method| method == "miniblock_transaction_hashes")
+        .count();
+    assert_eq!(diagnosis_requests, 1);
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn commitment_mismatch(compare_commitments: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
 
-    fn report_divergence(&mut self, _diverged_l1_batch: L1BatchNumber) {
-        // Do nothing
-    }
+    for number in 1..=2 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        let metadata = create_l1_batch_metadata(number);
+        let artifacts = l1_batch_metadata_to_commitment_artifacts(&metadata);
+        storage
+            .blocks_dal()
+            .save_l1_batch_commitment_artifacts(L1BatchNumber(number), &artifacts)
+            .await
+            .unwrap();
 
-    fn start_shutting_down(&mut self) {
-        // Do nothing
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+        // The main node has the same state root for L1 batch #2, but a different commitment.
+        let remote_commitment = if number == 2 {
+            H256::repeat_byte(0xff)
+        } else {
+            artifacts.commitment_hash.commitment
+        };
+        client
+            .commitment_responses
+            .insert(L1BatchNumber(number), remote_commitment);
     }
-}
+    drop(storage);
+    client.latest_miniblock_response = Some(MiniblockNumber(2));
+    client.latest_l1_batch_response = Some(L1BatchNumber(2));
 
-fn create_mock_detector(client: MockMainNodeClient, pool: ConnectionPool) -> ReorgDetector {
-    let (health_check, health_updater) = ReactiveHealthCheck::new("reorg_detector");
-    ReorgDetector {
-        client: Box::new(client),
-        event_handler: Box::new(health_updater),
-        pool,
-        sleep_interval: Duration::from_millis(10),
-        health_check,
+    let mut detector =
+        create_mock_detector(client, pool).with_commitment_comparison(compare_commitments);
+    let report = detector.forensic_scan().await.unwrap();
+    let outcome = detector.check_consistency().await.unwrap();
+    if compare_commitments {
+        assert_eq!(report.first_diverged_l1_batch, Some(L1BatchNumber(2)));
+        assert_eq!(report.diverged_hash, Some(DivergedHash::Commitment));
+        assert_eq!(
+            outcome,
+            CheckOutcome::ReorgDetected {
+                last_correct_l1_batch: L1BatchNumber(1),
+                divergence: None,
+            }
+        );
+    } else {
+        assert_eq!(report.first_diverged_l1_batch, None);
+        assert_eq!(report.diverged_hash, None);
+        assert_eq!(
+            outcome,
+            CheckOutcome::Consistent {
+                l1_batch: L1BatchNumber(2),
+                miniblock: MiniblockNumber(2),
+            }
+        );
     }
 }
 
-#[test_casing(4, Product(([false, true], [false, true])))]
+#[test_casing(2, [false, true])]
 #[tokio::test]
-async fn normal_reorg_function(snapshot_recovery: bool, with_transient_errors: bool) {
+async fn pass_through_data_hash_mismatch(compare_pass_through_data: bool) {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
     let mut client = MockMainNodeClient::default();
-    if snapshot_recovery {
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+
+    let mut local_hash = H256::zero();
+    for number in 1..=2 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        let metadata = create_l1_batch_metadata(number);
+        let artifacts = l1_batch_metadata_to_commitment_artifacts(&metadata);
         storage
-            .protocol_versions_dal()
-            .save_protocol_version_with_tx(ProtocolVersion::default())
-            .await;
-    } else {
-        let genesis_root_hash =
-            ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
-                .await
-                .unwrap();
-        client.miniblock_hash_responses.insert(
-            MiniblockNumber(0),
-            MiniblockHasher::legacy_hash(MiniblockNumber(0)),
-        );
-        client
-            .l1_batch_root_hash_responses
-            .insert(L1BatchNumber(0), genesis_root_hash);
-    }
+            .blocks_dal()
+            .save_l1_batch_commitment_artifacts(L1BatchNumber(number), &artifacts)
+            .await
+            .unwrap();
+        local_hash = artifacts.commitment_hash.pass_through_data;
 
-    let l1_batch_numbers = if snapshot_recovery {
-        11_u32..=20
-    } else {
-        1_u32..=10
-    };
-    let last_l1_batch_number = L1BatchNumber(*l1_batch_numbers.end());
-    let last_miniblock_number = MiniblockNumber(*l1_batch_numbers.end());
-    let miniblock_and_l1_batch_hashes = l1_batch_numbers.map(|number| {
-        let miniblock_hash = H256::from_low_u64_be(number.into());
         client
             .miniblock_hash_responses
-            .insert(MiniblockNumber(number), miniblock_hash);
-        let l1_batch_hash = H256::repeat_byte(number as u8);
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
         client
             .l1_batch_root_hash_responses
-            .insert(L1BatchNumber(number), l1_batch_hash);
-        (number, miniblock_hash, l1_batch_hash)
-    });
-    let miniblock_and_l1_batch_hashes: Vec<_> = miniblock_and_l1_batch_hashes.collect();
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+        // The main node has the same state root for L1 batch #2, but different pass-through data.
+        let remote_hash = if number == 2 {
+            H256::repeat_byte(0xff)
+        } else {
+            local_hash
+        };
+        client
+            .pass_through_data_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+    client.latest_miniblock_response = Some(MiniblockNumber(2));
+    client.latest_l1_batch_response = Some(L1BatchNumber(2));
 
-    if with_transient_errors {
-        *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
-        // "Fix" the client after a certain delay.
-        let error_kind = Arc::clone(&client.error_kind);
-        tokio::spawn(async move {
-            tokio::time::sleep(Duration::from_millis(100)).await;
-            *error_kind.lock().unwrap() = None;
-        });
+    let mut detector = create_mock_detector(client, pool)
+        .with_pass_through_data_comparison(compare_pass_through_data);
+    let report = detector.forensic_scan().await.unwrap();
+    let outcome = detector.check_consistency().await.unwrap();
+    if compare_pass_through_data {
+        assert_eq!(report.first_diverged_l1_batch, Some(L1BatchNumber(2)));
+        assert_eq!(report.diverged_hash, Some(DivergedHash::PassThroughData));
+        assert_eq!(
+            outcome,
+            CheckOutcome::ReorgDetected {
+                last_correct_l1_batch: L1BatchNumber(1),
+                divergence: Some(DivergenceKind::PassThroughDataHash {
+                    l1_batch: L1BatchNumber(2),
+                    local: local_hash,
+                    remote: H256::repeat_byte(0xff),
+                }),
+            }
+        );
+    } else {
+        assert_eq!(report.first_diverged_l1_batch, None);
+        assert_eq!(report.diverged_hash, None);
+        assert_eq!(
+            outcome,
+            CheckOutcome::Consistent {
+                l1_batch: L1BatchNumber(2),
+                miniblock: MiniblockNumber(2),
+            }
+        );
     }
+}
 
-    let (stop_sender, stop_receiver) = watch::channel(false);
-    let (block_update_sender, mut block_update_receiver) =
-        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
-    let detector = ReorgDetector {
-        event_handler: Box::new(block_update_sender),
-        ..create_mock_detector(client, pool.clone())
-    };
-    let detector_task = tokio::spawn(detector.run(stop_receiver));
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn miniblock_count_mismatch(compare_miniblock_counts: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .miniblock_range_responses
+        .insert(L1BatchNumber(0), (MiniblockNumber(0), MiniblockNumber(0)));
 
-    for (number, miniblock_hash, l1_batch_hash) in miniblock_and_l1_batch_hashes {
-        store_miniblock(&mut storage, number, miniblock_hash).await;
-        tokio::time::sleep(Duration::from_millis(10)).await;
-        seal_l1_batch(&mut storage, number, l1_batch_hash).await;
-        tokio::time::sleep(Duration::from_millis(10)).await;
+    for number in 1..=2 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+        // The main node has the same state root for L1 batch #2, but attributes an extra miniblock to it.
+        let remote_range = if number == 2 {
+            (MiniblockNumber(2), MiniblockNumber(3))
+        } else {
+            (MiniblockNumber(number), MiniblockNumber(number))
+        };
+        client
+            .miniblock_range_responses
+            .insert(L1BatchNumber(number), remote_range);
     }
+    drop(storage);
+    client.latest_miniblock_response = Some(MiniblockNumber(2));
+    client.latest_l1_batch_response = Some(L1BatchNumber(2));
 
-    while let Some((miniblock, l1_batch)) = block_update_receiver.recv().await {
-        assert!(miniblock <= last_miniblock_number);
-        assert!(l1_batch <= last_l1_batch_number);
-        if miniblock == last_miniblock_number && l1_batch == last_l1_batch_number {
-            break;
-        }
+    let mut detector = create_mock_detector(client, pool)
+        .with_miniblock_count_comparison(compare_miniblock_counts);
+    let report = detector.forensic_scan().await.unwrap();
+    let outcome = detector.check_consistency().await.unwrap();
+    if compare_miniblock_counts {
+        assert_eq!(report.first_diverged_l1_batch, Some(L1BatchNumber(2)));
+        assert_eq!(report.diverged_hash, Some(DivergedHash::MiniblockCount));
+        assert_eq!(
+            outcome,
+            CheckOutcome::ReorgDetected {
+                last_correct_l1_batch: L1BatchNumber(1),
+                divergence: None,
+            }
+        );
+    } else {
+        assert_eq!(report.first_diverged_l1_batch, None);
+        assert_eq!(
+            outcome,
+            CheckOutcome::Consistent {
+                l1_batch: L1BatchNumber(2),
+                miniblock: MiniblockNumber(2),
+            }
+        );
     }
-
-    // Check detector shutdown
-    stop_sender.send_replace(true);
-    let task_result = detector_task.await.unwrap();
-    assert_eq!(task_result.unwrap(), None);
 }
 
 #[tokio::test]
-async fn detector_stops_on_fatal_rpc_error() {
+async fn linear_scan_fallback_on_inconsistent_reorg_localization() {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
     ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
         .await
         .unwrap();
+    let mut client = MockMainNodeClient::default();
+    for number in 1..=10 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        let remote_hash = if number >= 6 {
+            H256::repeat_byte(0xff)
+        } else {
+            H256::repeat_byte(number as u8)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
+    // Prefetched root hashes wrongly indicate that L1 batches #4 and #5 have diverged, so binary search
+    // localizes the reorg incorrectly.
+    for number in [4, 5] {
+        client
+            .stale_l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(0xff));
+    }
 
-    let client = MockMainNodeClient::default();
-    *client.error_kind.lock().unwrap() = Some(RpcErrorKind::Fatal);
-
-    let (_stop_sender, stop_receiver) = watch::channel(false);
-    let detector = create_mock_detector(client, pool.clone());
-    // Check that the detector stops when a fatal RPC error is encountered.
-    detector.run(stop_receiver).await.unwrap_err();
+    let detector = create_mock_detector(client, pool);
+    let last_correct_l1_batch = detector
+        .detect_reorg(L1BatchNumber(0), L1BatchNumber(10))
+        .await
+        .unwrap();
+    assert_eq!(last_correct_l1_batch, L1BatchNumber(5));
 }
 
 #[tokio::test]
-async fn reorg_is_detected_on_batch_hash_mismatch() {
+async fn verifying_l1_batch_range() {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
     let genesis_root_hash =
@@ -290,297 +5141,521 @@ async fn reorg_is_detected_on_batch_hash_mismatch() {
             .await
             .unwrap();
     let mut client = MockMainNodeClient::default();
-    client.miniblock_hash_responses.insert(
-        MiniblockNumber(0),
-        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
-    );
     client
         .l1_batch_root_hash_responses
         .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=20 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        // Divergences are not monotonic: L1 batch #16 matches the main node.
+        let remote_hash = if [12, 14, 17].contains(&number) {
+            H256::repeat_byte(0xff)
+        } else {
+            H256::repeat_byte(number as u8)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
 
-    let (_stop_sender, stop_receiver) = watch::channel(false);
-    let miniblock_hash = H256::from_low_u64_be(23);
-    client
-        .miniblock_hash_responses
-        .insert(MiniblockNumber(1), miniblock_hash);
+    let detector = create_mock_detector(client, pool);
+    let verify = |from, to| detector.verify_range(L1BatchNumber(from), L1BatchNumber(to));
+    assert_eq!(verify(0, 11).await.unwrap(), None);
+    assert_eq!(verify(0, 20).await.unwrap(), Some(L1BatchNumber(12)));
+    assert_eq!(verify(13, 20).await.unwrap(), Some(L1BatchNumber(14)));
+    assert_eq!(verify(15, 16).await.unwrap(), None);
+    assert_eq!(verify(15, 30).await.unwrap(), Some(L1BatchNumber(17)));
+    assert_eq!(verify(20, 10).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn divergence_among_recent_l1_batches_is_caught_on_startup() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
     client
         .l1_batch_root_hash_responses
-        .insert(L1BatchNumber(1), H256::repeat_byte(1));
-    client
-        .miniblock_hash_responses
-        .insert(MiniblockNumber(2), miniblock_hash);
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=5 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+    }
+    drop(storage);
+    // Only the latest L1 batch is checked in the main loop, so the divergence wouldn't be noticed there.
     client
         .l1_batch_root_hash_responses
-        .insert(L1BatchNumber(2), H256::repeat_byte(2));
-
-    let detector = create_mock_detector(client, pool.clone());
-    let detector_task = tokio::spawn(detector.run(stop_receiver));
-
-    store_miniblock(&mut storage, 1, miniblock_hash).await;
-    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
-    store_miniblock(&mut storage, 2, miniblock_hash).await;
-    seal_l1_batch(&mut storage, 2, H256::repeat_byte(0xff)).await;
-    // ^ Hash of L1 batch #2 differs from that on the main node.
+        .insert(L1BatchNumber(4), H256::repeat_byte(0xff));
 
-    let task_result = detector_task.await.unwrap();
-    let last_correct_l1_batch = task_result.unwrap();
-    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(1)));
+    let (_stop_sender, mut stop_receiver) = watch::channel(false);
+    let mut detector = create_mock_detector(client, pool).with_startup_verification_depth(3);
+    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
+    assert_matches!(err, HashMatchError::StartupDivergence(L1BatchNumber(4)));
 }
 
 #[tokio::test]
-async fn reorg_is_detected_on_miniblock_hash_mismatch() {
+async fn startup_verification_is_not_repeated_on_reentry() {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
-    let mut client = MockMainNodeClient::default();
     let genesis_root_hash =
         ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
             .await
             .unwrap();
-    client.miniblock_hash_responses.insert(
-        MiniblockNumber(0),
-        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
-    );
+    let mut client = MockMainNodeClient::default();
     client
         .l1_batch_root_hash_responses
         .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=3 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+    }
+    drop(storage);
+    let root_hash_responses = client.l1_batch_root_hash_responses.clone();
 
-    let (_stop_sender, stop_receiver) = watch::channel(false);
-    let miniblock_hash = H256::from_low_u64_be(23);
-    client
-        .miniblock_hash_responses
-        .insert(MiniblockNumber(1), miniblock_hash);
+    let (stop_sender, mut stop_receiver) = watch::channel(false);
+    let stop_sender = Arc::new(stop_sender);
+    let stop_after_delay = || {
+        stop_sender.send_replace(false);
+        let stop_sender = stop_sender.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            stop_sender.send_replace(true);
+        });
+    };
+    let mut detector = create_mock_detector(client, pool);
+    stop_after_delay();
+    let outcome = detector.run_inner(&mut stop_receiver).await.unwrap();
+    assert_eq!(outcome, None);
+    assert!(detector.startup_verified);
+
+    // Emulates `run_inner()` re-entry after a transient error. If startup verification were repeated,
+    // it would fail on the earliest L1 batch.
+    let mut client = MockMainNodeClient {
+        l1_batch_root_hash_responses: root_hash_responses,
+        ..MockMainNodeClient::default()
+    };
     client
         .l1_batch_root_hash_responses
-        .insert(L1BatchNumber(1), H256::repeat_byte(1));
-    client
-        .miniblock_hash_responses
-        .insert(MiniblockNumber(2), miniblock_hash);
+        .insert(L1BatchNumber(0), H256::repeat_byte(0xff));
+    for number in 1..=3 {
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+    }
+    detector.client = Box::new(client);
+    stop_after_delay();
+    let outcome = detector.run_inner(&mut stop_receiver).await.unwrap();
+    assert_eq!(outcome, None);
+}
+
+#[tokio::test]
+async fn reorg_is_localized_and_reported_in_dry_run_mode() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
     client
-        .miniblock_hash_responses
-        .insert(MiniblockNumber(3), miniblock_hash);
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=5 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        // L1 batches starting from #3 are diverged.
+        let remote_hash = if number >= 3 {
+            H256::repeat_byte(0xff)
+        } else {
+            H256::repeat_byte(number as u8)
+        };
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), remote_hash);
+    }
+    drop(storage);
 
-    let detector = create_mock_detector(client, pool.clone());
+    let detector = create_mock_detector(client, pool).with_dry_run(true);
+    let health_check = detector.health_check();
+    let (stop_sender, stop_receiver) = watch::channel(false);
     let detector_task = tokio::spawn(detector.run(stop_receiver));
 
-    store_miniblock(&mut storage, 1, miniblock_hash).await;
-    seal_l1_batch(&mut storage, 1, H256::repeat_byte(1)).await;
-    store_miniblock(&mut storage, 2, miniblock_hash).await;
-    store_miniblock(&mut storage, 3, H256::repeat_byte(42)).await;
-    // ^ Hash of the miniblock #3 differs from that on the main node.
+    let expected_health = Health::from(HealthStatus::Affected).with_details(serde_json::json!({
+        "reason": "reorg_detected",
+        "diverged_l1_batch": 3,
+        "last_correct_l1_batch": 2,
+    }));
+    while health_check.check_health().await != expected_health {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    // Wait for several iterations of the main loop to check that the detector keeps running.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!detector_task.is_finished());
 
+    stop_sender.send_replace(true);
     let task_result = detector_task.await.unwrap();
-    let last_correct_l1_batch = task_result.unwrap();
-    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(1)));
-    // ^ All locally stored L1 batches should be correct.
-}
-
-#[derive(Debug, Clone, Copy)]
-enum StorageUpdateStrategy {
-    /// Prefill the local storage with all block data.
-    Prefill,
-    /// Sequentially add a new L1 batch after the previous one was checked.
-    Sequential,
+    assert_eq!(task_result.unwrap(), None);
 }
 
-impl StorageUpdateStrategy {
-    const ALL: [Self; 2] = [Self::Prefill, Self::Sequential];
+/// Returns last correct L1 batches from all buffered `ReorgLocalized` events.
+fn drain_localized_reorgs(receiver: &mut broadcast::Receiver<ReorgEvent>) -> Vec<L1BatchNumber> {
+    let mut last_correct_l1_batches = vec![];
+    while let Ok(event) = receiver.try_recv() {
+        if let ReorgEvent::ReorgLocalized {
+            last_correct_l1_batch,
+            ..
+        } = event
+        {
+            last_correct_l1_batches.push(last_correct_l1_batch);
+        }
+    }
+    last_correct_l1_batches
 }
 
-#[test_casing(16, Product(([false, true], [2, 3, 5, 8], StorageUpdateStrategy::ALL)))]
 #[tokio::test]
-async fn reorg_is_detected_on_historic_batch_hash_mismatch(
-    snapshot_recovery: bool,
-    last_correct_batch: u32,
-    storage_update_strategy: StorageUpdateStrategy,
-) {
-    assert!(last_correct_batch < 10);
-    let (l1_batch_numbers, last_correct_batch) = if snapshot_recovery {
-        (11_u32..=20, last_correct_batch + 10)
-    } else {
-        (1_u32..=10, last_correct_batch)
-    };
-
+async fn reorg_is_reported_once_in_dry_run_mode() {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
-    storage
-        .protocol_versions_dal()
-        .save_protocol_version_with_tx(ProtocolVersion::default())
-        .await;
-    let earliest_l1_batch_number = l1_batch_numbers.start() - 1;
-    store_miniblock(&mut storage, earliest_l1_batch_number, H256::zero()).await;
-    seal_l1_batch(&mut storage, earliest_l1_batch_number, H256::zero()).await;
-
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
     let mut client = MockMainNodeClient::default();
-    client
-        .miniblock_hash_responses
-        .insert(MiniblockNumber(earliest_l1_batch_number), H256::zero());
     client
         .l1_batch_root_hash_responses
-        .insert(L1BatchNumber(earliest_l1_batch_number), H256::zero());
-
-    let miniblock_and_l1_batch_hashes = l1_batch_numbers.clone().map(|number| {
-        let mut miniblock_hash = H256::from_low_u64_be(number.into());
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=5 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
         client
             .miniblock_hash_responses
-            .insert(MiniblockNumber(number), miniblock_hash);
-        let mut l1_batch_hash = H256::repeat_byte(number as u8);
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        let remote_hash = if number >= 3 {
+            H256::repeat_byte(0xff)
+        } else {
+            H256::repeat_byte(number as u8)
+        };
         client
             .l1_batch_root_hash_responses
-            .insert(L1BatchNumber(number), l1_batch_hash);
-
-        if number > last_correct_batch {
-            miniblock_hash = H256::zero();
-            l1_batch_hash = H256::zero();
-        }
-        (number, miniblock_hash, l1_batch_hash)
-    });
-    let mut miniblock_and_l1_batch_hashes: Vec<_> = miniblock_and_l1_batch_hashes.collect();
-
-    if matches!(storage_update_strategy, StorageUpdateStrategy::Prefill) {
-        for &(number, miniblock_hash, l1_batch_hash) in &miniblock_and_l1_batch_hashes {
-            store_miniblock(&mut storage, number, miniblock_hash).await;
-            seal_l1_batch(&mut storage, number, l1_batch_hash).await;
-        }
+            .insert(L1BatchNumber(number), remote_hash);
     }
+    drop(storage);
 
-    let (_stop_sender, stop_receiver) = watch::channel(false);
-    let (block_update_sender, mut block_update_receiver) =
-        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
-    let detector = ReorgDetector {
-        event_handler: Box::new(block_update_sender),
-        ..create_mock_detector(client, pool.clone())
-    };
-    let detector_task = tokio::spawn(detector.run(stop_receiver));
-
-    if matches!(storage_update_strategy, StorageUpdateStrategy::Sequential) {
-        let mut last_number = earliest_l1_batch_number;
-        while let Some((miniblock, l1_batch)) = block_update_receiver.recv().await {
-            if miniblock == MiniblockNumber(last_number) && l1_batch == L1BatchNumber(last_number) {
-                let (number, miniblock_hash, l1_batch_hash) =
-                    miniblock_and_l1_batch_hashes.remove(0);
-                assert_eq!(number, last_number + 1);
-                store_miniblock(&mut storage, number, miniblock_hash).await;
-                seal_l1_batch(&mut storage, number, l1_batch_hash).await;
-                last_number = number;
+    let (event_sender, mut event_receiver) = broadcast::channel(64);
+    let sink = CapturingMetricsSink::default();
+    let calls = sink.0.clone();
+    let mut detector = create_mock_detector(client.clone(), pool)
+        .with_dry_run(true)
+        .with_event_broadcast(event_sender)
+        .with_metrics_sink(sink);
+    for _ in 0..3 {
+        let outcome = detector.check_consistency().await.unwrap();
+        assert_matches!(
+            outcome,
+            CheckOutcome::ReorgDetected {
+                last_correct_l1_batch: L1BatchNumber(2),
+                ..
             }
-        }
+        );
     }
 
-    let task_result = detector_task.await.unwrap();
-    let last_correct_l1_batch = task_result.unwrap();
     assert_eq!(
-        last_correct_l1_batch,
-        Some(L1BatchNumber(last_correct_batch))
+        drain_localized_reorgs(&mut event_receiver),
+        [L1BatchNumber(2)]
+    );
+    let detected_reorgs = calls
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|&&call| call == MetricCall::Counter(DetectorCounter::DetectedReorgs))
+        .count();
+    assert_eq!(detected_reorgs, 1);
+
+    // The main node has changed the state at the divergence point, so the reorg should be localized again.
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(3), H256::repeat_byte(3));
+    detector.client = Box::new(client);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(
+        outcome,
+        CheckOutcome::ReorgDetected {
+            last_correct_l1_batch: L1BatchNumber(3),
+            ..
+        }
+    );
+    assert_eq!(
+        drain_localized_reorgs(&mut event_receiver),
+        [L1BatchNumber(3)]
     );
 }
 
 #[tokio::test]
-async fn stopping_reorg_detector_while_waiting_for_l1_batch() {
+async fn reorg_health_is_reset_after_passing_check_in_dry_run_mode() {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
-    assert!(storage.blocks_dal().is_genesis_needed().await.unwrap());
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    let mut client = MockMainNodeClient::default();
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    for number in 1..=3 {
+        store_miniblock(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        client
+            .miniblock_hash_responses
+            .insert(MiniblockNumber(number), H256::repeat_byte(number as u8));
+        client
+            .l1_batch_root_hash_responses
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
+    }
     drop(storage);
+    let mut diverged_client = client.clone();
+    diverged_client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(3), H256::repeat_byte(0xff));
 
-    let (stop_sender, stop_receiver) = watch::channel(false);
-    let detector = create_mock_detector(MockMainNodeClient::default(), pool);
-    let detector_task = tokio::spawn(detector.run(stop_receiver));
+    let mut detector = create_mock_detector(diverged_client, pool).with_dry_run(true);
+    let health_check = detector.health_check();
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(outcome, CheckOutcome::ReorgDetected { .. });
+    let health = serde_json::to_value(health_check.check_health().await).unwrap();
+    assert_eq!(health["status"], "affected");
+    assert_eq!(health["details"]["reason"], "reorg_detected");
 
-    stop_sender.send_replace(true);
+    // The main node has reverted the divergence.
+    detector.client = Box::new(client);
+    let outcome = detector.check_consistency().await.unwrap();
+    assert_matches!(outcome, CheckOutcome::Consistent { .. });
+    let health = health_check.check_health().await;
+    assert!(is_ready_with_correct_blocks(health, 3, 3));
+}
 
-    let task_result = detector_task.await.unwrap();
-    let last_correct_l1_batch = task_result.unwrap();
-    assert_eq!(last_correct_l1_batch, None);
+/// Client factory returning the provided client once.
+#[derive(Debug)]
+struct OneshotClientFactory(Arc<Mutex<Option<MockMainNodeClient>>>);
+
+impl CreateMainNodeClient for OneshotClientFactory {
+    fn create(&self) -> anyhow::Result<Box<dyn MainNodeClient>> {
+        let client = self
+            .0
+            .lock()
+            .unwrap()
+            .take()
+            .context("client is already taken")?;
+        Ok(Box::new(client))
+    }
 }
 
 #[tokio::test]
-async fn detector_errors_on_earliest_batch_hash_mismatch() {
+async fn client_is_recreated_after_repeated_failures() {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
     let genesis_root_hash =
         ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
             .await
             .unwrap();
-    assert_ne!(genesis_root_hash, H256::zero());
+    drop(storage);
 
-    let mut client = MockMainNodeClient::default();
-    client
+    let broken_client = MockMainNodeClient::default();
+    *broken_client.error_kind.lock().unwrap() = Some(RpcErrorKind::Transient);
+    let mut fresh_client = MockMainNodeClient::default();
+    fresh_client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
+    fresh_client
         .l1_batch_root_hash_responses
-        .insert(L1BatchNumber(0), H256::zero());
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    let fresh_client = Arc::new(Mutex::new(Some(fresh_client)));
 
-    let (_stop_sender, mut stop_receiver) = watch::channel(false);
-    let mut detector = create_mock_detector(client, pool.clone());
+    let detector = ReorgDetector {
+        client_factory: Some(Box::new(OneshotClientFactory(fresh_client.clone()))),
+        max_consecutive_client_failures: 3,
+        ..create_mock_detector(broken_client, pool)
+    };
+    let health_check = detector.health_check();
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
 
-    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
-    assert_matches!(err, HashMatchError::EarliestHashMismatch(L1BatchNumber(0)));
+    // The broken client always fails, so the detector can only become healthy after switching to the fresh client.
+    while !is_ready_with_correct_blocks(health_check.check_health().await, 0, 0) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(fresh_client.lock().unwrap().is_none());
+
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
 }
 
 #[tokio::test]
-async fn detector_errors_on_earliest_batch_hash_mismatch_with_snapshot_recovery() {
+async fn http_client_factory_with_auth_headers() {
+    let factory = HttpClientFactory::new("http://127.0.0.1:3050")
+        .with_header("x-api-key", "key")
+        .unwrap()
+        .with_bearer_token("secret")
+        .unwrap();
+    assert_eq!(factory.headers["x-api-key"], "key");
+    assert_eq!(factory.headers[AUTHORIZATION], "Bearer secret");
+    assert!(factory.headers[AUTHORIZATION].is_sensitive());
+    assert!(!format!("{factory:?}").contains("secret"));
+
+    HttpClientFactory::new("http://127.0.0.1:3050")
+        .with_header("invalid header", "value")
+        .unwrap_err();
+    HttpClientFactory::new("http://127.0.0.1:3050")
+        .with_bearer_token("invalid\ntoken")
+        .unwrap_err();
+
+    let pool = ConnectionPool::test_pool().await;
+    let detector = ReorgDetector::builder_with_client_factory(&factory, pool)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(
+        detector.sleep_interval,
+        ReorgDetector::DEFAULT_SLEEP_INTERVAL
+    );
+}
+
+#[tokio::test]
+async fn proof_status_mismatch_is_reported_as_suspicion() {
     let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let genesis_root_hash =
+        ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
+            .await
+            .unwrap();
+    store_miniblock(&mut storage, 1, H256::zero()).await;
+    seal_l1_batch(&mut storage, 1, H256::zero()).await;
+    drop(storage);
+
     let mut client = MockMainNodeClient::default();
     client
         .l1_batch_root_hash_responses
-        .insert(L1BatchNumber(3), H256::zero());
+        .insert(L1BatchNumber(0), genesis_root_hash);
+    client
+        .miniblock_hash_responses
+        .insert(MiniblockNumber(1), H256::zero());
+    client
+        .l1_batch_root_hash_responses
+        .insert(L1BatchNumber(1), H256::zero());
+    // The local L1 batch is not proven.
+    client
+        .proven_l1_batch_responses
+        .insert(L1BatchNumber(1), true);
 
-    let (_stop_sender, mut stop_receiver) = watch::channel(false);
-    let mut detector = create_mock_detector(client, pool.clone());
+    let (suspicion_sender, mut suspicion_receiver) = mpsc::unbounded_channel::<ReorgSuspicion>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(suspicion_sender),
+        ..create_mock_detector(client, pool)
+    }
+    .with_proof_status_comparison(true);
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let detector_task = tokio::spawn(detector.run(stop_receiver));
 
-    tokio::spawn(async move {
-        tokio::time::sleep(Duration::from_millis(20)).await;
-        let mut storage = pool.access_storage().await.unwrap();
-        storage
-            .protocol_versions_dal()
-            .save_protocol_version_with_tx(ProtocolVersion::default())
-            .await;
-        store_miniblock(&mut storage, 3, H256::from_low_u64_be(3)).await;
-        seal_l1_batch(&mut storage, 3, H256::from_low_u64_be(3)).await;
-    });
+    let suspicion = suspicion_receiver.recv().await.unwrap();
+    assert_eq!(
+        suspicion,
+        ReorgSuspicion::DivergentProofStatus {
+            l1_batch: L1BatchNumber(1),
+            local_proven: false,
+            remote_proven: true,
+        }
+    );
 
-    let err = detector.run_inner(&mut stop_receiver).await.unwrap_err();
-    assert_matches!(err, HashMatchError::EarliestHashMismatch(L1BatchNumber(3)));
+    // The signal must not be treated as a reorg.
+    stop_sender.send_replace(true);
+    let task_result = detector_task.await.unwrap();
+    assert_eq!(task_result.unwrap(), None);
 }
 
 #[tokio::test]
-async fn reorg_is_detected_without_waiting_for_main_node_to_catch_up() {
+async fn concurrent_checks() {
     let pool = ConnectionPool::test_pool().await;
     let mut storage = pool.access_storage().await.unwrap();
     let genesis_root_hash =
         ensure_genesis_state(&mut storage, L2ChainId::default(), &GenesisParams::mock())
             .await
             .unwrap();
-    // Fill in local storage with some data, so that it's ahead of the main node.
-    for number in 1..5 {
-        store_miniblock(&mut storage, number, H256::zero()).await;
-        seal_l1_batch(&mut storage, number, H256::zero()).await;
-    }
-    drop(storage);
-
     let mut client = MockMainNodeClient::default();
+    client.miniblock_hash_responses.insert(
+        MiniblockNumber(0),
+        MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+    );
     client
         .l1_batch_root_hash_responses
         .insert(L1BatchNumber(0), genesis_root_hash);
-    for number in 1..3 {
-        client
-            .miniblock_hash_responses
-            .insert(MiniblockNumber(number), H256::zero());
+    for number in 1..=12 {
+        client.miniblock_hash_responses.insert(
+            MiniblockNumber(number),
+            H256::from_low_u64_be(number.into()),
+        );
         client
             .l1_batch_root_hash_responses
-            .insert(L1BatchNumber(number), H256::zero());
+            .insert(L1BatchNumber(number), H256::repeat_byte(number as u8));
     }
-    client
-        .miniblock_hash_responses
-        .insert(MiniblockNumber(3), H256::zero());
-    client
-        .l1_batch_root_hash_responses
-        .insert(L1BatchNumber(3), H256::repeat_byte(0xff));
-    client.latest_l1_batch_response = Some(L1BatchNumber(3));
-    client.latest_miniblock_response = Some(MiniblockNumber(3));
 
+    let (block_update_sender, mut block_update_receiver) =
+        mpsc::unbounded_channel::<(MiniblockNumber, L1BatchNumber)>();
+    let detector = ReorgDetector {
+        event_handler: Box::new(block_update_sender),
+        ..create_mock_detector(client, pool.clone())
+    }
+    .with_concurrent_checks(3);
     let (_stop_sender, stop_receiver) = watch::channel(false);
-    let detector = create_mock_detector(client, pool);
     let detector_task = tokio::spawn(detector.run(stop_receiver));
 
+    for number in 1..=10 {
+        store_miniblock(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+        seal_l1_batch(&mut storage, number, H256::repeat_byte(number as u8)).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // Overlapping checks must not move the last correct blocks backwards.
+    let mut last_correct_blocks = (MiniblockNumber(0), L1BatchNumber(0));
+    while let Some((miniblock, l1_batch)) = block_update_receiver.recv().await {
+        assert!(miniblock >= last_correct_blocks.0, "{miniblock:?}");
+        assert!(l1_batch >= last_correct_blocks.1, "{l1_batch:?}");
+        last_correct_blocks = (miniblock, l1_batch);
+        if last_correct_blocks == (MiniblockNumber(10), L1BatchNumber(10)) {
+            break;
+        }
+    }
+
+    // Emulate a reorg starting from L1 batch #12.
+    for number in 11..=12 {
+        store_miniblock(&mut storage, number, H256::from_low_u64_be(number.into())).await;
+        let l1_batch_hash = if number == 12 {
+            H256::repeat_byte(0xff)
+        } else {
+            H256::repeat_byte(number as u8)
+        };
+        seal_l1_batch(&mut storage, number, l1_batch_hash).await;
+    }
     let task_result = detector_task.await.unwrap();
-    let last_correct_l1_batch = task_result.unwrap();
-    assert_eq!(last_correct_l1_batch, Some(L1BatchNumber(2)));
+    assert_eq!(task_result.unwrap(), Some(L1BatchNumber(11)));
 }
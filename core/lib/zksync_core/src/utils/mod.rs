@@ -38,19 +38,57 @@ where
 
 /// Finds the greatest `u32` value for which `f` returns `true`.
 pub(crate) async fn binary_search_with<P: BinarySearchPredicate>(
+    left: u32,
+    right: u32,
+    predicate: P,
+) -> Result<u32, P::Error> {
+    search_with(left, right, |left, right| (left + right) / 2, predicate).await
+}
+
+/// Generalization of [`binary_search_with()`] in which probed values are chosen by `select_probe`
+/// based on the current exclusive search bounds `(left, right)`. Probes outside the bounds are clamped.
+pub(crate) async fn search_with<P: BinarySearchPredicate>(
+    left: u32,
+    right: u32,
+    select_probe: impl FnMut(u32, u32) -> u32 + Send,
+    predicate: P,
+) -> Result<u32, P::Error> {
+    let outcome = search_with_cancellation(left, right, select_probe, predicate, || false).await?;
+    match outcome {
+        SearchOutcome::Complete(value) => Ok(value),
+        SearchOutcome::Cancelled { .. } => unreachable!("search cannot be cancelled"),
+    }
+}
+
+/// Outcome of [`search_with_cancellation()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SearchOutcome {
+    /// Search has completed with the specified value.
+    Complete(u32),
+    /// Search was cancelled; contains the exclusive search bounds at the moment of cancellation.
+    Cancelled { left: u32, right: u32 },
+}
+
+/// Cancellable version of [`search_with()`]. `is_cancelled` is checked before each probe.
+pub(crate) async fn search_with_cancellation<P: BinarySearchPredicate>(
     mut left: u32,
     mut right: u32,
+    mut select_probe: impl FnMut(u32, u32) -> u32 + Send,
     mut predicate: P,
-) -> Result<u32, P::Error> {
+    is_cancelled: impl Fn() -> bool + Send,
+) -> Result<SearchOutcome, P::Error> {
     while left + 1 < right {
-        let middle = (left + right) / 2;
-        if predicate.eval(middle).await? {
-            left = middle;
+        if is_cancelled() {
+            return Ok(SearchOutcome::Cancelled { left, right });
+        }
+        let probe = select_probe(left, right).clamp(left + 1, right - 1);
+        if predicate.eval(probe).await? {
+            left = probe;
         } else {
-            right = middle;
+            right = probe;
         }
     }
-    Ok(left)
+    Ok(SearchOutcome::Complete(left))
 }
 
 /// Repeatedly polls the DB until there is an L1 batch. We may not have such a batch initially